@@ -0,0 +1,125 @@
+use std::str::FromStr;
+
+use cesrox::{
+    derivation_code::DerivationCode,
+    encode::Encode,
+    error::Error,
+    parse_all, parse_all_ref,
+    primitives::codes::{
+        attached_signature_code::AttachedSignatureCode, basic, label::LabelCode,
+        number::NumberCode, rand_128::Rand128Code, seed::SeedCode, self_addressing, self_signing,
+        special::SpecialCode, timestamp::TimestampCode, PrimitiveCode, TagCode,
+    },
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+const EVENTS: usize = 1000;
+
+const KEL: &str = r#"{"v":"KERI10JSON000188_","t":"icp","d":"EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg","i":"EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg","s":"0","kt":"1","k":["DA4cgeFcpglZf6fQ7u1j8fMs7GbkOQBzVHhBJlaHQLC9"],"nt":"1","n":["EJMujtnS0x3RGp_kHC2bh3p6cAz_4nKp6E3Yrj2u-Lsh"],"bt":"2","b":["BJq7UABlttINuWJh1Xl2lkqZG4NTdUdqnbFJDa6ZyxCC","BDg1zxxf8u4Hx5IPraZzmStfSCZFZbDzMHjqVcFW5OfP"],"c":[],"a":[]}-KABAADZCv1YufmwIvFbzC9jNoVZx2ZgOF8hzrxcuP9vlhJ0tNAYIvNEh0yKIGtkk1bIhrLIAEScbBmxxPosX-rGSAsD-MABBDg1zxxf8u4Hx5IPraZzmStfSCZFZbDzMHjqVcFW5OfP0BCQwOrc3LZqdYs8OEKhQlP4LpB9AqCVpwyGHCB1nfjrBjSYiWtlcvSYI5Vugh3H3rh0gfDqGHUfRKEQrIXKTWAC-MABBJq7UABlttINuWJh1Xl2lkqZG4NTdUdqnbFJDa6ZyxCC0BCO8ycCB9reZHhv7wT4yEAy-q_IFbCA29ttaU3IcQ1tZAIGNKYNkZMY9EjGfRsq8shizeURuoxdYoRXGscQFVQM{"v":"KERI10JSON000160_","t":"rot","d":"EO3KriXb_p3p4dWuG87UIILNR5CsqNClvuc08oRWaAl5","i":"EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg","s":"1","p":"EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg","kt":"1","k":["BMUt1GfFIZXF_2dI1AGBEdmHjMDsSQOGSORU3igbzSvD"],"nt":"1","n":["ELSdoQmwS1FA2p0d1rlabH8nogFS_-ehA1D45kAmYkkJ"],"bt":"1","br":[],"ba":[],"a":[]}-KABAABXD4O4zkPCDSSTUPCVfFy3fFN4ycOKfUoGd-WOXHflJIGaU137PE6ututuwU8xClsES5ByLw8ytvZw4I1mXRgL-MABBDg1zxxf8u4Hx5IPraZzmStfSCZFZbDzMHjqVcFW5OfP0BCVRmDSy-EvjDxhQXJuUgWw_XhKZ2hxQxsDMxcz9K67Lqy3g9kGevXhlP3bAbmRZ6dmWiyoA_3rYG20LJX7CA4K-MABBJq7UABlttINuWJh1Xl2lkqZG4NTdUdqnbFJDa6ZyxCC0BDLp2_wVt_GWUNSm9BDizNWgyGCPnSXdiM5tObP3dze5ah1Me-laex_xFDozxq5beWT3XZf56pYYsdjUYv_iFsA"#;
+
+/// Primitives of KEL events: identifiers, keys, digests, sequence numbers and
+/// signatures.
+const KEL_PRIMITIVES: [&str; 9] = [
+    "EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg",
+    "0AAAAAAAAAAAAAAAAAAAAAAB",
+    "DA4cgeFcpglZf6fQ7u1j8fMs7GbkOQBzVHhBJlaHQLC9",
+    "EJMujtnS0x3RGp_kHC2bh3p6cAz_4nKp6E3Yrj2u-Lsh",
+    "BJq7UABlttINuWJh1Xl2lkqZG4NTdUdqnbFJDa6ZyxCC",
+    "BDg1zxxf8u4Hx5IPraZzmStfSCZFZbDzMHjqVcFW5OfP",
+    "0BCQwOrc3LZqdYs8OEKhQlP4LpB9AqCVpwyGHCB1nfjrBjSYiWtlcvSYI5Vugh3H3rh0gfDqGHUfRKEQrIXKTWAC",
+    "1AAG2020-08-22T17c50c09d988921p00c00",
+    "0BCO8ycCB9reZHhv7wT4yEAy-q_IFbCA29ttaU3IcQ1tZAIGNKYNkZMY9EjGfRsq8shizeURuoxdYoRXGscQFVQM",
+];
+
+/// Code parsing as it was done before the master code table: parsers of all
+/// codes are tried in turn.
+fn sequential_from_str(s: &str) -> Result<PrimitiveCode, Error> {
+    use PrimitiveCode::*;
+
+    type ParserFn = fn(&str) -> Result<PrimitiveCode, Error>;
+
+    let parsers: &[ParserFn] = &[
+        |s| Rand128Code::from_str(s).map(Random),
+        |s| SeedCode::from_str(s).map(Seed),
+        |s| AttachedSignatureCode::from_str(s).map(IndexedSignature),
+        |s| basic::Basic::from_str(s).map(Basic),
+        |s| self_addressing::SelfAddressing::from_str(s).map(SelfAddressing),
+        |s| self_signing::SelfSigning::from_str(s).map(SelfSigning),
+        |s| Rand128Code::from_str(s).map(SerialNumber),
+        |s| SeedCode::from_str(s).map(Seed),
+        |s| TimestampCode::from_str(s).map(Timestamp),
+        |s| TagCode::from_str(s).map(Tag),
+        |s| NumberCode::from_str(s).map(Number),
+        |s| LabelCode::from_str(s).map(Label),
+        |s| SpecialCode::from_str(s).map(Special),
+    ];
+
+    for parser in parsers {
+        if let Ok(code) = parser(s) {
+            return Ok(code);
+        }
+    }
+
+    Err(Error::UnknownCodeError)
+}
+
+/// Resolves codes of all primitives, returning their total size.
+fn resolve_codes(primitives: &[&str], from_str: fn(&str) -> Result<PrimitiveCode, Error>) -> usize {
+    primitives
+        .iter()
+        .map(|primitive| from_str(primitive).unwrap().full_size())
+        .sum()
+}
+
+fn bench_code_lookup(c: &mut Criterion) {
+    let primitives: Vec<&str> = KEL_PRIMITIVES.repeat(EVENTS);
+    let size: usize = primitives.iter().map(|primitive| primitive.len()).sum();
+    let mut group = c.benchmark_group("code_lookup");
+    group.throughput(Throughput::Bytes(size as u64));
+    group.bench_function("table", |b| {
+        b.iter(|| resolve_codes(black_box(&primitives), PrimitiveCode::from_str))
+    });
+    group.bench_function("sequential", |b| {
+        b.iter(|| resolve_codes(black_box(&primitives), sequential_from_str))
+    });
+    group.finish();
+}
+
+fn bench_kel_stream(c: &mut Criterion) {
+    let stream = KEL.repeat(EVENTS);
+    let mut group = c.benchmark_group("kel_stream");
+    group.throughput(Throughput::Bytes(stream.len() as u64));
+    group.bench_function("parse_all", |b| {
+        b.iter(|| parse_all(black_box(&stream)).unwrap())
+    });
+    group.bench_function("parse_all_ref", |b| {
+        b.iter(|| parse_all_ref(black_box(&stream)).unwrap())
+    });
+    #[cfg(feature = "parallel")]
+    group.bench_function("parse_all_par", |b| {
+        b.iter(|| cesrox::parse_all_par(black_box(&stream)).unwrap())
+    });
+    let (_, values) = parse_all(&stream).unwrap();
+    group.bench_function("to_string", |b| {
+        b.iter(|| {
+            black_box(&values)
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<String>()
+        })
+    });
+    group.bench_function("write_cesr", |b| {
+        b.iter(|| {
+            let values = black_box(&values);
+            let mut text = String::with_capacity(values.iter().map(Encode::encoded_len).sum());
+            for value in values {
+                value.write_cesr(&mut text).unwrap();
+            }
+            text
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_code_lookup, bench_kel_stream);
+criterion_main!(benches);
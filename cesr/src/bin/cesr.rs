@@ -0,0 +1,91 @@
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+use cesrox::{
+    catalog::{all_codes, to_json},
+    transcode::{transcode, CesrVersion},
+};
+use clap::{Arg, Command};
+use std::{
+    fs,
+    io::{self, Read},
+    process::exit,
+    str::FromStr,
+};
+
+fn main() {
+    let matches = Command::new("CESR")
+        .version(VERSION)
+        .subcommand(
+            Command::new("transcode")
+                .about("Convert stream between CESR 1.0 and CESR 2.0 code tables")
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .num_args(1)
+                        .required(true)
+                        .value_parser(["1", "2"])
+                        .help("CESR version of source stream"),
+                )
+                .arg(
+                    Arg::new("to")
+                        .long("to")
+                        .num_args(1)
+                        .required(true)
+                        .value_parser(["1", "2"])
+                        .help("CESR version of output stream"),
+                )
+                .arg(
+                    Arg::new("data")
+                        .short('d')
+                        .long("data")
+                        .num_args(1)
+                        .conflicts_with("file")
+                        .help("Source stream. Read from standard input if neither data nor file is provided"),
+                )
+                .arg(
+                    Arg::new("file")
+                        .short('f')
+                        .long("file")
+                        .num_args(1)
+                        .help("File from which source stream is read"),
+                ),
+        )
+        .subcommand(
+            Command::new("codes")
+                .about("Print primitive and counter codes with their sizes, as JSON"),
+        )
+        .get_matches();
+
+    if let Some(matches) = matches.subcommand_matches("transcode") {
+        let from = CesrVersion::from_str(matches.get_one::<String>("from").unwrap()).unwrap();
+        let to = CesrVersion::from_str(matches.get_one::<String>("to").unwrap()).unwrap();
+
+        let stream = if let Some(data) = matches.get_one::<String>("data") {
+            data.clone()
+        } else if let Some(path) = matches.get_one::<String>("file") {
+            fs::read_to_string(path).unwrap_or_else(|e| {
+                eprintln!("Can't read {}: {}", path, e);
+                exit(1)
+            })
+        } else {
+            let mut data = String::new();
+            io::stdin().read_to_string(&mut data).unwrap_or_else(|e| {
+                eprintln!("Can't read standard input: {}", e);
+                exit(1)
+            });
+            data
+        };
+
+        match transcode(stream.trim_end(), from, to) {
+            Ok(transcoded) => println!("{}", transcoded),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1)
+            }
+        }
+    }
+
+    if matches.subcommand_matches("codes").is_some() {
+        println!("{}", to_json(&all_codes()));
+    }
+}
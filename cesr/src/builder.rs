@@ -0,0 +1,447 @@
+//! Building of CESR streams from payloads and their attachments. Counts of
+//! groups are computed while stream is encoded, so groups can be built item
+//! by item.
+
+use std::mem;
+
+use crate::{
+    conversion::from_text_to_bytes,
+    error::Error,
+    genus::CodeTable,
+    group::{
+        codes::{write_counter_code, BIG_COUNT_MAX, SMALL_COUNT_MAX},
+        Group,
+    },
+    payload::Payload,
+    primitives::{Digest, Identifier, IndexedSignature, PublicKey, Signature},
+    seal::{DigestSeal, EventSeal},
+    value::Value,
+};
+
+/// Payload with its attachments.
+#[derive(Debug, Clone, Default)]
+struct Message {
+    payload: Option<Payload>,
+    attachments: Vec<Value>,
+}
+
+/// Builder of CESR stream. Payloads start messages, and attachments are
+/// added to the last message. Attachments added before any payload make a
+/// message of their own. Consecutive items of the same group, e.g.
+/// controller signatures, are collected into one group.
+#[derive(Debug, Clone, Default)]
+pub struct StreamBuilder {
+    messages: Vec<Message>,
+    table: Option<CodeTable>,
+    attachment_groups: bool,
+    pipeline: bool,
+}
+
+/// Domain that stream is encoded in: Base64 text, where counts are in
+/// quadlets, or binary, where counts are in triplets.
+#[derive(Clone, Copy)]
+enum Domain {
+    Text,
+    Binary,
+}
+
+impl Domain {
+    fn unit(self) -> usize {
+        match self {
+            Domain::Text => 4,
+            Domain::Binary => 3,
+        }
+    }
+
+    /// Converts Base64 text, aligned to quadlets, into the domain.
+    fn convert(self, text: String) -> Result<Vec<u8>, Error> {
+        match self {
+            Domain::Text => Ok(text.into_bytes()),
+            Domain::Binary => from_text_to_bytes(&text),
+        }
+    }
+}
+
+impl StreamBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encodes groups with counters of given code table, counting their
+    /// content the way the table does. Native counters are used by default.
+    pub fn with_table(mut self, table: CodeTable) -> Self {
+        self.table = Some(table);
+        self
+    }
+
+    /// Frames attachments of each message in attachment group, `-C`.
+    pub fn attachment_groups(mut self, attachment_groups: bool) -> Self {
+        self.attachment_groups = attachment_groups;
+        self
+    }
+
+    /// Frames the whole stream in generic pipeline group, `-A`.
+    pub fn pipeline(mut self, pipeline: bool) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Starts new message with payload.
+    pub fn payload(mut self, payload: Payload) -> Self {
+        self.messages.push(Message {
+            payload: Some(payload),
+            attachments: vec![],
+        });
+        self
+    }
+
+    /// Attaches value to the last message.
+    pub fn value(mut self, value: Value) -> Self {
+        self.attachments().push(value);
+        self
+    }
+
+    /// Attaches group to the last message.
+    pub fn group(self, group: Group) -> Self {
+        self.value(Value::SpecificGroup(group))
+    }
+
+    pub fn controller_signature(mut self, signature: IndexedSignature) -> Self {
+        match self.last_group() {
+            Some(Group::IndexedControllerSignatures(signatures)) => signatures.push(signature),
+            _ => return self.group(Group::IndexedControllerSignatures(vec![signature])),
+        }
+        self
+    }
+
+    pub fn witness_signature(mut self, signature: IndexedSignature) -> Self {
+        match self.last_group() {
+            Some(Group::IndexedWitnessSignatures(signatures)) => signatures.push(signature),
+            _ => return self.group(Group::IndexedWitnessSignatures(vec![signature])),
+        }
+        self
+    }
+
+    /// Attaches receipt of nontransferable identifier, e.g. witness.
+    pub fn witness_receipt(mut self, key: PublicKey, signature: Signature) -> Self {
+        match self.last_group() {
+            Some(Group::NontransReceiptCouples(couples)) => couples.push((key, signature)),
+            _ => return self.group(Group::NontransReceiptCouples(vec![(key, signature)])),
+        }
+        self
+    }
+
+    /// Attaches receipt of transferable identifier, signed with key of its
+    /// establishment event of given serial number and digest.
+    pub fn validator_receipt(
+        mut self,
+        identifier: Identifier,
+        sn: u64,
+        digest: Digest,
+        signature: IndexedSignature,
+    ) -> Self {
+        let quadruple = (identifier, sn, digest, signature);
+        match self.last_group() {
+            Some(Group::TransReceiptQuadruples(quadruples)) => quadruples.push(quadruple),
+            _ => return self.group(Group::TransReceiptQuadruples(vec![quadruple])),
+        }
+        self
+    }
+
+    pub fn digest_seal(mut self, seal: DigestSeal) -> Self {
+        match self.last_group() {
+            Some(Group::DigestSeals(seals)) => seals.push(seal),
+            _ => return self.group(Group::DigestSeals(vec![seal])),
+        }
+        self
+    }
+
+    pub fn anchoring_seal(mut self, seal: EventSeal) -> Self {
+        match self.last_group() {
+            Some(Group::AnchoringSeals(seals)) => seals.push(seal),
+            _ => return self.group(Group::AnchoringSeals(vec![seal])),
+        }
+        self
+    }
+
+    /// Encodes stream as text. Fails if payload isn't valid UTF-8, if framed
+    /// content isn't aligned to quadlets, or if count doesn't fit into its
+    /// counter.
+    pub fn build(&self) -> Result<String, Error> {
+        let bytes = self.encode(Domain::Text)?;
+        String::from_utf8(bytes).map_err(|_| Error::Utf8DecodingError)
+    }
+
+    /// Encodes stream in binary domain: payloads as they are and Base64
+    /// values and counters as their qb2 bytes. Framed content has to be
+    /// aligned to triplets.
+    pub fn build_qb2(&self) -> Result<Vec<u8>, Error> {
+        self.encode(Domain::Binary)
+    }
+
+    /// Returns attachments of the last message.
+    fn attachments(&mut self) -> &mut Vec<Value> {
+        if self.messages.is_empty() {
+            self.messages.push(Message::default());
+        }
+        let last = self.messages.len() - 1;
+        &mut self.messages[last].attachments
+    }
+
+    fn last_group(&mut self) -> Option<&mut Group> {
+        match self.messages.last_mut()?.attachments.last_mut()? {
+            Value::SpecificGroup(group) => Some(group),
+            _ => None,
+        }
+    }
+
+    fn encode(&self, domain: Domain) -> Result<Vec<u8>, Error> {
+        let mut stream = vec![];
+        for message in &self.messages {
+            if let Some(payload) = &message.payload {
+                if let Domain::Text = domain {
+                    std::str::from_utf8(payload.as_bytes())
+                        .map_err(|_| Error::Utf8DecodingError)?;
+                }
+                stream.extend_from_slice(payload.as_bytes());
+            }
+            let mut text = String::new();
+            for value in &message.attachments {
+                value.write_with(self.table.as_ref(), &mut text)?;
+            }
+            if !text.len().is_multiple_of(4) {
+                return Err(Error::MisalignedTextError(text));
+            }
+            let attachments = domain.convert(text)?;
+            if self.attachment_groups && !attachments.is_empty() {
+                let counter = match &self.table {
+                    Some(table) if !table.has_universal_codes() => table
+                        .attachment_counter()
+                        .ok_or_else(|| self.no_equivalent("-C"))?,
+                    _ => "-C",
+                };
+                stream.append(&mut self.frame(counter, attachments, domain)?);
+            } else {
+                stream.extend(attachments);
+            }
+        }
+        if self.pipeline {
+            if self
+                .table
+                .as_ref()
+                .is_some_and(|t| !t.has_universal_codes())
+            {
+                return Err(self.no_equivalent("-A"));
+            }
+            stream = self.frame("-A", mem::take(&mut stream), domain)?;
+        }
+        Ok(stream)
+    }
+
+    /// Prepends counter of content size to content. Counter takes big form,
+    /// with `--` selector and five chars count, if size doesn't fit into two
+    /// chars count. Tables without universal codes have no big counters, so
+    /// such content can't be framed with them.
+    fn frame(&self, code: &str, content: Vec<u8>, domain: Domain) -> Result<Vec<u8>, Error> {
+        if !content.len().is_multiple_of(domain.unit()) {
+            return Err(Error::MisalignedTextError(format!(
+                "{} bytes framed by {}",
+                content.len(),
+                code
+            )));
+        }
+        let count = content.len() / domain.unit();
+        let big_allowed = self
+            .table
+            .as_ref()
+            .is_none_or(CodeTable::has_universal_codes);
+        if count > BIG_COUNT_MAX || (count > SMALL_COUNT_MAX && !big_allowed) {
+            return Err(Error::NumberOverflowError(format!("{} count", code)));
+        }
+        let mut counter = String::with_capacity(8);
+        write_counter_code(code, count as u64, &mut counter)?;
+        let mut stream = domain.convert(counter)?;
+        stream.extend(content);
+        Ok(stream)
+    }
+
+    fn no_equivalent(&self, code: &str) -> Error {
+        Error::NoEquivalentCodeError {
+            code: code.to_string(),
+            table: self.table.as_ref().map_or("", |t| t.name()).to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        builder::StreamBuilder,
+        config::{ParserConfig, ParserLimits},
+        conversion::from_text_to_bytes,
+        error::Error,
+        genus::CodeTable,
+        group::Group,
+        parse_all, parse_all_with,
+        payload::Payload,
+        primitives::{IndexedSignature, PublicKey, Signature},
+        universal_codes::UniversalGroupCode,
+        value::Value,
+    };
+
+    const EVENT: &str = r#"{"v":"KERI10JSON000188_","t":"icp","d":"EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg","i":"EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg","s":"0","kt":"1","k":["DA4cgeFcpglZf6fQ7u1j8fMs7GbkOQBzVHhBJlaHQLC9"],"nt":"1","n":["EJMujtnS0x3RGp_kHC2bh3p6cAz_4nKp6E3Yrj2u-Lsh"],"bt":"2","b":["BJq7UABlttINuWJh1Xl2lkqZG4NTdUdqnbFJDa6ZyxCC","BDg1zxxf8u4Hx5IPraZzmStfSCZFZbDzMHjqVcFW5OfP"],"c":[],"a":[]}"#;
+    const ATTACHMENTS: &str = "-KABAADZCv1YufmwIvFbzC9jNoVZx2ZgOF8hzrxcuP9vlhJ0tNAYIvNEh0yKIGtkk1bIhrLIAEScbBmxxPosX-rGSAsD-MABBDg1zxxf8u4Hx5IPraZzmStfSCZFZbDzMHjqVcFW5OfP0BCQwOrc3LZqdYs8OEKhQlP4LpB9AqCVpwyGHCB1nfjrBjSYiWtlcvSYI5Vugh3H3rh0gfDqGHUfRKEQrIXKTWAC";
+
+    fn signature() -> IndexedSignature {
+        let (_, values) = parse_all(ATTACHMENTS).unwrap();
+        let Value::SpecificGroup(Group::IndexedControllerSignatures(signatures)) = &values[0]
+        else {
+            panic!("Unexpected value: {:?}", values[0]);
+        };
+        signatures[0].clone()
+    }
+
+    fn receipt() -> (PublicKey, Signature) {
+        let (_, values) = parse_all(ATTACHMENTS).unwrap();
+        let Value::SpecificGroup(Group::NontransReceiptCouples(couples)) = &values[1] else {
+            panic!("Unexpected value: {:?}", values[1]);
+        };
+        couples[0].clone()
+    }
+
+    fn builder() -> StreamBuilder {
+        let (key, receipt) = receipt();
+        StreamBuilder::new()
+            .payload(Payload::JSON(EVENT.as_bytes().to_vec()))
+            .controller_signature(signature())
+            .witness_receipt(key, receipt)
+    }
+
+    #[test]
+    fn test_build() {
+        assert_eq!(builder().build().unwrap(), [EVENT, ATTACHMENTS].concat());
+
+        // Consecutive items are counted in one group
+        let stream = builder()
+            .controller_signature(signature())
+            .controller_signature(signature())
+            .build()
+            .unwrap();
+        let (rest, values) = parse_all(&stream).unwrap();
+        assert!(rest.is_empty());
+        assert!(matches!(
+            &values[3],
+            Value::SpecificGroup(Group::IndexedControllerSignatures(signatures))
+                if signatures.len() == 2
+        ));
+
+        // 57 quadlets of attachments
+        let stream = builder().attachment_groups(true).build().unwrap();
+        assert_eq!(stream, [EVENT, "-CA5", ATTACHMENTS].concat());
+        let (rest, values) = parse_all(&stream).unwrap();
+        assert!(rest.is_empty());
+        assert!(matches!(values[1], Value::UniversalGroup(_, _)));
+
+        // Attachments of CESR 1.0 are framed with its own counter
+        let stream = builder()
+            .attachment_groups(true)
+            .with_table(CodeTable::keri_v1())
+            .build()
+            .unwrap();
+        assert!(stream[EVENT.len()..].starts_with("-VA5-AAB"));
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let attachments = StreamBuilder::new()
+            .controller_signature(signature())
+            .pipeline(true);
+        let stream = attachments.build().unwrap();
+        let (rest, values) = parse_all(&stream).unwrap();
+        assert!(rest.is_empty());
+        assert!(stream.starts_with("-AAX"));
+        assert_eq!(values.len(), 1);
+
+        let qb2 = attachments.build_qb2().unwrap();
+        assert_eq!(qb2, from_text_to_bytes(&stream).unwrap());
+
+        // Messages in pipeline are counted too
+        let stream = builder().pipeline(true).build().unwrap();
+        assert_eq!(stream, ["-ACb", EVENT, ATTACHMENTS].concat());
+        let misaligned = StreamBuilder::new()
+            .payload(Payload::JSON(br#"{"a":1}"#.to_vec()))
+            .pipeline(true);
+        assert!(matches!(
+            misaligned.build(),
+            Err(Error::MisalignedTextError(_))
+        ));
+        assert!(matches!(
+            builder()
+                .pipeline(true)
+                .with_table(CodeTable::keri_v1())
+                .build(),
+            Err(Error::NoEquivalentCodeError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_big_counter() {
+        let (key, receipt) = receipt();
+        let stream = (0..1100)
+            .fold(StreamBuilder::new(), |builder, _| {
+                builder.witness_receipt(key.clone(), receipt.clone())
+            })
+            .attachment_groups(true)
+            .build()
+            .unwrap();
+        // Counter and 1100 couples of 33 quadlets each
+        assert!(stream.starts_with("--CAAI3N-MRM"));
+        assert_eq!(stream.len(), 8 + 4 + 1100 * 132);
+
+        let (rest, values) = parse_all(&stream).unwrap();
+        assert!(rest.is_empty());
+        assert!(matches!(
+            &values[0],
+            Value::UniversalGroup(UniversalGroupCode::OverrideAllowed { quadlets: 36301, .. }, values)
+                if values.len() == 1
+        ));
+
+        // CESR 1.0 has no big counters
+        let v1 = (0..1100)
+            .fold(StreamBuilder::new(), |builder, _| {
+                builder.witness_receipt(key.clone(), receipt.clone())
+            })
+            .attachment_groups(true)
+            .with_table(CodeTable::keri_v1());
+        assert!(matches!(v1.build(), Err(Error::NumberOverflowError(_))));
+
+        // Count of 4096 signatures doesn't fit into small counter either
+        let signatures = (0..4096).fold(StreamBuilder::new(), |builder, _| {
+            builder.controller_signature(signature())
+        });
+        let stream = signatures.build().unwrap();
+        assert!(stream.starts_with("--KAABAA"));
+        let config = ParserConfig::default().with_limits(ParserLimits::unlimited());
+        let (rest, values) = parse_all_with(&stream, &config).unwrap();
+        assert!(rest.is_empty());
+        assert!(matches!(
+            &values[0],
+            Value::SpecificGroup(Group::IndexedControllerSignatures(signatures))
+                if signatures.len() == 4096
+        ));
+        assert!(matches!(
+            signatures.with_table(CodeTable::keri_v1()).build(),
+            Err(Error::NumberOverflowError(_))
+        ));
+    }
+
+    #[test]
+    fn test_qb2() {
+        let qb2 = builder().build_qb2().unwrap();
+        assert_eq!(&qb2[..EVENT.len()], EVENT.as_bytes());
+        assert_eq!(
+            &qb2[EVENT.len()..],
+            from_text_to_bytes(ATTACHMENTS).unwrap()
+        );
+    }
+}
@@ -0,0 +1,336 @@
+//! Catalog of codes this crate parses, with their sizes, for tooling and
+//! documentation. Primitive codes are shared by all genera, counters are
+//! listed for each code table of genus registry.
+
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::{
+    derivation_code::DerivationCode,
+    genus::{CountKind, GenusRegistry},
+    group::codes::GroupCode,
+    primitives::codes::{
+        attached_signature_code::AttachedSignatureCode,
+        table::{CodeKind, MASTER_CODES},
+    },
+};
+
+/// What code stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CodeCategory {
+    /// Fixed size primitive of master code table.
+    Primitive,
+    /// Indexed signature of indexed code table.
+    IndexedSignature,
+    /// Primitive which size is given in soft part of code.
+    VariableLength,
+    /// Counter of group specific to genus.
+    Counter,
+    /// Universal count code, common to genera.
+    UniversalCounter,
+    /// Genus version code.
+    GenusVersion,
+}
+
+/// Code with its metadata. Sizes of code parts and of full primitive are in
+/// chars, size of raw value in bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CodeInfo {
+    /// Hard part of code.
+    pub code: String,
+    /// Name of code in CESR code tables for primitives, name of group for
+    /// counters.
+    pub name: String,
+    pub category: CodeCategory,
+    /// Kind of primitive, for codes of master code table.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<CodeKind>,
+    pub hard: usize,
+    pub soft: usize,
+    /// Raw value size, for fixed size primitives.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<usize>,
+    /// Size of code with value, for fixed size primitives.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub full: Option<usize>,
+    /// What count of counter means.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<CountKind>,
+    /// Genus of code table, without `-_` selector. `None` for codes of all
+    /// genera, and for counters read when no genus is selected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genus: Option<String>,
+    /// Major version of genus.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u16>,
+}
+
+impl CodeInfo {
+    fn new(code: &str, name: &str, category: CodeCategory, hard: usize, soft: usize) -> Self {
+        Self {
+            code: code.to_string(),
+            name: name.to_string(),
+            category,
+            kind: None,
+            hard,
+            soft,
+            raw: None,
+            full: None,
+            count: None,
+            genus: None,
+            version: None,
+        }
+    }
+}
+
+/// Indexed signature codes with their names. Ed448 codes of big indexes
+/// aren't parsed by this crate, so they're left out.
+const INDEXED_CODES: [(&str, &str); 10] = [
+    ("A", "Ed25519_Sig"),
+    ("B", "Ed25519_Crt_Sig"),
+    ("C", "ECDSA_256k1_Sig"),
+    ("D", "ECDSA_256k1_Crt_Sig"),
+    ("0A", "Ed448_Sig"),
+    ("0B", "Ed448_Crt_Sig"),
+    ("2A", "Ed25519_Big_Sig"),
+    ("2B", "Ed25519_Big_Crt_Sig"),
+    ("2C", "ECDSA_256k1_Big_Sig"),
+    ("2D", "ECDSA_256k1_Big_Crt_Sig"),
+];
+
+/// Variable length codes by lead bytes, without selector, with their names.
+const VARIABLE_LENGTH_CODES: [(&str, &str); 4] = [
+    ("A", "StrB64"),
+    ("B", "Bytes"),
+    ("F", "HPKEBase_Cipher"),
+    ("G", "HPKEAuth_Cipher"),
+];
+
+/// Returns all primitive codes: fixed size codes of master code table,
+/// indexed signature codes and variable length codes.
+pub fn primitive_codes() -> Vec<CodeInfo> {
+    let master = MASTER_CODES.iter().map(|entry| CodeInfo {
+        kind: Some(entry.kind),
+        raw: Some(entry.raw_size()),
+        full: Some(entry.full),
+        ..CodeInfo::new(
+            entry.code,
+            entry.name,
+            CodeCategory::Primitive,
+            entry.hard(),
+            entry.soft,
+        )
+    });
+    let indexed = INDEXED_CODES.iter().map(|(code, name)| {
+        // Sizes are taken from code of zero indexes
+        let sample = AttachedSignatureCode::from_str(&format!("{:A<10}", code))
+            .expect("indexed code is parsed");
+        let lead = sample.code_size() % 4;
+        CodeInfo {
+            raw: Some((sample.value_size() + lead) / 4 * 3 - lead),
+            full: Some(sample.full_size()),
+            ..CodeInfo::new(
+                code,
+                name,
+                CodeCategory::IndexedSignature,
+                sample.hard_size(),
+                sample.soft_size(),
+            )
+        }
+    });
+    let variable = ["4", "5", "6", "7", "8", "9"]
+        .into_iter()
+        .flat_map(|selector| {
+            let big = selector > "6";
+            let lead = (selector.as_bytes()[0] - b'4') % 3;
+            VARIABLE_LENGTH_CODES.iter().map(move |(code, name)| {
+                let (code, name) = if big {
+                    (
+                        [selector, "AA", code].concat(),
+                        format!("{}_Big_L{}", name, lead),
+                    )
+                } else {
+                    ([selector, code].concat(), format!("{}_L{}", name, lead))
+                };
+                let size = code.len();
+                CodeInfo::new(&code, &name, CodeCategory::VariableLength, size, size)
+            })
+        });
+    master.chain(indexed).chain(variable).collect()
+}
+
+/// Returns counter codes: universal count codes, counters read when no genus
+/// is selected, genus version codes of registered genera and counters of
+/// their code tables.
+pub fn counter_codes(genera: &GenusRegistry) -> Vec<CodeInfo> {
+    let universal = [
+        ("-A", "GenericGroup"),
+        ("-C", "AttachmentGroup"),
+        ("-E", "ESSRWrapperGroup"),
+    ]
+    .map(|(code, name)| CodeInfo {
+        count: Some(CountKind::Quadlets),
+        ..CodeInfo::new(code, name, CodeCategory::UniversalCounter, 2, 2)
+    });
+    let counter = |code: &str, name: &str, kind| CodeInfo {
+        count: Some(kind),
+        ..CodeInfo::new(code, name, CodeCategory::Counter, 2, 2)
+    };
+    let mut codes: Vec<_> = universal.into_iter().collect();
+    codes.extend(native_counters().map(|group| {
+        let code = group.to_str();
+        counter(&code[..2], group.name(), CountKind::Items)
+    }));
+    for (genus, major, table) in genera.tables() {
+        let in_genus = |info: CodeInfo| CodeInfo {
+            genus: Some(genus.to_string()),
+            version: Some(major),
+            ..info
+        };
+        let code = ["-_", genus].concat();
+        codes.push(in_genus(CodeInfo::new(
+            &code,
+            table.name(),
+            CodeCategory::GenusVersion,
+            5,
+            3,
+        )));
+        codes.extend(
+            table
+                .counters()
+                .map(|(code, group, kind)| in_genus(counter(code, group.name(), kind))),
+        );
+        if let Some(code) = table.attachment_counter() {
+            let attachments = counter(code, "AttachmentGroup", CountKind::Quadlets);
+            codes.push(in_genus(attachments));
+        }
+    }
+    codes
+}
+
+/// Groups of native code table, in order of their counters.
+fn native_counters() -> impl Iterator<Item = GroupCode> {
+    let selectors = ('A'..='Z').chain('a'..='z');
+    selectors.filter_map(|c| GroupCode::from_str(&format!("-{}AA", c)).ok())
+}
+
+/// Returns primitive and counter codes, with counters of built-in genera.
+pub fn all_codes() -> Vec<CodeInfo> {
+    let mut codes = primitive_codes();
+    codes.extend(counter_codes(&GenusRegistry::default()));
+    codes
+}
+
+/// Exports codes to JSON array of code objects.
+pub fn to_json(codes: &[CodeInfo]) -> String {
+    serde_json::to_string_pretty(codes).expect("codes are serialized")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        catalog::{all_codes, counter_codes, primitive_codes, to_json, CodeCategory},
+        config::ParserConfig,
+        encode::Encode,
+        genus::GenusRegistry,
+        primitives::codes::table::CodeKind,
+        universal_codes::GenusCountCode,
+        value::{parse_value, parse_value_with, Value},
+    };
+
+    #[test]
+    fn test_primitive_codes() {
+        let codes = primitive_codes();
+        let e = codes.iter().find(|info| info.code == "E").unwrap();
+        assert_eq!(e.name, "Blake3_256");
+        assert_eq!((e.hard, e.soft, e.raw, e.full), (1, 0, Some(32), Some(44)));
+
+        // Sizes agree with parser
+        for info in &codes {
+            let (Some(full), Some(raw)) = (info.full, info.raw) else {
+                continue;
+            };
+            if info.category != CodeCategory::Primitive || info.kind == Some(CodeKind::Unsupported)
+            {
+                continue;
+            }
+            let lead = (info.hard + info.soft) % 4;
+            assert_eq!((full - info.hard - info.soft + lead) * 3 / 4, raw + lead);
+            // Padded tags start with pad char
+            let fill = if info.kind == Some(CodeKind::Tag) {
+                "_"
+            } else {
+                "A"
+            };
+            let text = [info.code.as_str(), &fill.repeat(full - info.code.len())].concat();
+            let (rest, value) = parse_value(&text).unwrap();
+            assert!(rest.is_empty(), "{}", info.code);
+            assert_eq!(value.encoded_len(), full);
+        }
+        let variable = codes
+            .iter()
+            .filter(|info| info.category == CodeCategory::VariableLength);
+        assert_eq!(variable.count(), 24);
+    }
+
+    #[test]
+    fn test_counter_codes() {
+        let codes = counter_codes(&GenusRegistry::default());
+        let controller_signatures: Vec<_> = codes
+            .iter()
+            .filter(|info| info.name == "IndexedControllerSignatures")
+            .map(|info| (info.code.as_str(), info.genus.as_deref(), info.version))
+            .collect();
+        assert_eq!(
+            controller_signatures,
+            [
+                ("-K", None, None),
+                ("-A", Some("AAA"), Some(1)),
+                ("-K", Some("AAA"), Some(2)),
+                ("-K", Some("AAB"), Some(1)),
+            ]
+        );
+
+        // Counters are known to parser, when their genus is selected
+        for info in codes
+            .iter()
+            .filter(|info| info.category == CodeCategory::Counter)
+        {
+            let config = match (&info.genus, info.version) {
+                (Some(genus), Some(major)) => {
+                    let genus = GenusCountCode::new(genus, major, 0);
+                    ParserConfig::default().with_genus(genus)
+                }
+                _ => ParserConfig::default(),
+            };
+            let config = config.with_passthrough_unknown(true);
+            let counter = [info.code.as_str(), "AA"].concat();
+            let parsed = parse_value_with(&counter, &config);
+            assert!(
+                !matches!(parsed, Ok((_, Value::Unknown { .. }))),
+                "{}",
+                info.code
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_json() {
+        let json: serde_json::Value = serde_json::from_str(&to_json(&all_codes())).unwrap();
+        assert_eq!(
+            json[4],
+            serde_json::json!({
+                "code": "E",
+                "name": "Blake3_256",
+                "category": "primitive",
+                "kind": "self_addressing",
+                "hard": 1,
+                "soft": 0,
+                "raw": 32,
+                "full": 44
+            })
+        );
+    }
+}
@@ -1,8 +1,11 @@
+use std::fmt;
+
 use base64::prelude::*;
 use serde::Deserialize;
 
 use crate::{
     conversion::from_bytes_to_text,
+    encode::Encode,
     variable_length::{
         LeadBytes, SmallVariableLengthCode, VariableLengthCode, VariableLengthPrimitive,
     },
@@ -68,6 +71,23 @@ impl MaterialPath {
     }
 }
 
+impl Encode for MaterialPath {
+    fn encoded_len(&self) -> usize {
+        // Path is always encoded with small code
+        4 + self.base.len()
+    }
+
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        let code = VariableLengthCode::Small {
+            lb: self.lead_bytes(),
+            code: SmallVariableLengthCode::Base64String,
+            length: (self.base.len() / 4) as u16,
+        };
+        code.write_cesr(writer)?;
+        writer.write_str(&self.base)
+    }
+}
+
 #[test]
 pub fn test_path_to_cesr() -> Result<(), Error> {
     assert_eq!(
@@ -101,5 +121,9 @@ pub fn test_path_to_cesr() -> Result<(), Error> {
         "6AAGAAA-field0-field1-field3"
     );
 
+    let path = MaterialPath::create_from_str("-a-b-c".into());
+    assert_eq!(path.to_cesr_string(), path.to_cesr());
+    assert_eq!(path.encoded_len(), path.to_cesr().len());
+
     Ok(())
 }
@@ -0,0 +1,378 @@
+use std::sync::Arc;
+
+use crate::{
+    error::{ParsingError, StreamError, StreamErrorKind},
+    genus::{CodeTable, GenusRegistry},
+    registry::CodeRegistry,
+    universal_codes::GenusCountCode,
+};
+
+/// Default maximum nesting depth of groups. Parser recurses into nested
+/// groups, so depth is limited to keep it within small thread stacks.
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+/// Default maximum number of group items, one more than small counter can
+/// count.
+pub const DEFAULT_MAX_GROUP_ITEMS: usize = 4096;
+
+/// Limits of resources that parsing of untrusted stream may take. `None`
+/// means no limit. Default limits bound nesting depth and number of group
+/// items, see `ParserLimits::unlimited` for parsing without limits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserLimits {
+    /// Maximum nesting depth of groups that contain values, e.g. universal
+    /// groups. Group at the top level of stream has depth 1.
+    pub max_depth: Option<usize>,
+    /// Maximum number of items in group.
+    pub max_group_items: Option<usize>,
+    /// Maximum size of group content, in quadlets.
+    pub max_group_quadlets: Option<usize>,
+    /// Maximum size of payload, in bytes.
+    pub max_payload_size: Option<usize>,
+    /// Maximum size of stream passed to parsing functions, in bytes.
+    pub max_stream_size: Option<usize>,
+}
+
+impl Default for ParserLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            max_group_items: Some(DEFAULT_MAX_GROUP_ITEMS),
+            max_group_quadlets: None,
+            max_payload_size: None,
+            max_stream_size: None,
+        }
+    }
+}
+
+impl ParserLimits {
+    /// No limits at all. Deeply nested stream may then overflow the stack of
+    /// parsing thread, so it's meant only for trusted streams.
+    pub fn unlimited() -> Self {
+        Self {
+            max_depth: None,
+            max_group_items: None,
+            max_group_quadlets: None,
+            max_payload_size: None,
+            max_stream_size: None,
+        }
+    }
+}
+
+/// Parser options. Default configuration fails on any code unknown to this
+/// crate, knows only built-in genera and reads counters with native CESR 2.0
+/// code table. Only canonical text of primitives is accepted, see
+/// `ParserConfig::with_strict`.
+#[derive(Debug, Clone)]
+pub struct ParserConfig {
+    /// Keep unknown codes of determinable size as `Value::Unknown` instead of
+    /// failing, so streams of newer spec versions can be passed through.
+    pub passthrough_unknown: bool,
+    /// Genera accepted in genus version codes.
+    pub genera: Arc<GenusRegistry>,
+    /// Limits of parser resources.
+    pub limits: ParserLimits,
+    /// Primitive and counter codes registered by application.
+    pub codes: Arc<CodeRegistry>,
+    /// Require lead bits of primitive text, that aren't part of code, to be
+    /// zero.
+    pub strict: bool,
+    /// Genus version which code table is used to read counters.
+    genus: Option<GenusCountCode>,
+    /// Nesting depth of parsed group content.
+    depth: usize,
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self {
+            passthrough_unknown: false,
+            genera: Default::default(),
+            limits: Default::default(),
+            codes: Default::default(),
+            strict: true,
+            genus: None,
+            depth: 0,
+        }
+    }
+}
+
+impl ParserConfig {
+    pub fn with_passthrough_unknown(mut self, passthrough_unknown: bool) -> Self {
+        self.passthrough_unknown = passthrough_unknown;
+        self
+    }
+
+    pub fn with_genera(mut self, genera: GenusRegistry) -> Self {
+        self.genera = Arc::new(genera);
+        self
+    }
+
+    pub fn with_limits(mut self, limits: ParserLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_codes(mut self, codes: CodeRegistry) -> Self {
+        self.codes = Arc::new(codes);
+        self
+    }
+
+    /// Strict parser accepts only canonical text of primitives, the one raw
+    /// value is encoded to. Lenient parser drops non-zero lead bits, so
+    /// streams of encoders that don't zero them can be read.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Selects code table of genus version, as genus version code in stream
+    /// does.
+    pub fn with_genus(mut self, genus: GenusCountCode) -> Self {
+        self.genus = Some(genus);
+        self
+    }
+
+    pub fn genus(&self) -> Option<&GenusCountCode> {
+        self.genus.as_ref()
+    }
+
+    /// Returns code table of selected genus version. `None` means native
+    /// code table.
+    pub fn code_table(&self) -> Option<&CodeTable> {
+        self.genus
+            .as_ref()
+            .and_then(|genus| self.genera.table_for(genus))
+    }
+
+    /// Checks if universal count codes are available in selected code table.
+    pub(crate) fn universal_codes(&self) -> bool {
+        self.code_table()
+            .is_none_or(|table| table.has_universal_codes())
+    }
+
+    /// Checks size of whole stream, before it's parsed.
+    pub(crate) fn check_stream_size(&self, stream: &str) -> Result<(), ParsingError> {
+        match self.limits.max_stream_size {
+            Some(max) if stream.len() > max => {
+                Err(ParsingError::StreamSizeLimitExceeded(stream.len()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns configuration for content of group that starts `input`.
+    /// Fails if group is nested too deep.
+    pub(crate) fn nested<'a>(
+        &self,
+        input: &'a str,
+    ) -> Result<ParserConfig, nom::Err<StreamError<&'a str>>> {
+        let depth = self.depth + 1;
+        if self.limits.max_depth.is_some_and(|max| depth > max) {
+            return Err(StreamError::failure(input, StreamErrorKind::DepthLimit));
+        }
+        Ok(ParserConfig {
+            depth,
+            ..self.clone()
+        })
+    }
+
+    /// Checks number of group items.
+    pub(crate) fn check_group_items<'a>(
+        &self,
+        input: &'a str,
+        items: usize,
+    ) -> Result<(), nom::Err<StreamError<&'a str>>> {
+        check_limit(
+            input,
+            items,
+            self.limits.max_group_items,
+            StreamErrorKind::GroupItemsLimit,
+        )
+    }
+
+    /// Checks size of group content in quadlets.
+    pub(crate) fn check_group_quadlets<'a>(
+        &self,
+        input: &'a str,
+        quadlets: usize,
+    ) -> Result<(), nom::Err<StreamError<&'a str>>> {
+        check_limit(
+            input,
+            quadlets,
+            self.limits.max_group_quadlets,
+            StreamErrorKind::GroupQuadletsLimit,
+        )
+    }
+}
+
+fn check_limit(
+    input: &str,
+    value: usize,
+    max: Option<usize>,
+    kind: StreamErrorKind,
+) -> Result<(), nom::Err<StreamError<&str>>> {
+    match max {
+        Some(max) if value > max => Err(StreamError::failure(input, kind)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::{ParserConfig, ParserLimits},
+        conversion::u64_to_b64,
+        error::ParsingError,
+        frame::scan_frames,
+        parse_all, parse_all_ref_with, parse_all_with,
+    };
+
+    const SIGNATURES: &str = "-KABAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
+
+    fn limited(limits: ParserLimits) -> ParserConfig {
+        ParserConfig::default().with_limits(limits)
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let nested = ["-AAX", SIGNATURES].concat();
+        let twice = ["-AAY", &nested].concat();
+        let config = limited(ParserLimits {
+            max_depth: Some(1),
+            ..Default::default()
+        });
+        assert!(parse_all_with(&nested, &config).is_ok());
+        assert!(matches!(
+            parse_all_with(&twice, &config),
+            Err(ParsingError::DepthLimitExceeded(_))
+        ));
+
+        // Borrowed group content is checked when it's parsed
+        let (rest, values) = parse_all_ref_with(&twice, &config).unwrap();
+        assert!(rest.is_empty());
+        assert!(matches!(
+            values[0].to_owned(),
+            Err(ParsingError::DepthLimitExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn test_default_depth_limit() {
+        // Nested generic groups, each counting its content
+        let mut nested = "1AAK".to_string();
+        for _ in 0..4000 {
+            let counter = u64_to_b64(nested.len() as u64 / 4, 2);
+            nested = ["-A", &counter, &nested].concat();
+        }
+
+        // Parsed on small stack, without overflow
+        let result = std::thread::Builder::new()
+            .stack_size(2 * 1024 * 1024)
+            .spawn(move || parse_all(&nested).map(|(rest, _)| rest.len()))
+            .unwrap()
+            .join()
+            .unwrap();
+        assert!(matches!(result, Err(ParsingError::DepthLimitExceeded(_))));
+
+        let unlimited = limited(ParserLimits::unlimited());
+        let shallow = ["-AAB", "1AAK"].concat();
+        assert!(parse_all_with(&shallow, &unlimited).is_ok());
+    }
+
+    #[test]
+    fn test_group_limits() {
+        // Count of items is checked before items are parsed
+        let config = limited(ParserLimits {
+            max_group_items: Some(10),
+            ..Default::default()
+        });
+        assert_eq!(parse_all("-KA_").unwrap(), ("-KA_", vec![]));
+        assert!(matches!(
+            parse_all_with("-KA_", &config),
+            Err(ParsingError::GroupItemsLimitExceeded(_))
+        ));
+        assert!(matches!(
+            parse_all_ref_with("-KA_", &config),
+            Err(ParsingError::GroupItemsLimitExceeded(_))
+        ));
+
+        let nested = ["-AAX", SIGNATURES].concat();
+        let config = limited(ParserLimits {
+            max_group_items: Some(0),
+            ..Default::default()
+        });
+        assert!(matches!(
+            parse_all_with(&nested, &config),
+            Err(ParsingError::GroupItemsLimitExceeded(_))
+        ));
+
+        // Universal group has 23 quadlets and signatures group 22
+        let config = limited(ParserLimits {
+            max_group_quadlets: Some(22),
+            ..Default::default()
+        });
+        assert!(parse_all_with(SIGNATURES, &config).is_ok());
+        assert!(matches!(
+            parse_all_with(&nested, &config),
+            Err(ParsingError::GroupQuadletsLimitExceeded(_))
+        ));
+        assert!(matches!(
+            parse_all_ref_with(&nested, &config),
+            Err(ParsingError::GroupQuadletsLimitExceeded(_))
+        ));
+        let config = limited(ParserLimits {
+            max_group_quadlets: Some(21),
+            ..Default::default()
+        });
+        assert!(matches!(
+            parse_all_with(SIGNATURES, &config),
+            Err(ParsingError::GroupQuadletsLimitExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn test_payload_size_limit() {
+        let payload = r#"{"hello":"world"}"#;
+        let limits = |max| {
+            limited(ParserLimits {
+                max_payload_size: Some(max),
+                ..Default::default()
+            })
+        };
+        assert!(parse_all_with(payload, &limits(17)).is_ok());
+        assert!(matches!(
+            parse_all_with(payload, &limits(16)),
+            Err(ParsingError::PayloadSizeLimitExceeded(_))
+        ));
+        assert!(matches!(
+            parse_all_ref_with(payload, &limits(16)),
+            Err(ParsingError::PayloadSizeLimitExceeded(_))
+        ));
+
+        // Size declared in version string is checked before payload is parsed
+        let declared = r#"{"v":"KERI10JSON000100_"}"#;
+        assert!(matches!(
+            parse_all_with(declared, &limits(100)),
+            Err(ParsingError::PayloadSizeLimitExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn test_stream_size_limit() {
+        let config = limited(ParserLimits {
+            max_stream_size: Some(SIGNATURES.len() - 1),
+            ..Default::default()
+        });
+        let exceeded = Err(ParsingError::StreamSizeLimitExceeded(SIGNATURES.len()));
+        assert_eq!(parse_all_with(SIGNATURES, &config), exceeded);
+        assert!(matches!(
+            parse_all_ref_with(SIGNATURES, &config),
+            Err(ParsingError::StreamSizeLimitExceeded(_))
+        ));
+        assert!(matches!(
+            scan_frames(SIGNATURES, &config),
+            Err(ParsingError::StreamSizeLimitExceeded(_))
+        ));
+    }
+}
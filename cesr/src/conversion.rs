@@ -1,7 +1,11 @@
+use std::fmt;
+
 use base64::prelude::*;
 
 use super::error::Error;
 
+/// Decodes Base64 text, left padded with `A` to whole quadlets. Distinct
+/// texts may decode to the same bytes, see `decode_lead`.
 pub fn from_text_to_bytes(text: &str) -> Result<Vec<u8>, Error> {
     let lead_size = (4 - (text.len() % 4)) % 4;
     let full_derivative = [&"A".repeat(lead_size), text].concat();
@@ -43,6 +47,36 @@ pub fn num_to_b64(num: u16) -> String {
     }
 }
 
+/// Parses the number of any length from radix 64 using digits from url-safe
+/// base64 (`A` = 0, `_` = 63)
+pub fn b64_to_u64(b64: &str) -> Result<u64, Error> {
+    b64.chars().try_fold(0u64, |acc, c| {
+        let digit = match c {
+            'A'..='Z' => c as u64 - 'A' as u64,
+            'a'..='z' => c as u64 - 'a' as u64 + 26,
+            '0'..='9' => c as u64 - '0' as u64 + 52,
+            '-' => 62,
+            '_' => 63,
+            _ => return Err(Error::Base64DecodingError),
+        };
+        acc.checked_mul(64)
+            .map(|acc| acc + digit)
+            .ok_or(Error::NumberOverflowError(b64.to_string()))
+    })
+}
+
+/// Formats the number in radix 64 using digits from url-safe base64 (`A` = 0,
+/// `_` = 63), left padded with `A` to expected length.
+pub fn u64_to_b64(num: u64, expected_length: usize) -> String {
+    let mut digits = Vec::with_capacity(expected_length);
+    let mut rest = num;
+    while rest > 0 || digits.len() < expected_length {
+        digits.push(B64_DIGITS[(rest % 64) as usize]);
+        rest /= 64;
+    }
+    digits.iter().rev().map(|d| *d as char).collect()
+}
+
 pub fn adjust_with_num(sn: u16, expected_length: usize) -> String {
     if expected_length > 0 {
         let i = num_to_b64(sn);
@@ -58,6 +92,101 @@ pub fn adjust_with_num(sn: u16, expected_length: usize) -> String {
     }
 }
 
+const B64_DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Writes the number in radix 64, left padded with `A` to exactly `length`
+/// digits. Fails if the number doesn't fit.
+pub(crate) fn write_b64_num<W: fmt::Write + ?Sized>(
+    num: u64,
+    length: usize,
+    writer: &mut W,
+) -> fmt::Result {
+    if length < 11 && num >> (6 * length) != 0 {
+        return Err(fmt::Error);
+    }
+    for i in (0..length).rev() {
+        let digit = num.checked_shr(6 * i as u32).unwrap_or(0) & 63;
+        writer.write_char(B64_DIGITS[digit as usize] as char)?;
+    }
+    Ok(())
+}
+
+/// Writes url-safe base64 of bytes prepended with zero lead bytes to a
+/// multiple of three, as `from_bytes_to_text` encodes them, skipping first
+/// `skip` chars.
+pub(crate) fn write_b64<W: fmt::Write + ?Sized>(
+    bytes: &[u8],
+    skip: usize,
+    writer: &mut W,
+) -> fmt::Result {
+    write_b64_with_lead(bytes, 0, skip, writer)
+}
+
+/// Writes url-safe base64 of bytes prepended with `lead` zero bytes, as
+/// `write_b64` does.
+pub(crate) fn write_b64_with_lead<W: fmt::Write + ?Sized>(
+    bytes: &[u8],
+    lead: usize,
+    skip: usize,
+    writer: &mut W,
+) -> fmt::Result {
+    let lead_size = lead + (3 - ((lead + bytes.len()) % 3)) % 3;
+    let byte = |i: usize| i.checked_sub(lead_size).map_or(0, |i| bytes[i]);
+    let mut written = 0;
+    for start in (0..lead_size + bytes.len()).step_by(3) {
+        let triplet =
+            (byte(start) as u32) << 16 | (byte(start + 1) as u32) << 8 | byte(start + 2) as u32;
+        for shift in [18, 12, 6, 0] {
+            if written >= skip {
+                writer.write_char(B64_DIGITS[(triplet >> shift & 63) as usize] as char)?;
+            }
+            written += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Length of base64 text written by `write_b64` for `size` bytes, before
+/// skipping.
+pub(crate) fn b64_len(size: usize) -> usize {
+    size.div_ceil(3) * 4
+}
+
+/// Decodes Base64 text that follows `lead_chars` chars of code in its first
+/// quadlet, and drops `lead_bytes` lead bytes. Code and text have to make
+/// whole quadlets, and text has no padding chars. If `strict`, lead bits
+/// that aren't part of code have to be zero, so only canonical text, the one
+/// that value is encoded to, is accepted. Otherwise they are dropped whatever
+/// they are, so text of encoders that don't zero them can be read.
+pub(crate) fn decode_lead(
+    text: &str,
+    lead_chars: usize,
+    lead_bytes: usize,
+    strict: bool,
+) -> Result<Vec<u8>, Error> {
+    if !(lead_chars + text.len()).is_multiple_of(4) {
+        return Err(Error::MisalignedTextError(text.to_string()));
+    }
+    if !is_base64(text) {
+        return Err(Error::NonBase64TextError(text.to_string()));
+    }
+    let mut bytes = from_text_to_bytes(text)?;
+    let Some(lead) = bytes.get(..lead_bytes) else {
+        return Err(Error::IncorrectLengthError(text.to_string()));
+    };
+    if strict && lead.iter().any(|byte| *byte != 0) {
+        return Err(Error::NonZeroPadBitsError(text.to_string()));
+    }
+    bytes.drain(..lead_bytes);
+    Ok(bytes)
+}
+
+/// Checks if text contains only url-safe base64 characters.
+pub fn is_base64(text: &str) -> bool {
+    text.chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
 pub fn check_first_three_bits(byte: &u8) -> u8 {
     (byte >> 5) & 0b111 // Shift right by 5 and mask the first 3 bits
 }
@@ -85,6 +214,17 @@ fn b64_to_num_test() {
     assert_eq!(b64_to_num("__").unwrap(), 4095);
 }
 
+#[test]
+fn u64_b64_test() {
+    assert_eq!(u64_to_b64(0, 4), "AAAA");
+    assert_eq!(u64_to_b64(100, 4), "AABk");
+    assert_eq!(u64_to_b64(4096, 4), "ABAA");
+    assert_eq!(u64_to_b64(16_777_215, 4), "____");
+    assert_eq!(b64_to_u64("AABk").unwrap(), 100);
+    assert_eq!(b64_to_u64("____").unwrap(), 16_777_215);
+    assert!(b64_to_u64("AA=A").is_err());
+}
+
 #[test]
 fn test_from_text_to_bytes() {
     assert_eq!(hex::encode(from_text_to_bytes("MP__").unwrap()), "30ffff");
@@ -116,3 +256,19 @@ fn test_adjust_with_num() {
     assert_eq!(adjust_with_num(2, 4), "AAAC");
     assert_eq!(adjust_with_num(27, 6), "AAAAAb");
 }
+
+#[test]
+fn test_write_b64() {
+    for bytes in [&b""[..], b"a", b"ab", b"abc", b"abcd", &[0xff; 33]] {
+        let expected = from_bytes_to_text(bytes);
+        let mut text = String::new();
+        write_b64(bytes, 1, &mut text).unwrap();
+        assert_eq!(text, expected.get(1..).unwrap_or_default());
+        assert_eq!(b64_len(bytes.len()), expected.len());
+    }
+
+    let mut text = String::new();
+    write_b64_num(80, 4, &mut text).unwrap();
+    assert_eq!(text, "AABQ");
+    assert!(write_b64_num(4096, 2, &mut text).is_err());
+}
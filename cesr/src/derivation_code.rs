@@ -1,3 +1,5 @@
+use std::fmt;
+
 pub trait DerivationCode {
     /// hard (fixed) part of code size in chars
     fn hard_size(&self) -> usize;
@@ -14,4 +16,12 @@ pub trait DerivationCode {
         self.code_size() + self.value_size()
     }
     fn to_str(&self) -> String;
+    /// Writes code text. Codes of static text override it, so nothing is
+    /// allocated.
+    fn write_code<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result
+    where
+        Self: Sized,
+    {
+        writer.write_str(&self.to_str())
+    }
 }
@@ -0,0 +1,205 @@
+//! Streaming CESR text encoding. Values are written piece by piece into any
+//! `fmt::Write` or `io::Write`, without building intermediate strings, and
+//! their exact text size is known before anything is written.
+
+use std::{fmt, io};
+
+use crate::{
+    conversion::{b64_len, write_b64},
+    derivation_code::DerivationCode,
+    primitives::Matter,
+};
+
+pub trait Encode {
+    /// Exact size in chars of CESR text.
+    fn encoded_len(&self) -> usize;
+
+    /// Writes CESR text. Fails if writer fails, or if number doesn't fit into
+    /// its code. Indexes of signatures are checked when signature is created,
+    /// and counts of groups that don't fit into small counter are written in
+    /// big one.
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result;
+
+    /// Writes CESR text as UTF-8 bytes. Text is written in small pieces, so
+    /// unbuffered writers should be wrapped in `io::BufWriter`.
+    fn write_cesr_io<W: io::Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        let mut adapter = IoAdapter {
+            inner: writer,
+            error: None,
+        };
+        self.write_cesr(&mut adapter).map_err(|_| {
+            adapter.error.unwrap_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "can't encode CESR text")
+            })
+        })
+    }
+
+    /// Encodes CESR text into string allocated once, with exact size. Panics
+    /// if `write_cesr` fails.
+    fn to_cesr_string(&self) -> String {
+        let mut text = String::with_capacity(self.encoded_len());
+        self.write_cesr(&mut text)
+            .expect("numbers fit into their codes");
+        text
+    }
+}
+
+/// `fmt::Write` over `io::Write`, keeping the error that `fmt::Error` can't
+/// carry.
+struct IoAdapter<'a, W: ?Sized> {
+    inner: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write + ?Sized> fmt::Write for IoAdapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+/// Size of qb64 text of raw value with code. Lead chars of Base64 value are
+/// replaced by code, as in `encode_qb64`.
+pub(crate) fn qb64_len(code_size: usize, raw_size: usize) -> usize {
+    code_size + b64_len(raw_size) - code_size % 4
+}
+
+/// Writes Base64 value of qb64 text, that follows code of given size.
+pub(crate) fn write_qb64_value<W: fmt::Write + ?Sized>(
+    code_size: usize,
+    raw: &[u8],
+    writer: &mut W,
+) -> fmt::Result {
+    write_b64(raw, code_size % 4, writer)
+}
+
+impl<M: Matter> Encode for M {
+    fn encoded_len(&self) -> usize {
+        qb64_len(self.code().code_size(), self.raw().len())
+    }
+
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        self.code().write_code(writer)?;
+        write_qb64_value(self.code().code_size(), self.raw(), writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::{
+        config::{ParserConfig, ParserLimits},
+        encode::Encode,
+        group::Group,
+        parse_all, parse_all_ref_with,
+        primitives::{
+            codes::{
+                attached_signature_code::{AttachedSignatureCode, Index},
+                self_signing::SelfSigning,
+            },
+            IndexedSignature, Matter, PublicKey,
+        },
+        universal_codes::generic_pipeline,
+        value::{parse_value, parse_value_with, Value},
+    };
+
+    const KEL: &str = r#"{"v":"KERI10JSON000188_","t":"icp","d":"EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg","i":"EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg","s":"0","kt":"1","k":["DA4cgeFcpglZf6fQ7u1j8fMs7GbkOQBzVHhBJlaHQLC9"],"nt":"1","n":["EJMujtnS0x3RGp_kHC2bh3p6cAz_4nKp6E3Yrj2u-Lsh"],"bt":"2","b":["BJq7UABlttINuWJh1Xl2lkqZG4NTdUdqnbFJDa6ZyxCC","BDg1zxxf8u4Hx5IPraZzmStfSCZFZbDzMHjqVcFW5OfP"],"c":[],"a":[]}-KABAADZCv1YufmwIvFbzC9jNoVZx2ZgOF8hzrxcuP9vlhJ0tNAYIvNEh0yKIGtkk1bIhrLIAEScbBmxxPosX-rGSAsD-MABBDg1zxxf8u4Hx5IPraZzmStfSCZFZbDzMHjqVcFW5OfP0BCQwOrc3LZqdYs8OEKhQlP4LpB9AqCVpwyGHCB1nfjrBjSYiWtlcvSYI5Vugh3H3rh0gfDqGHUfRKEQrIXKTWAC-MABBJq7UABlttINuWJh1Xl2lkqZG4NTdUdqnbFJDa6ZyxCC0BCO8ycCB9reZHhv7wT4yEAy-q_IFbCA29ttaU3IcQ1tZAIGNKYNkZMY9EjGfRsq8shizeURuoxdYoRXGscQFVQM"#;
+
+    #[test]
+    fn test_encoded_len() {
+        let (rest, values) = parse_all(KEL).unwrap();
+        assert!(rest.is_empty());
+        let total: usize = values.iter().map(Encode::encoded_len).sum();
+        assert_eq!(total, KEL.len());
+
+        let mut text = String::new();
+        let mut bytes = vec![];
+        for value in &values {
+            assert_eq!(value.to_cesr_string().len(), value.encoded_len());
+            value.write_cesr(&mut text).unwrap();
+            value.write_cesr_io(&mut bytes).unwrap();
+        }
+        assert_eq!(text, KEL);
+        assert_eq!(bytes, KEL.as_bytes());
+
+        let key = PublicKey::from_qb64("DA4cgeFcpglZf6fQ7u1j8fMs7GbkOQBzVHhBJlaHQLC9").unwrap();
+        assert_eq!(key.encoded_len(), 44);
+        assert_eq!(key.to_cesr_string(), key.qb64());
+    }
+
+    #[test]
+    fn test_generic_pipeline() {
+        let (_, values) = parse_all(KEL).unwrap();
+        let attachments = values[1..].to_vec();
+        let pipeline = generic_pipeline(attachments);
+        let text = pipeline.to_cesr_string();
+        assert!(text.starts_with("-A"));
+        assert_eq!(text.len(), pipeline.encoded_len());
+        let (rest, parsed) = parse_value(&text).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parsed, pipeline);
+    }
+
+    #[test]
+    fn test_big_counters() {
+        let signature = IndexedSignature::new(
+            AttachedSignatureCode::new(SelfSigning::Ed25519Sha512, Index::BothSame(0)),
+            vec![0; 64],
+        )
+        .unwrap();
+        let config = ParserConfig::default().with_limits(ParserLimits::unlimited());
+
+        // Count of 4096 items doesn't fit into two chars
+        let group = Value::SpecificGroup(Group::IndexedControllerSignatures(vec![signature; 4096]));
+        let text = group.to_string();
+        assert!(text.starts_with("--KAABAA"));
+        assert_eq!(text.len(), group.encoded_len());
+        assert_eq!(group.to_cesr_string(), text);
+        let (rest, parsed) = parse_value_with(&text, &config).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parsed, group);
+
+        // 90114 quadlets of content
+        let pipeline = generic_pipeline(vec![group]);
+        let text = pipeline.to_string();
+        assert!(text.starts_with("--AAAWAC--KAABAA"));
+        assert_eq!(text.len(), pipeline.encoded_len());
+        let (rest, parsed) = parse_value_with(&text, &config).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parsed, pipeline);
+        let (rest, values) = parse_all_ref_with(&text, &config).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(values[0].to_owned().unwrap(), pipeline);
+    }
+
+    #[test]
+    fn test_write_errors() {
+        struct Full;
+
+        impl io::Write for Full {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::StorageFull, "full"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let (_, values) = parse_all(KEL).unwrap();
+        let error = values[1].write_cesr_io(&mut Full).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::StorageFull);
+
+        // Index doesn't fit into one char of soft code, so signature can't
+        // be created
+        let code = AttachedSignatureCode::new(SelfSigning::Ed25519Sha512, Index::BothSame(64));
+        assert!(IndexedSignature::new(code, vec![0; 64]).is_err());
+        let code = AttachedSignatureCode::new(SelfSigning::Ed25519Sha512, Index::BigDual(64, 4095));
+        let signature = IndexedSignature::new(code, vec![0; 64]).unwrap();
+        let group = Value::SpecificGroup(Group::IndexedControllerSignatures(vec![signature]));
+        assert_eq!(group.to_string().len(), group.encoded_len());
+    }
+}
@@ -25,6 +25,39 @@ pub enum Error {
 
     #[error("Payload serialization error")]
     PayloadSerializationError,
+
+    #[error("Number overflow: {0}")]
+    NumberOverflowError(String),
+
+    #[error("Not a Base64 text: {0}")]
+    NonBase64TextError(String),
+
+    #[error("UTF-8 decoding error")]
+    Utf8DecodingError,
+
+    #[error("Expected {expected}, found {found}")]
+    UnexpectedValueError { expected: String, found: String },
+
+    #[error("No equivalent of {code} in {table} code table")]
+    NoEquivalentCodeError { code: String, table: String },
+
+    #[error("Can't write CESR text")]
+    WriteError,
+
+    #[error("Non-zero pad bits: {0}")]
+    NonZeroPadBitsError(String),
+
+    #[error("Text not aligned to quadlets: {0}")]
+    MisalignedTextError(String),
+
+    #[error("Code already in use: {0}")]
+    CodeConflictError(String),
+}
+
+impl From<std::fmt::Error> for Error {
+    fn from(_: std::fmt::Error) -> Self {
+        Error::WriteError
+    }
 }
 
 impl From<base64::DecodeError> for Error {
@@ -50,16 +83,156 @@ pub enum ParsingError {
     Error(String),
     #[error("Parsing failure: {0}")]
     Failure(String),
+    #[error("Genus version override not allowed: {0}")]
+    GenusOverrideNotAllowed(String),
+    #[error("Group nesting depth limit exceeded: {0}")]
+    DepthLimitExceeded(String),
+    #[error("Group items limit exceeded: {0}")]
+    GroupItemsLimitExceeded(String),
+    #[error("Group size limit exceeded: {0}")]
+    GroupQuadletsLimitExceeded(String),
+    #[error("Payload size limit exceeded: {0}")]
+    PayloadSizeLimitExceeded(String),
+    #[error("Stream size limit exceeded: {0} bytes")]
+    StreamSizeLimitExceeded(usize),
+    #[error("Non-canonical encoding, pad bits not zero: {0}")]
+    NonZeroPadBits(String),
+}
+
+impl ParsingError {
+    /// Error of value which text can't be decoded.
+    pub(crate) fn undecodable(error: Error, text: &str) -> Self {
+        match error {
+            Error::NonZeroPadBitsError(_) => ParsingError::NonZeroPadBits(text.to_string()),
+            _ => ParsingError::Error(text.to_string()),
+        }
+    }
 }
 
-impl From<nom::Err<nom::error::Error<&str>>> for ParsingError {
-    fn from(err: nom::Err<nom::error::Error<&str>>) -> Self {
-        match err {
+impl From<nom::Err<StreamError<&str>>> for ParsingError {
+    fn from(err: nom::Err<StreamError<&str>>) -> Self {
+        let e = match err {
             nom::Err::Incomplete(_) => {
-                ParsingError::IncompleteStream("Stream is incomplete".to_string())
+                return ParsingError::IncompleteStream("Stream is incomplete".to_string())
+            }
+            nom::Err::Error(e) => return ParsingError::Error(e.input.to_string()),
+            nom::Err::Failure(e) => e,
+        };
+        let input = e.input.to_string();
+        match e.kind {
+            StreamErrorKind::Nom(_) => ParsingError::Failure(input),
+            StreamErrorKind::GenusOverrideNotAllowed => {
+                ParsingError::GenusOverrideNotAllowed(input)
             }
-            nom::Err::Error(e) => ParsingError::Error(e.input.to_string()),
-            nom::Err::Failure(e) => ParsingError::Failure(e.input.to_string()),
+            StreamErrorKind::DepthLimit => ParsingError::DepthLimitExceeded(input),
+            StreamErrorKind::GroupItemsLimit => ParsingError::GroupItemsLimitExceeded(input),
+            StreamErrorKind::GroupQuadletsLimit => ParsingError::GroupQuadletsLimitExceeded(input),
+            StreamErrorKind::PayloadSizeLimit => ParsingError::PayloadSizeLimitExceeded(input),
+            StreamErrorKind::NonZeroPadBits => ParsingError::NonZeroPadBits(input),
         }
     }
 }
+
+/// Result of stream parsers.
+pub type IResult<I, O> = nom::IResult<I, O, StreamError<I>>;
+
+/// Error of stream parsers. Besides errors of nom parsers, it tells why
+/// parsing was stopped on purpose, to be reported as specific
+/// `ParsingError`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamError<I> {
+    pub input: I,
+    pub kind: StreamErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamErrorKind {
+    /// Error of nom parser.
+    Nom(nom::error::ErrorKind),
+    /// Genus version code found where it can't override code table.
+    GenusOverrideNotAllowed,
+    /// Group nested deeper than configuration allows.
+    DepthLimit,
+    /// Group of more items than configuration allows.
+    GroupItemsLimit,
+    /// Group content larger than configuration allows.
+    GroupQuadletsLimit,
+    /// Payload larger than configuration allows.
+    PayloadSizeLimit,
+    /// Primitive text with pad bits set.
+    NonZeroPadBits,
+}
+
+impl<I> StreamError<I> {
+    pub fn new(input: I, kind: StreamErrorKind) -> Self {
+        Self { input, kind }
+    }
+
+    /// Failure that stops parsing of the whole stream.
+    pub(crate) fn failure(input: I, kind: StreamErrorKind) -> nom::Err<Self> {
+        nom::Err::Failure(Self::new(input, kind))
+    }
+}
+
+impl<I> nom::error::ParseError<I> for StreamError<I> {
+    fn from_error_kind(input: I, kind: nom::error::ErrorKind) -> Self {
+        Self::new(input, StreamErrorKind::Nom(kind))
+    }
+
+    fn append(_input: I, _kind: nom::error::ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum TranscodingError {
+    #[error("Can't parse stream: {0}")]
+    Parsing(#[from] ParsingError),
+    #[error("Unparsed data left in stream: {0}")]
+    UnparsedData(String),
+    #[error(transparent)]
+    Encoding(#[from] Error),
+    #[error("No equivalent in CESR {version}: {}", codes.join(", "))]
+    NoEquivalent { version: String, codes: Vec<String> },
+}
+
+#[cfg(feature = "cesr-log")]
+#[derive(Debug, thiserror::Error)]
+pub enum LogError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Can't parse message: {0}")]
+    Parsing(#[from] ParsingError),
+    #[error("Invalid index file: {0}")]
+    Index(#[from] serde_json::Error),
+    #[error("Not a UTF-8 text at offset {0}")]
+    NonUtf8(usize),
+    #[error("No message at position {0}")]
+    NoMessage(usize),
+    #[error("Log file was truncated or rewritten")]
+    Truncated,
+}
+
+#[test]
+fn test_parsing_error_from_stream_error() {
+    use nom::error::{make_error, ErrorKind};
+
+    // Failures of nom parsers are never taken for exceeded limits
+    for kind in [
+        ErrorKind::Fix,
+        ErrorKind::Count,
+        ErrorKind::TooLarge,
+        ErrorKind::Not,
+    ] {
+        let failure = nom::Err::Failure(make_error("-KAB", kind));
+        assert_eq!(
+            ParsingError::from(failure),
+            ParsingError::Failure("-KAB".into())
+        );
+    }
+    let exceeded = StreamError::failure("-KAB", StreamErrorKind::GroupItemsLimit);
+    assert_eq!(
+        ParsingError::from(exceeded),
+        ParsingError::GroupItemsLimitExceeded("-KAB".into())
+    );
+}
@@ -0,0 +1,193 @@
+//! Splitting stream into message frames, that can be decoded independently.
+//! Frames are found by sequential scan, which takes payload sizes from
+//! version strings and group sizes from counters, without decoding
+//! primitives.
+
+use std::{borrow::Cow, sync::Arc};
+
+use crate::{
+    config::ParserConfig,
+    error::ParsingError,
+    parse_all_with,
+    payload::declared_size,
+    value::Value,
+    value_ref::{value_ref, ValueRef},
+};
+
+/// Message frame: payload followed by its attachments. Values before the
+/// first payload make a frame of their own.
+#[derive(Debug, Clone)]
+pub struct Frame<'a> {
+    text: &'a str,
+    /// Stream from the start of frame.
+    tail: &'a str,
+    config: Arc<ParserConfig>,
+}
+
+impl<'a> Frame<'a> {
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Configuration in force at the start of frame, with genus version
+    /// selected by preceding genus version codes.
+    pub fn config(&self) -> &ParserConfig {
+        &self.config
+    }
+
+    /// Decodes all values of frame. Fails if frame can't be decoded whole.
+    pub fn parse(&self) -> Result<Vec<Value>, ParsingError> {
+        match parse_all_with(self.text, &self.config)? {
+            ("", values) => Ok(values),
+            (rest, _) => Err(ParsingError::Error(rest.to_string())),
+        }
+    }
+
+    /// Parses stream from the start of frame, as `parse_all_with` does, so
+    /// that values end where sequential parsing ends them. Meant for frame
+    /// that can't be decoded whole.
+    pub fn parse_tail(&self) -> Result<(&'a str, Vec<Value>), ParsingError> {
+        parse_all_with(self.tail, &self.config)
+    }
+}
+
+/// Splits stream into message frames, until stream ends or can't be scanned
+/// further, as `parse_all_with` does. Returns unscanned rest of stream.
+///
+/// Values are only framed, so value that scans but doesn't decode, e.g.
+/// primitive of invalid Base64 text, fails in `Frame::parse`.
+pub fn scan_frames<'a>(
+    stream: &'a str,
+    config: &ParserConfig,
+) -> Result<(&'a str, Vec<Frame<'a>>), ParsingError> {
+    config.check_stream_size(stream)?;
+    let mut config: Cow<'a, ParserConfig> = Cow::Owned(config.clone());
+    let mut frame_config = Arc::new(config.as_ref().clone());
+    let mut frames = vec![];
+    let mut frame_start = stream;
+    let mut rest = stream;
+    while !rest.is_empty() {
+        let is_payload = rest.starts_with('{');
+        if is_payload && rest.len() < frame_start.len() {
+            let frame_len = frame_start.len() - rest.len();
+            frames.push(Frame {
+                text: &frame_start[..frame_len],
+                tail: frame_start,
+                config: frame_config,
+            });
+            frame_config = Arc::new(config.as_ref().clone());
+            frame_start = rest;
+        }
+        let more = match payload_size(rest) {
+            Some(size) if is_payload => &rest[size..],
+            _ => match value_ref(rest, &config) {
+                Ok((more, ValueRef::VersionGenus(genus))) => {
+                    config = Cow::Owned(config.into_owned().with_genus(genus));
+                    more
+                }
+                Ok((more, _)) => more,
+                Err(nom::Err::Error(_)) => break,
+                Err(e) => return Err(e.into()),
+            },
+        };
+        rest = more;
+    }
+    if rest.len() < frame_start.len() {
+        let frame_len = frame_start.len() - rest.len();
+        frames.push(Frame {
+            text: &frame_start[..frame_len],
+            tail: frame_start,
+            config: frame_config,
+        });
+    }
+    Ok((rest, frames))
+}
+
+/// Size of JSON payload at the start of stream, declared in its version
+/// string. Size that can't be right, e.g. that cuts payload before its
+/// closing brace, is ignored, so payload is parsed instead.
+fn payload_size(stream: &str) -> Option<usize> {
+    declared_size(stream).filter(|size| {
+        *size > 0 && stream.is_char_boundary(*size) && stream.as_bytes()[size - 1] == b'}'
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{config::ParserConfig, error::ParsingError, frame::scan_frames, parse_all};
+
+    const ICP: &str = r#"{"v":"KERI10JSON000188_","t":"icp","d":"EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg","i":"EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg","s":"0","kt":"1","k":["DA4cgeFcpglZf6fQ7u1j8fMs7GbkOQBzVHhBJlaHQLC9"],"nt":"1","n":["EJMujtnS0x3RGp_kHC2bh3p6cAz_4nKp6E3Yrj2u-Lsh"],"bt":"2","b":["BJq7UABlttINuWJh1Xl2lkqZG4NTdUdqnbFJDa6ZyxCC","BDg1zxxf8u4Hx5IPraZzmStfSCZFZbDzMHjqVcFW5OfP"],"c":[],"a":[]}-KABAADZCv1YufmwIvFbzC9jNoVZx2ZgOF8hzrxcuP9vlhJ0tNAYIvNEh0yKIGtkk1bIhrLIAEScbBmxxPosX-rGSAsD-MABBDg1zxxf8u4Hx5IPraZzmStfSCZFZbDzMHjqVcFW5OfP0BCQwOrc3LZqdYs8OEKhQlP4LpB9AqCVpwyGHCB1nfjrBjSYiWtlcvSYI5Vugh3H3rh0gfDqGHUfRKEQrIXKTWAC"#;
+    const SIGNATURE: &str =
+        "AADZCv1YufmwIvFbzC9jNoVZx2ZgOF8hzrxcuP9vlhJ0tNAYIvNEh0yKIGtkk1bIhrLIAEScbBmxxPosX-rGSAsD";
+
+    #[test]
+    fn test_scan_frames() {
+        let stream = [ICP, ICP, "-KAB", SIGNATURE, ICP].concat();
+        let (rest, frames) = scan_frames(&stream, &ParserConfig::default()).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].text(), ICP);
+        assert_eq!(frames[1].text(), [ICP, "-KAB", SIGNATURE].concat());
+
+        let values: Vec<_> = frames
+            .iter()
+            .flat_map(|frame| frame.parse().unwrap())
+            .collect();
+        assert_eq!(values, parse_all(&stream).unwrap().1);
+
+        // Payload without version string is parsed to find its end
+        let stream = [r#"{"name":"John"}-KAB"#, SIGNATURE, "{"].concat();
+        let (rest, frames) = scan_frames(&stream, &ParserConfig::default()).unwrap();
+        assert_eq!(rest, "{");
+        assert_eq!(frames[0].text(), &stream[..stream.len() - 1]);
+    }
+
+    #[test]
+    fn test_scan_genus() {
+        // Values before the first payload make a frame, and genus version
+        // code switches code table of following frames.
+        let payload = &ICP[..ICP.find("-KAB").unwrap()];
+        let stream = ["-_AAABAA", payload, "-AAB", SIGNATURE].concat();
+        let (rest, frames) = scan_frames(&stream, &ParserConfig::default()).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].text(), "-_AAABAA");
+        assert!(frames[0].config().genus().is_none());
+        assert!(frames[1].config().genus().is_some());
+        let values: Vec<_> = frames
+            .iter()
+            .flat_map(|frame| frame.parse().unwrap())
+            .collect();
+        assert_eq!(values, parse_all(&stream).unwrap().1);
+    }
+
+    #[test]
+    fn test_undecodable_frame() {
+        // Signature text isn't decoded while scanning
+        let invalid = [ICP, "-KAB", &SIGNATURE.replace('D', "=")].concat();
+        let (rest, frames) = scan_frames(&invalid, &ParserConfig::default()).unwrap();
+        assert!(rest.is_empty());
+        assert!(matches!(frames[0].parse(), Err(ParsingError::Error(_))));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parse_all_par() {
+        let stream = [ICP, "-KAB", SIGNATURE].concat().repeat(100) + "{";
+        let (rest, values) = crate::parse_all_par(&stream).unwrap();
+        assert_eq!(rest, "{");
+        assert_eq!((rest, values), parse_all(&stream).unwrap());
+
+        // Values end at the value that doesn't decode, as in sequential
+        // parsing, even if following frames do
+        let invalid = [ICP, "-KAB", &SIGNATURE.replace('D', "="), ICP].concat();
+        let (rest, values) = crate::parse_all_par(&invalid).unwrap();
+        assert!(rest.len() > ICP.len());
+        assert_eq!((rest, values), parse_all(&invalid).unwrap());
+
+        // Non-zero pad bits fail both the same way
+        let failing = [ICP, &ICP.replace("-MABBDg1", "-MABB_g1")].concat();
+        assert!(parse_all(&failing).is_err());
+        assert_eq!(crate::parse_all_par(&failing), parse_all(&failing));
+    }
+}
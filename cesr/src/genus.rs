@@ -0,0 +1,333 @@
+use std::{collections::HashMap, mem::discriminant};
+
+use serde::Serialize;
+
+use crate::{
+    conversion::is_base64,
+    error::Error,
+    group::codes::{split_counter, GroupCode},
+    universal_codes::{GenusCountCode, KERI_GENUS, TSP_GENUS},
+};
+
+/// Constructor of group code of given count.
+pub type GroupCodeFn = fn(u32) -> GroupCode;
+
+/// What the count of counter code means: number of framed items (CESR 1.0
+/// groups), or number of quadlets of framed content (CESR 2.0 groups).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CountKind {
+    Items,
+    Quadlets,
+}
+
+/// Code table of genus version. Maps two chars hard part of counter code to
+/// group it frames.
+#[derive(Debug, Clone)]
+pub struct CodeTable {
+    name: String,
+    counters: Vec<(String, GroupCodeFn, CountKind)>,
+    // CESR 1.0 tables use universal count codes letters for specific groups
+    universal_codes: bool,
+    // CESR 1.0 frames attachments with its own counter instead of `-C`
+    attachment_counter: Option<String>,
+}
+
+impl CodeTable {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            counters: vec![],
+            universal_codes: true,
+            attachment_counter: None,
+        }
+    }
+
+    /// Sets if universal count codes (`-A`, `-C`, `-E`) are reserved in this
+    /// table. Counters of the same letters can't be used otherwise.
+    pub fn with_universal_codes(mut self, universal_codes: bool) -> Self {
+        self.universal_codes = universal_codes;
+        self
+    }
+
+    pub fn has_universal_codes(&self) -> bool {
+        self.universal_codes
+    }
+
+    /// Sets counter of attachment group, used by tables without universal
+    /// codes. Its count is number of quadlets of framed attachments.
+    pub fn with_attachment_counter(mut self, code: &str) -> Result<Self, Error> {
+        check_counter(code)?;
+        self.attachment_counter = Some(code.to_string());
+        Ok(self)
+    }
+
+    pub fn attachment_counter(&self) -> Option<&str> {
+        self.attachment_counter.as_deref()
+    }
+
+    /// Adds counter to the table. Counter code is `-` followed by one
+    /// Base64 char.
+    pub fn with_counter(
+        mut self,
+        code: &str,
+        group: GroupCodeFn,
+        kind: CountKind,
+    ) -> Result<Self, Error> {
+        check_counter(code)?;
+        self.counters.retain(|(known, _, _)| known != code);
+        self.counters.push((code.to_string(), group, kind));
+        Ok(self)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Decodes group code from its text representation, using counters of
+    /// this table. Big counters, `-` followed by counter code and five chars
+    /// count, belong to tables with universal codes only.
+    pub fn group_code(&self, s: &str) -> Result<GroupCode, Error> {
+        if s.starts_with("--") && !self.universal_codes {
+            return Err(Error::UnknownCodeError);
+        }
+        let (code, count) = split_counter(s)?;
+        self.counters
+            .iter()
+            .find(|(known, _, _)| known == code)
+            .map(|(_, group, _)| group(count as u32))
+            .ok_or(Error::UnknownCodeError)
+    }
+
+    /// Checks if two chars hard part of counter code is defined in this
+    /// table.
+    pub fn knows_counter(&self, code: &str) -> bool {
+        self.attachment_counter.as_deref() == Some(code)
+            || self.counters.iter().any(|(known, _, _)| known == code)
+    }
+
+    /// Returns hard part of counter code, that this table assigns to group.
+    pub fn counter_code(&self, group_code: &GroupCode) -> Option<&str> {
+        self.counter(group_code).map(|(code, _, _)| code.as_str())
+    }
+
+    /// Returns how this table counts content of group. Groups unknown to
+    /// the table are counted in items.
+    pub fn count_kind(&self, group_code: &GroupCode) -> CountKind {
+        self.counter(group_code)
+            .map_or(CountKind::Items, |(_, _, kind)| *kind)
+    }
+
+    /// Returns counters of table with groups they frame, in order of
+    /// registration.
+    pub fn counters(&self) -> impl Iterator<Item = (&str, GroupCode, CountKind)> {
+        self.counters
+            .iter()
+            .map(|(code, group, kind)| (code.as_str(), group(0), *kind))
+    }
+
+    fn counter(&self, group_code: &GroupCode) -> Option<&(String, GroupCodeFn, CountKind)> {
+        self.counters
+            .iter()
+            .find(|(_, group, _)| discriminant(&group(0)) == discriminant(group_code))
+    }
+
+    /// KERI and ACDC code table of CESR 1.0. Groups are counted in items,
+    /// except pathed material, and attachments are framed with `-V`.
+    pub fn keri_v1() -> Self {
+        let counters: Vec<(&str, GroupCodeFn)> = vec![
+            ("-A", GroupCode::IndexedControllerSignatures),
+            ("-B", GroupCode::IndexedWitnessSignatures),
+            ("-C", GroupCode::NontransferableReceiptCouples),
+            ("-D", GroupCode::TransReceiptQuadruples),
+            ("-E", GroupCode::FirstSeenReplyCouples),
+            ("-F", GroupCode::TransIndexedSigGroups),
+            ("-G", GroupCode::SealSourceCouples),
+            ("-H", GroupCode::TransLastIdxSigGroups),
+        ];
+        let mut table = Self::from_counters("KERI 1.0", counters, CountKind::Items);
+        #[cfg(feature = "cesr-proof")]
+        table.counters.push((
+            "-L".to_string(),
+            GroupCode::PathedMaterialQuadruple,
+            CountKind::Quadlets,
+        ));
+        table.universal_codes = false;
+        table.attachment_counter = Some("-V".to_string());
+        table
+    }
+
+    /// KERI and ACDC code table of CESR 2.0. All groups are counted in
+    /// quadlets. Streams that don't select any genus are parsed with the same
+    /// counters, but with counts of items.
+    pub fn keri_v2() -> Self {
+        Self::from_counters("KERI 2.0", Self::v2_counters(), CountKind::Quadlets)
+    }
+
+    /// TSP code table. TSP messages reuse signature, payload and seal groups
+    /// of CESR 2.0.
+    pub fn tsp() -> Self {
+        Self::from_counters("TSP 1.0", Self::v2_counters(), CountKind::Quadlets)
+    }
+
+    fn v2_counters() -> Vec<(&'static str, GroupCodeFn)> {
+        vec![
+            ("-K", GroupCode::IndexedControllerSignatures),
+            ("-L", GroupCode::IndexedWitnessSignatures),
+            ("-M", GroupCode::NontransferableReceiptCouples),
+            ("-N", GroupCode::TransReceiptQuadruples),
+            ("-O", GroupCode::FirstSeenReplyCouples),
+            #[cfg(feature = "cesr-proof")]
+            ("-P", GroupCode::PathedMaterialQuadruple),
+            ("-Q", GroupCode::DigestSealSingles),
+            ("-R", GroupCode::MerkleRootSealSingles),
+            ("-S", GroupCode::AnchoringEventSeals),
+            ("-T", GroupCode::SealSourceCouples),
+            ("-U", GroupCode::SealSourceLastSingles),
+            ("-V", GroupCode::BackerRegistrarSealCouples),
+            ("-X", GroupCode::TransIndexedSigGroups),
+            ("-Y", GroupCode::TransLastIdxSigGroups),
+            ("-Z", GroupCode::TSPPayload),
+            ("-a", GroupCode::BlindedStateQuadruples),
+            ("-b", GroupCode::BoundStateSextuples),
+        ]
+    }
+
+    fn from_counters(name: &str, counters: Vec<(&str, GroupCodeFn)>, kind: CountKind) -> Self {
+        Self {
+            counters: counters
+                .into_iter()
+                .map(|(code, group)| (code.to_string(), group, kind))
+                .collect(),
+            ..Self::new(name)
+        }
+    }
+}
+
+pub(crate) fn check_counter(code: &str) -> Result<(), Error> {
+    if code.len() != 2 || !code.starts_with('-') || !is_base64(&code[1..]) {
+        return Err(Error::IncorrectLengthError(code.to_string()));
+    }
+    Ok(())
+}
+
+/// Code tables of known genera, by genus code and major version.
+#[derive(Debug, Clone)]
+pub struct GenusRegistry {
+    tables: HashMap<(String, u16), CodeTable>,
+}
+
+impl Default for GenusRegistry {
+    /// Registry of built-in KERI/ACDC and TSP genera.
+    fn default() -> Self {
+        let tables = [
+            ((KERI_GENUS.to_string(), 1), CodeTable::keri_v1()),
+            ((KERI_GENUS.to_string(), 2), CodeTable::keri_v2()),
+            ((TSP_GENUS.to_string(), 1), CodeTable::tsp()),
+        ];
+        Self {
+            tables: tables.into_iter().collect(),
+        }
+    }
+}
+
+impl GenusRegistry {
+    /// Registers code table of genus version. Genus is three Base64 chars,
+    /// without `-_` selector. Replaces table registered before for the same
+    /// genus version.
+    pub fn register(&mut self, genus: &str, major: u16, table: CodeTable) -> Result<(), Error> {
+        if !is_base64(genus) {
+            return Err(Error::NonBase64TextError(genus.to_string()));
+        }
+        if genus.len() != 3 {
+            return Err(Error::IncorrectLengthError(genus.to_string()));
+        }
+        self.tables.insert((genus.to_string(), major), table);
+        Ok(())
+    }
+
+    pub fn table(&self, genus: &str, major: u16) -> Option<&CodeTable> {
+        self.tables.get(&(genus.to_string(), major))
+    }
+
+    /// Returns registered genus versions and their code tables, ordered by
+    /// genus and major version.
+    pub fn tables(&self) -> impl Iterator<Item = (&str, u16, &CodeTable)> {
+        let mut tables: Vec<_> = self
+            .tables
+            .iter()
+            .map(|((genus, major), table)| (genus.as_str(), *major, table))
+            .collect();
+        tables.sort_by_key(|(genus, major, _)| (*genus, *major));
+        tables.into_iter()
+    }
+
+    /// Returns code table selected by parsed genus version code.
+    pub fn table_for(&self, code: &GenusCountCode) -> Option<&CodeTable> {
+        self.table(code.genus(), code.major())
+    }
+}
+
+#[test]
+fn test_code_tables() -> Result<(), Error> {
+    let v1 = CodeTable::keri_v1();
+    let v2 = CodeTable::keri_v2();
+    assert_eq!(
+        v1.group_code("-AAC")?,
+        GroupCode::IndexedControllerSignatures(2)
+    );
+    assert_eq!(
+        v2.group_code("-KAC")?,
+        GroupCode::IndexedControllerSignatures(2)
+    );
+    assert_eq!(v1.group_code("-KAC"), Err(Error::UnknownCodeError));
+
+    let code = GroupCode::TransIndexedSigGroups(1);
+    assert_eq!(v1.counter_code(&code), Some("-F"));
+    assert_eq!(v2.counter_code(&code), Some("-X"));
+    assert_eq!(v1.counter_code(&GroupCode::DigestSealSingles(1)), None);
+    assert_eq!(v1.count_kind(&code), CountKind::Items);
+    assert_eq!(v2.count_kind(&code), CountKind::Quadlets);
+    assert!(v1.knows_counter("-V"));
+    assert!(!v2.knows_counter("-W"));
+    Ok(())
+}
+
+#[test]
+fn test_genus_registry() -> Result<(), Error> {
+    let mut registry = GenusRegistry::default();
+    let keri: GenusCountCode = "_AAACAA".parse()?;
+    assert_eq!(
+        registry.table_for(&keri).map(CodeTable::name),
+        Some("KERI 2.0")
+    );
+    let tsp: GenusCountCode = "_AABBAA".parse()?;
+    assert_eq!(tsp, GenusCountCode::Tsp { minor: 0, major: 1 });
+    assert_eq!(
+        registry.table_for(&tsp).map(CodeTable::name),
+        Some("TSP 1.0")
+    );
+
+    let custom: GenusCountCode = "_XYZBAB".parse()?;
+    assert_eq!(
+        (custom.genus(), custom.major(), custom.minor()),
+        ("XYZ", 1, 1)
+    );
+    assert!(registry.table_for(&custom).is_none());
+
+    let table = CodeTable::new("XYZ 1.0").with_counter(
+        "-s",
+        GroupCode::DigestSealSingles,
+        CountKind::Items,
+    )?;
+    registry.register("XYZ", 1, table)?;
+    let table = registry.table_for(&custom).unwrap();
+    assert_eq!(table.group_code("-sAB")?, GroupCode::DigestSealSingles(1));
+
+    assert!(registry.register("XY", 1, CodeTable::new("")).is_err());
+    assert!(registry.register("X@Z", 1, CodeTable::new("")).is_err());
+    assert!(CodeTable::new("")
+        .with_counter("-", GroupCode::TSPPayload, CountKind::Quadlets)
+        .is_err());
+    Ok(())
+}
@@ -0,0 +1,394 @@
+use std::fmt;
+
+use nom::{
+    branch::alt,
+    combinator::map,
+    error::{make_error, ErrorKind},
+    sequence::tuple,
+};
+
+use crate::{
+    derivation_code::DerivationCode,
+    encode::Encode,
+    error::IResult,
+    primitives::{
+        codes::{
+            number::{write_number, NumberCode},
+            rand_128::Rand128Code,
+            self_addressing::SelfAddressing,
+            special::SpecialCode,
+        },
+        parsers::{number_parser_with, parse_primitive, primitive_with},
+        text::{label_parser_with, Label},
+        Digest, SaltyNounce,
+    },
+};
+#[cfg(feature = "digest")]
+use crate::{error::Error, primitives::Matter};
+
+/// Placeholder char that fills the blinding digest while it is computed.
+#[cfg(feature = "digest")]
+const DUMMY: char = '#';
+
+/// Blinded state of ACDC in transaction event registry: blinding digest,
+/// UUID salt, ACDC SAID and state. Not yet issued ACDC has empty SAID and
+/// state, encoded with `Empty` code.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BlindedState {
+    pub digest: Digest,
+    pub uuid: SaltyNounce,
+    pub acdc: Option<Digest>,
+    pub state: Option<Label>,
+}
+
+/// Blinded state bound to key state of issuee: blinded state with serial
+/// number and digest of issuee key event.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BoundState {
+    pub digest: Digest,
+    pub uuid: SaltyNounce,
+    pub acdc: Option<Digest>,
+    pub state: Option<Label>,
+    pub bound_sn: u128,
+    pub bound_digest: Digest,
+}
+
+impl BlindedState {
+    /// Creates blinded state, computing its blinding digest with hash
+    /// algorithm of the code.
+    #[cfg(feature = "digest")]
+    pub fn new(
+        code: SelfAddressing,
+        uuid: SaltyNounce,
+        acdc: Option<Digest>,
+        state: Option<Label>,
+    ) -> Result<Self, Error> {
+        let mut blinded = Self {
+            // Placeholder, replaced by computed digest
+            digest: Digest::new_unchecked(code.clone(), vec![]),
+            uuid,
+            acdc,
+            state,
+        };
+        blinded.digest = blinding_digest(&code, &blinded)?;
+        Ok(blinded)
+    }
+
+    pub fn to_cesr(&self) -> String {
+        self.to_cesr_string()
+    }
+
+    /// Recomputes blinding digest from revealed fields, with the code of
+    /// current digest.
+    #[cfg(feature = "digest")]
+    pub fn compute_digest(&self) -> Result<Digest, Error> {
+        blinding_digest(self.digest.code(), self)
+    }
+
+    /// Checks if blinding digest matches revealed fields.
+    #[cfg(feature = "digest")]
+    pub fn verify_digest(&self) -> bool {
+        verify_blinding(&self.digest, self)
+    }
+
+    fn revealed_len(&self) -> usize {
+        self.uuid.encoded_len() + optional_len(&self.acdc) + optional_len(&self.state)
+    }
+}
+
+impl Revealed for BlindedState {
+    fn write_revealed<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        self.uuid.write_cesr(writer)?;
+        write_optional(&self.acdc, writer)?;
+        write_optional(&self.state, writer)
+    }
+}
+
+impl Encode for BlindedState {
+    fn encoded_len(&self) -> usize {
+        self.digest.encoded_len() + self.revealed_len()
+    }
+
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        self.digest.write_cesr(writer)?;
+        self.write_revealed(writer)
+    }
+}
+
+impl BoundState {
+    /// Creates bound state, computing its blinding digest with hash algorithm
+    /// of the code.
+    #[cfg(feature = "digest")]
+    pub fn new(
+        code: SelfAddressing,
+        uuid: SaltyNounce,
+        acdc: Option<Digest>,
+        state: Option<Label>,
+        bound_sn: u128,
+        bound_digest: Digest,
+    ) -> Result<Self, Error> {
+        let mut bound = Self {
+            // Placeholder, replaced by computed digest
+            digest: Digest::new_unchecked(code.clone(), vec![]),
+            uuid,
+            acdc,
+            state,
+            bound_sn,
+            bound_digest,
+        };
+        bound.digest = blinding_digest(&code, &bound)?;
+        Ok(bound)
+    }
+
+    pub fn to_cesr(&self) -> String {
+        self.to_cesr_string()
+    }
+
+    /// Recomputes blinding digest from revealed fields, with the code of
+    /// current digest.
+    #[cfg(feature = "digest")]
+    pub fn compute_digest(&self) -> Result<Digest, Error> {
+        blinding_digest(self.digest.code(), self)
+    }
+
+    /// Checks if blinding digest matches revealed fields.
+    #[cfg(feature = "digest")]
+    pub fn verify_digest(&self) -> bool {
+        verify_blinding(&self.digest, self)
+    }
+
+    fn revealed_len(&self) -> usize {
+        self.uuid.encoded_len()
+            + optional_len(&self.acdc)
+            + optional_len(&self.state)
+            + NumberCode::for_value(self.bound_sn).full_size()
+            + self.bound_digest.encoded_len()
+    }
+}
+
+impl Revealed for BoundState {
+    fn write_revealed<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        self.uuid.write_cesr(writer)?;
+        write_optional(&self.acdc, writer)?;
+        write_optional(&self.state, writer)?;
+        write_number(self.bound_sn, writer)?;
+        self.bound_digest.write_cesr(writer)
+    }
+}
+
+impl Encode for BoundState {
+    fn encoded_len(&self) -> usize {
+        self.digest.encoded_len() + self.revealed_len()
+    }
+
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        self.digest.write_cesr(writer)?;
+        self.write_revealed(writer)
+    }
+}
+
+/// Fields of blinded state that follow blinding digest.
+trait Revealed {
+    fn write_revealed<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result;
+}
+
+/// Digest is computed over CESR text of all fields, with blinding digest
+/// replaced by dummy chars of the same length.
+#[cfg(feature = "digest")]
+fn blinding_digest(code: &SelfAddressing, state: &impl Revealed) -> Result<Digest, Error> {
+    let mut text: String = std::iter::repeat_n(DUMMY, code.full_size()).collect();
+    state.write_revealed(&mut text)?;
+    Digest::new(code.clone(), code.digest(text.as_bytes()))
+}
+
+#[cfg(feature = "digest")]
+fn verify_blinding(digest: &Digest, state: &impl Revealed) -> bool {
+    blinding_digest(digest.code(), state).is_ok_and(|computed| &computed == digest)
+}
+
+/// Size of optional field, that is encoded with `Empty` code if missing.
+fn optional_len(field: &Option<impl Encode>) -> usize {
+    match field {
+        Some(field) => field.encoded_len(),
+        None => SpecialCode::Empty.full_size(),
+    }
+}
+
+fn write_optional<W: fmt::Write + ?Sized>(
+    field: &Option<impl Encode>,
+    writer: &mut W,
+) -> fmt::Result {
+    match field {
+        Some(field) => field.write_cesr(writer),
+        None => SpecialCode::Empty.write_code(writer),
+    }
+}
+
+fn empty(s: &str) -> IResult<&str, ()> {
+    match parse_primitive::<SpecialCode>(s)? {
+        (rest, (SpecialCode::Empty, _)) => Ok((rest, ())),
+        _ => Err(nom::Err::Error(make_error(s, ErrorKind::Tag))),
+    }
+}
+
+fn optional_digest(strict: bool) -> impl Fn(&str) -> IResult<&str, Option<Digest>> {
+    move |s| {
+        alt((
+            map(empty, |_| None),
+            map(primitive_with::<SelfAddressing>(strict), Some),
+        ))(s)
+    }
+}
+
+fn optional_label(strict: bool) -> impl Fn(&str) -> IResult<&str, Option<Label>> {
+    move |s| alt((map(empty, |_| None), map(label_parser_with(strict), Some)))(s)
+}
+
+pub fn blinded_state(s: &str) -> IResult<&str, BlindedState> {
+    blinded_state_with(true)(s)
+}
+
+pub fn bound_state(s: &str) -> IResult<&str, BoundState> {
+    bound_state_with(true)(s)
+}
+
+/// Parses blinded state, accepting text with non-zero lead bits unless
+/// `strict`.
+pub(crate) fn blinded_state_with(strict: bool) -> impl Fn(&str) -> IResult<&str, BlindedState> {
+    move |s| {
+        let (rest, (digest, uuid, acdc, state)) = tuple((
+            primitive_with::<SelfAddressing>(strict),
+            primitive_with::<Rand128Code>(strict),
+            optional_digest(strict),
+            optional_label(strict),
+        ))(s)?;
+        Ok((
+            rest,
+            BlindedState {
+                digest,
+                uuid,
+                acdc,
+                state,
+            },
+        ))
+    }
+}
+
+/// Parses bound state, accepting text with non-zero lead bits unless
+/// `strict`.
+pub(crate) fn bound_state_with(strict: bool) -> impl Fn(&str) -> IResult<&str, BoundState> {
+    move |s| {
+        let (rest, (digest, uuid, acdc, state, bound_sn, bound_digest)) = tuple((
+            primitive_with::<SelfAddressing>(strict),
+            primitive_with::<Rand128Code>(strict),
+            optional_digest(strict),
+            optional_label(strict),
+            number_parser_with(strict),
+            primitive_with::<SelfAddressing>(strict),
+        ))(s)?;
+        Ok((
+            rest,
+            BoundState {
+                digest,
+                uuid,
+                acdc,
+                state,
+                bound_sn,
+                bound_digest,
+            },
+        ))
+    }
+}
+
+#[cfg(all(test, feature = "digest"))]
+mod tests {
+    use crate::{
+        group::{parsers::parse_group, Group},
+        primitives::{
+            codes::{rand_128::Rand128Code, self_addressing::SelfAddressing},
+            text::Label,
+            Digest, SaltyNounce,
+        },
+    };
+
+    use super::{BlindedState, BoundState};
+
+    #[test]
+    fn test_blinded_states() {
+        let acdc = Digest::new(
+            SelfAddressing::Blake3_256,
+            SelfAddressing::Blake3_256.digest(b"acdc"),
+        )
+        .unwrap();
+        let issued = BlindedState::new(
+            SelfAddressing::Blake3_256,
+            SaltyNounce::new(Rand128Code, vec![1; 16]).unwrap(),
+            Some(acdc),
+            Some(Label::new("issued").unwrap()),
+        )
+        .unwrap();
+        let placeholder = BlindedState::new(
+            SelfAddressing::Blake3_256,
+            SaltyNounce::new(Rand128Code, vec![2; 16]).unwrap(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(issued.verify_digest());
+        assert!(placeholder.verify_digest());
+        assert!(placeholder.to_cesr().ends_with("1AAP1AAP"));
+
+        // Digest of other algorithm has size of its code
+        let sha3 = BlindedState::new(
+            SelfAddressing::SHA3_512,
+            SaltyNounce::new(Rand128Code, vec![2; 16]).unwrap(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(sha3.verify_digest());
+        assert!(sha3.to_cesr().starts_with("0E"));
+
+        let group = Group::BlindedStates(vec![issued.clone(), placeholder]);
+        let cesr = group.to_cesr_str();
+        assert!(cesr.starts_with("-aAC"));
+        let (rest, parsed) = parse_group(&cesr).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parsed, group);
+
+        // Revealed state doesn't match blinding digest
+        let mut revoked = issued;
+        revoked.state = Some(Label::new("revoked").unwrap());
+        assert!(!revoked.verify_digest());
+    }
+
+    #[test]
+    fn test_bound_states() {
+        let key_event = Digest::new(
+            SelfAddressing::Blake3_256,
+            SelfAddressing::Blake3_256.digest(b"kel"),
+        )
+        .unwrap();
+        let bound = BoundState::new(
+            SelfAddressing::Blake3_256,
+            SaltyNounce::new(Rand128Code, vec![3; 16]).unwrap(),
+            None,
+            None,
+            5,
+            key_event,
+        )
+        .unwrap();
+        assert!(bound.verify_digest());
+
+        let group = Group::BoundStates(vec![bound.clone()]);
+        let cesr = group.to_cesr_str();
+        assert!(cesr.starts_with("-bAB"));
+        let (rest, parsed) = parse_group(&cesr).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parsed, group);
+
+        let mut rebound = bound;
+        rebound.bound_sn = 6;
+        assert!(!rebound.verify_digest());
+    }
+}
@@ -1,23 +1,118 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use crate::{
-    conversion::{adjust_with_num, b64_to_num},
+    config::ParserConfig,
+    conversion::{b64_to_u64, write_b64_num},
     derivation_code::DerivationCode,
     error::Error,
+    universal_codes::UniversalGroupCode,
 };
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum GroupCode {
-    IndexedControllerSignatures(u16),
-    IndexedWitnessSignatures(u16),
-    NontransferableReceiptCouples(u16),
-    FirstSeenReplyCouples(u16),
+    IndexedControllerSignatures(u32),
+    IndexedWitnessSignatures(u32),
+    NontransferableReceiptCouples(u32),
+    // Composed Base64 quadruple, pre+snu+dig+sig of transferable receipt
+    TransReceiptQuadruples(u32),
+    FirstSeenReplyCouples(u32),
     // Composed Base64 couple, snu+dig of given delegators or issuers event
-    SealSourceCouples(u16),
-    AnchoringEventSeals(u16),
+    SealSourceCouples(u32),
+    // Digest seal singles, dig of sealed data
+    DigestSealSingles(u32),
+    // Merkle tree root digest seal singles
+    MerkleRootSealSingles(u32),
+    AnchoringEventSeals(u32),
+    // Last establishment event seal singles, pre of sealed identifier
+    SealSourceLastSingles(u32),
+    // Backer registrar seal couples, brid+dig of backer metadata
+    BackerRegistrarSealCouples(u32),
+    // Composed Base64 group, pre+snu+dig+controller indexed signatures group
+    TransIndexedSigGroups(u32),
+    // Composed Base64 group, pre+controller indexed signatures group
+    TransLastIdxSigGroups(u32),
     #[cfg(feature = "cesr-proof")]
-    PathedMaterialQuadruple(u16),
-    TSPPayload(u16),
+    PathedMaterialQuadruple(u32),
+    // Blinded state quadruples, dig+uuid+said+state of ACDC registry entry
+    BlindedStateQuadruples(u32),
+    // Bound state sextuples, dig+uuid+said+state+bsn+bdig of ACDC registry entry
+    BoundStateSextuples(u32),
+    TSPPayload(u32),
+}
+
+impl GroupCode {
+    /// Count of group code: number of items, or of quadlets for groups framed
+    /// in quadlets.
+    pub fn count(&self) -> u32 {
+        match self {
+            GroupCode::IndexedControllerSignatures(count)
+            | GroupCode::IndexedWitnessSignatures(count)
+            | GroupCode::NontransferableReceiptCouples(count)
+            | GroupCode::TransReceiptQuadruples(count)
+            | GroupCode::FirstSeenReplyCouples(count)
+            | GroupCode::SealSourceCouples(count)
+            | GroupCode::DigestSealSingles(count)
+            | GroupCode::MerkleRootSealSingles(count)
+            | GroupCode::AnchoringEventSeals(count)
+            | GroupCode::SealSourceLastSingles(count)
+            | GroupCode::BackerRegistrarSealCouples(count)
+            | GroupCode::TransIndexedSigGroups(count)
+            | GroupCode::TransLastIdxSigGroups(count)
+            | GroupCode::BlindedStateQuadruples(count)
+            | GroupCode::BoundStateSextuples(count)
+            | GroupCode::TSPPayload(count) => *count,
+            #[cfg(feature = "cesr-proof")]
+            GroupCode::PathedMaterialQuadruple(count) => *count,
+        }
+    }
+
+    /// Name of group, as its variant is named.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GroupCode::IndexedControllerSignatures(_) => "IndexedControllerSignatures",
+            GroupCode::IndexedWitnessSignatures(_) => "IndexedWitnessSignatures",
+            GroupCode::NontransferableReceiptCouples(_) => "NontransferableReceiptCouples",
+            GroupCode::TransReceiptQuadruples(_) => "TransReceiptQuadruples",
+            GroupCode::FirstSeenReplyCouples(_) => "FirstSeenReplyCouples",
+            GroupCode::DigestSealSingles(_) => "DigestSealSingles",
+            GroupCode::MerkleRootSealSingles(_) => "MerkleRootSealSingles",
+            GroupCode::AnchoringEventSeals(_) => "AnchoringEventSeals",
+            GroupCode::SealSourceCouples(_) => "SealSourceCouples",
+            GroupCode::SealSourceLastSingles(_) => "SealSourceLastSingles",
+            GroupCode::BackerRegistrarSealCouples(_) => "BackerRegistrarSealCouples",
+            GroupCode::TransIndexedSigGroups(_) => "TransIndexedSigGroups",
+            GroupCode::TransLastIdxSigGroups(_) => "TransLastIdxSigGroups",
+            #[cfg(feature = "cesr-proof")]
+            GroupCode::PathedMaterialQuadruple(_) => "PathedMaterialQuadruple",
+            GroupCode::TSPPayload(_) => "TSPPayload",
+            GroupCode::BlindedStateQuadruples(_) => "BlindedStateQuadruples",
+            GroupCode::BoundStateSextuples(_) => "BoundStateSextuples",
+        }
+    }
+
+    /// Two chars hard part of counter.
+    pub(crate) fn hard_code(&self) -> &'static str {
+        match self {
+            GroupCode::IndexedControllerSignatures(_) => "-K",
+            GroupCode::IndexedWitnessSignatures(_) => "-L",
+            GroupCode::NontransferableReceiptCouples(_) => "-M",
+            GroupCode::TransReceiptQuadruples(_) => "-N",
+            GroupCode::FirstSeenReplyCouples(_) => "-O",
+            GroupCode::DigestSealSingles(_) => "-Q",
+            GroupCode::MerkleRootSealSingles(_) => "-R",
+            GroupCode::AnchoringEventSeals(_) => "-S",
+            GroupCode::SealSourceCouples(_) => "-T",
+            GroupCode::SealSourceLastSingles(_) => "-U",
+            GroupCode::BackerRegistrarSealCouples(_) => "-V",
+            GroupCode::TransIndexedSigGroups(_) => "-X",
+            GroupCode::TransLastIdxSigGroups(_) => "-Y",
+            #[cfg(feature = "cesr-proof")]
+            GroupCode::PathedMaterialQuadruple(_) => "-P",
+            GroupCode::TSPPayload(_) => "-Z",
+            GroupCode::BlindedStateQuadruples(_) => "-a",
+            GroupCode::BoundStateSextuples(_) => "-b",
+        }
+    }
 }
 
 impl DerivationCode for GroupCode {
@@ -25,27 +120,29 @@ impl DerivationCode for GroupCode {
         0
     }
 
+    /// Five chars count of big counter, two chars otherwise.
     fn soft_size(&self) -> usize {
-        2
+        counter_len(self.count() as usize) - self.hard_size()
     }
 
+    /// Hard part of big counter starts with `--`.
     fn hard_size(&self) -> usize {
-        2
+        match counter_len(self.count() as usize) {
+            4 => 2,
+            _ => 3,
+        }
     }
 
     fn to_str(&self) -> String {
-        let (code, count) = match self {
-            GroupCode::IndexedControllerSignatures(count) => ("-K", count),
-            GroupCode::IndexedWitnessSignatures(count) => ("-L", count),
-            GroupCode::NontransferableReceiptCouples(count) => ("-M", count),
-            GroupCode::FirstSeenReplyCouples(count) => ("-O", count),
-            GroupCode::AnchoringEventSeals(count) => ("-S", count),
-            GroupCode::SealSourceCouples(count) => ("-T", count),
-            #[cfg(feature = "cesr-proof")]
-            GroupCode::PathedMaterialQuadruple(len) => ("-P", len),
-            GroupCode::TSPPayload(len) => ("-Z", len),
-        };
-        [code, &adjust_with_num(count.to_owned(), self.soft_size())].join("")
+        let mut code = String::with_capacity(self.code_size());
+        // Only hard part is written if count doesn't fit into big counter
+        let _ = self.write_code(&mut code);
+        code
+    }
+
+    /// Fails if count doesn't fit into big counter.
+    fn write_code<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        write_counter_code(self.hard_code(), self.count() as u64, writer)
     }
 }
 
@@ -53,31 +150,109 @@ impl FromStr for GroupCode {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let code = s.get(..2).ok_or(Error::EmptyCodeError)?;
-        let count_part = s.get(2..4).ok_or(Error::EmptyCodeError)?;
-        let count = b64_to_num(count_part)?;
+        let (code, count) = split_counter(s)?;
+        let count = count as u32;
         match code {
             "-K" => Ok(Self::IndexedControllerSignatures(count)),
             "-L" => Ok(Self::IndexedWitnessSignatures(count)),
             "-M" => Ok(Self::NontransferableReceiptCouples(count)),
-            "-N" => todo!(),
+            "-N" => Ok(Self::TransReceiptQuadruples(count)),
             "-O" => Ok(Self::FirstSeenReplyCouples(count)),
             #[cfg(feature = "cesr-proof")]
             "-P" => Ok(Self::PathedMaterialQuadruple(count)),
-            "-R" => todo!(),
+            "-Q" => Ok(Self::DigestSealSingles(count)),
+            "-R" => Ok(Self::MerkleRootSealSingles(count)),
             "-S" => Ok(Self::AnchoringEventSeals(count)),
             "-T" => Ok(Self::SealSourceCouples(count)),
-            "-U" => todo!(),
-            "-V" => todo!(),
-            "-W" => todo!(),
-            "-X" => todo!(),
-            "-Y" => todo!(),
+            "-U" => Ok(Self::SealSourceLastSingles(count)),
+            "-V" => Ok(Self::BackerRegistrarSealCouples(count)),
+            "-X" => Ok(Self::TransIndexedSigGroups(count)),
+            "-Y" => Ok(Self::TransLastIdxSigGroups(count)),
             "-Z" => Ok(Self::TSPPayload(count)),
+            "-a" => Ok(Self::BlindedStateQuadruples(count)),
+            "-b" => Ok(Self::BoundStateSextuples(count)),
             _ => Err(Error::UnknownCodeError),
         }
     }
 }
 
+/// Largest count of small counter, with two chars count.
+pub(crate) const SMALL_COUNT_MAX: usize = 64usize.pow(2) - 1;
+/// Largest count of big counter, with five chars count.
+pub(crate) const BIG_COUNT_MAX: usize = 64usize.pow(5) - 1;
+
+/// Size in chars of counter of given count. Counter takes big form, with
+/// `--` selector and five chars count, if count doesn't fit into two chars.
+pub(crate) fn counter_len(count: usize) -> usize {
+    if count <= SMALL_COUNT_MAX {
+        4
+    } else {
+        8
+    }
+}
+
+/// Writes counter of two chars hard part, in small or big form, as count
+/// requires. Fails if count doesn't fit into big counter.
+pub(crate) fn write_counter_code<W: fmt::Write + ?Sized>(
+    hard: &str,
+    count: u64,
+    writer: &mut W,
+) -> fmt::Result {
+    if count as usize <= SMALL_COUNT_MAX {
+        writer.write_str(hard)?;
+        write_b64_num(count, 2, writer)
+    } else {
+        writer.write_char('-')?;
+        writer.write_str(hard)?;
+        write_b64_num(count, 5, writer)
+    }
+}
+
+/// Size in chars of counter that starts text: 8 for big counter, that
+/// starts with `--`, and 4 otherwise.
+pub(crate) fn counter_size(s: &str) -> usize {
+    if s.starts_with("--") {
+        8
+    } else {
+        4
+    }
+}
+
+/// Splits counter text into two chars hard part, without `-` of big form,
+/// and count.
+pub(crate) fn split_counter(s: &str) -> Result<(&str, u64), Error> {
+    let (code, count) = match s.strip_prefix('-').filter(|big| big.starts_with('-')) {
+        Some(big) => (big.get(..2), big.get(2..7)),
+        None => (s.get(..2), s.get(2..4)),
+    };
+    let (Some(code), Some(count)) = (code, count) else {
+        return Err(Error::EmptyCodeError);
+    };
+    Ok((code, b64_to_u64(count)?))
+}
+
+/// Returns code and value sizes of counter unknown to this crate. Count of
+/// CESR 2.0 counters is number of quadlets, so sizes follow from the code
+/// alone: `-` with one char hard part and two char count, or `--` with one
+/// char hard part and five char count.
+pub(crate) fn unknown_counter_size(s: &str, config: &ParserConfig) -> Option<(usize, usize)> {
+    if !s.starts_with('-') || s.starts_with("-_") {
+        return None;
+    }
+    let code_len = counter_size(s);
+    let code = s.get(..code_len)?;
+    let (hard, quadlets) = split_counter(code).ok()?;
+    let known = match config.code_table() {
+        // Code tables without universal codes have no big counters
+        Some(table) => table.knows_counter(hard) && (code_len == 4 || table.has_universal_codes()),
+        None => GroupCode::from_str(code).is_ok(),
+    };
+    if known || (config.universal_codes() && UniversalGroupCode::from_str(&code[1..]).is_ok()) {
+        return None;
+    }
+    Some((code_len, quadlets as usize * 4))
+}
+
 #[test]
 pub fn test_group_codes_to_str() -> Result<(), Error> {
     assert_eq!(GroupCode::IndexedControllerSignatures(3).to_str(), "-KAD");
@@ -89,6 +264,15 @@ pub fn test_group_codes_to_str() -> Result<(), Error> {
     assert_eq!(GroupCode::FirstSeenReplyCouples(127).to_str(), "-OB_");
     assert_eq!(GroupCode::AnchoringEventSeals(4095).to_str(), "-S__");
     assert_eq!(GroupCode::SealSourceCouples(0).to_str(), "-TAA");
+    assert_eq!(GroupCode::TransReceiptQuadruples(1).to_str(), "-NAB");
+    assert_eq!(GroupCode::TransIndexedSigGroups(2).to_str(), "-XAC");
+    assert_eq!(GroupCode::TransLastIdxSigGroups(64).to_str(), "-YBA");
+    assert_eq!(GroupCode::DigestSealSingles(1).to_str(), "-QAB");
+    assert_eq!(GroupCode::MerkleRootSealSingles(2).to_str(), "-RAC");
+    assert_eq!(GroupCode::SealSourceLastSingles(3).to_str(), "-UAD");
+    assert_eq!(GroupCode::BackerRegistrarSealCouples(4).to_str(), "-VAE");
+    assert_eq!(GroupCode::BlindedStateQuadruples(5).to_str(), "-aAF");
+    assert_eq!(GroupCode::BoundStateSextuples(6).to_str(), "-bAG");
     Ok(())
 }
 
@@ -103,5 +287,26 @@ pub fn test_group_codes_from_str() -> Result<(), Error> {
     assert_eq!(GroupCode::AnchoringEventSeals(4095), "-S__".parse()?);
     assert_eq!(GroupCode::FirstSeenReplyCouples(127), "-OB_".parse()?);
     assert_eq!(GroupCode::SealSourceCouples(0), "-TAA".parse()?);
+    assert_eq!(GroupCode::TransReceiptQuadruples(1), "-NAB".parse()?);
+    assert_eq!(GroupCode::TransIndexedSigGroups(2), "-XAC".parse()?);
+    assert_eq!(GroupCode::TransLastIdxSigGroups(64), "-YBA".parse()?);
+    assert_eq!(GroupCode::DigestSealSingles(1), "-QAB".parse()?);
+    assert_eq!(GroupCode::MerkleRootSealSingles(2), "-RAC".parse()?);
+    assert_eq!(GroupCode::SealSourceLastSingles(3), "-UAD".parse()?);
+    assert_eq!(GroupCode::BackerRegistrarSealCouples(4), "-VAE".parse()?);
+    assert_eq!(GroupCode::BlindedStateQuadruples(5), "-aAF".parse()?);
+    assert_eq!(GroupCode::BoundStateSextuples(6), "-bAG".parse()?);
+    Ok(())
+}
+
+#[test]
+pub fn test_big_group_codes() -> Result<(), Error> {
+    let code = GroupCode::IndexedControllerSignatures(4096);
+    assert_eq!(code.to_str(), "--KAABAA");
+    assert_eq!(code.code_size(), 8);
+    assert_eq!(code, "--KAABAA".parse()?);
+    assert_eq!(GroupCode::AnchoringEventSeals(4095).to_str(), "-S__");
+    assert_eq!(GroupCode::TSPPayload(64u32.pow(5) - 1), "--Z_____".parse()?);
+    assert!("--KAAB".parse::<GroupCode>().is_err());
     Ok(())
 }
@@ -1,22 +1,36 @@
+pub mod blind;
 pub mod codes;
 pub mod parsers;
 
+use std::fmt;
+
 use crate::{
     derivation_code::DerivationCode,
+    encode::Encode,
+    error::Error,
+    genus::{CodeTable, CountKind, GroupCodeFn},
     primitives::{
-        codes::{rand_128::pack_sn, timestamp::pack_datetime, TagCode},
+        codes::{
+            rand_128::{write_sn, Rand128Code},
+            timestamp::{write_datetime, TimestampCode},
+            TagCode,
+        },
         Identifier, SaltyNounce,
     },
+    seal::{
+        DigestSeal, EventSeal, LastEstablishmentSeal, MerkleRootSeal, RegistrarSeal, SourceSeal,
+    },
     value::Value,
 };
 
-use self::codes::GroupCode;
+use self::{
+    blind::{BlindedState, BoundState},
+    codes::{counter_len, write_counter_code, GroupCode, BIG_COUNT_MAX, SMALL_COUNT_MAX},
+};
 
 #[cfg(feature = "cesr-proof")]
 use super::cesr_proof::MaterialPath;
-use super::primitives::{
-    AnchoringEventSeal, CesrPrimitive, Digest, IndexedSignature, PublicKey, Signature, Timestamp,
-};
+use super::primitives::{Digest, IndexedSignature, PublicKey, Signature, Timestamp};
 
 #[derive(Debug, PartialEq)]
 pub struct TSPPayload {
@@ -32,76 +46,547 @@ pub enum Group {
     IndexedControllerSignatures(Vec<IndexedSignature>),
     IndexedWitnessSignatures(Vec<IndexedSignature>),
     NontransReceiptCouples(Vec<(PublicKey, Signature)>),
-    SourceSealCouples(Vec<(u64, Digest)>),
+    TransReceiptQuadruples(Vec<(Identifier, u64, Digest, IndexedSignature)>),
+    SourceSealCouples(Vec<SourceSeal>),
     FirstSeenReplyCouples(Vec<(u64, Timestamp)>),
-    AnchoringSeals(Vec<AnchoringEventSeal>),
+    DigestSeals(Vec<DigestSeal>),
+    MerkleRootSeals(Vec<MerkleRootSeal>),
+    AnchoringSeals(Vec<EventSeal>),
+    LastEstablishmentSeals(Vec<LastEstablishmentSeal>),
+    RegistrarSeals(Vec<RegistrarSeal>),
+    TransIndexedSigGroups(Vec<(Identifier, u64, Digest, Vec<IndexedSignature>)>),
+    TransLastIdxSigGroups(Vec<(Identifier, Vec<IndexedSignature>)>),
     #[cfg(feature = "cesr-proof")]
     PathedMaterialQuadruplet(MaterialPath, Vec<Group>),
     TSPPayload(Vec<Value>),
+    BlindedStates(Vec<BlindedState>),
+    BoundStates(Vec<BoundState>),
 }
 
 impl Group {
     pub fn to_cesr_str(&self) -> String {
-        let (code, value) = match self {
-            Group::IndexedControllerSignatures(sigs) => (
-                GroupCode::IndexedControllerSignatures(sigs.len() as u16),
-                sigs.iter()
-                    .fold("".into(), |acc, s| [acc, s.to_str()].join("")),
-            ),
-            Group::IndexedWitnessSignatures(sigs) => (
-                GroupCode::IndexedWitnessSignatures(sigs.len() as u16),
-                sigs.iter()
-                    .fold("".into(), |acc, s| [acc, s.to_str()].join("")),
-            ),
+        self.to_cesr_string()
+    }
+
+    /// Encodes group with counters of given code table, counting its content
+    /// the way the table does.
+    pub fn to_cesr_str_with(&self, table: &CodeTable) -> Result<String, Error> {
+        self.encode(Some(table))
+    }
+
+    pub(crate) fn encode(&self, table: Option<&CodeTable>) -> Result<String, Error> {
+        let mut text = String::with_capacity(self.encoded_len());
+        self.write_with(table, &mut text)?;
+        Ok(text)
+    }
+
+    /// Name of group code, as in code tables.
+    pub(crate) fn name(&self) -> &'static str {
+        (self.code().0)(0).name()
+    }
+
+    /// Group code and number of items, `None` for groups always counted in
+    /// quadlets.
+    fn code(&self) -> (GroupCodeFn, Option<usize>) {
+        match self {
+            Group::IndexedControllerSignatures(sigs) => {
+                (GroupCode::IndexedControllerSignatures, Some(sigs.len()))
+            }
+            Group::IndexedWitnessSignatures(sigs) => {
+                (GroupCode::IndexedWitnessSignatures, Some(sigs.len()))
+            }
             Group::NontransReceiptCouples(couples) => (
-                GroupCode::NontransferableReceiptCouples(couples.len() as u16),
-                couples
-                    .iter()
-                    .fold("".into(), |acc, (identifeir, signature)| {
-                        [acc, identifeir.to_str(), signature.to_str()].join("")
-                    }),
-            ),
-            Group::SourceSealCouples(quadruple) => (
-                GroupCode::SealSourceCouples(quadruple.len() as u16),
-                quadruple.iter().fold("".into(), |acc, (sn, digest)| {
-                    [acc, pack_sn(*sn), digest.to_str()].join("")
-                }),
-            ),
-            Group::FirstSeenReplyCouples(couples) => (
-                GroupCode::FirstSeenReplyCouples(couples.len() as u16),
-                couples.iter().fold("".into(), |acc, (sn, dt)| {
-                    [acc, pack_sn(*sn), pack_datetime(dt)].join("")
-                }),
-            ),
-            Group::AnchoringSeals(groups) => (
-                GroupCode::AnchoringEventSeals(groups.len() as u16),
-                groups
-                    .iter()
-                    .fold("".into(), |acc, (identifier, sn, digest)| {
-                        [acc, identifier.to_str(), pack_sn(*sn), digest.to_str()].join("")
-                    }),
+                GroupCode::NontransferableReceiptCouples,
+                Some(couples.len()),
             ),
+            Group::TransReceiptQuadruples(quadruples) => {
+                (GroupCode::TransReceiptQuadruples, Some(quadruples.len()))
+            }
+            Group::SourceSealCouples(seals) => (GroupCode::SealSourceCouples, Some(seals.len())),
+            Group::FirstSeenReplyCouples(couples) => {
+                (GroupCode::FirstSeenReplyCouples, Some(couples.len()))
+            }
+            Group::DigestSeals(seals) => (GroupCode::DigestSealSingles, Some(seals.len())),
+            Group::MerkleRootSeals(seals) => (GroupCode::MerkleRootSealSingles, Some(seals.len())),
+            Group::AnchoringSeals(seals) => (GroupCode::AnchoringEventSeals, Some(seals.len())),
+            Group::LastEstablishmentSeals(seals) => {
+                (GroupCode::SealSourceLastSingles, Some(seals.len()))
+            }
+            Group::RegistrarSeals(seals) => {
+                (GroupCode::BackerRegistrarSealCouples, Some(seals.len()))
+            }
+            Group::TransIndexedSigGroups(groups) => {
+                (GroupCode::TransIndexedSigGroups, Some(groups.len()))
+            }
+            Group::TransLastIdxSigGroups(groups) => {
+                (GroupCode::TransLastIdxSigGroups, Some(groups.len()))
+            }
+            #[cfg(feature = "cesr-proof")]
+            Group::PathedMaterialQuadruplet(_, _) => (GroupCode::PathedMaterialQuadruple, None),
+            Group::TSPPayload(_) => (GroupCode::TSPPayload, None),
+            Group::BlindedStates(states) => (GroupCode::BlindedStateQuadruples, Some(states.len())),
+            Group::BoundStates(states) => (GroupCode::BoundStateSextuples, Some(states.len())),
+        }
+    }
+
+    /// Size in chars of group content with native counters, without
+    /// counter of the group itself.
+    fn content_len(&self) -> usize {
+        match self {
+            Group::IndexedControllerSignatures(sigs) | Group::IndexedWitnessSignatures(sigs) => {
+                items_len(sigs)
+            }
+            Group::NontransReceiptCouples(couples) => couples
+                .iter()
+                .map(|(key, signature)| key.encoded_len() + signature.encoded_len())
+                .sum(),
+            Group::TransReceiptQuadruples(quadruples) => quadruples
+                .iter()
+                .map(|(identifier, _sn, digest, signature)| {
+                    identifier.encoded_len()
+                        + Rand128Code.full_size()
+                        + digest.encoded_len()
+                        + signature.encoded_len()
+                })
+                .sum(),
+            Group::SourceSealCouples(seals) => items_len(seals),
+            Group::FirstSeenReplyCouples(couples) => {
+                couples.len() * (Rand128Code.full_size() + TimestampCode.full_size())
+            }
+            Group::DigestSeals(seals) => items_len(seals),
+            Group::MerkleRootSeals(seals) => items_len(seals),
+            Group::AnchoringSeals(seals) => items_len(seals),
+            Group::LastEstablishmentSeals(seals) => items_len(seals),
+            Group::RegistrarSeals(seals) => items_len(seals),
+            Group::TransIndexedSigGroups(groups) => groups
+                .iter()
+                .map(|(identifier, _sn, digest, signatures)| {
+                    identifier.encoded_len()
+                        + Rand128Code.full_size()
+                        + digest.encoded_len()
+                        + counter_len(signatures.len())
+                        + items_len(signatures)
+                })
+                .sum(),
+            Group::TransLastIdxSigGroups(groups) => groups
+                .iter()
+                .map(|(identifier, signatures)| {
+                    identifier.encoded_len() + counter_len(signatures.len()) + items_len(signatures)
+                })
+                .sum(),
             #[cfg(feature = "cesr-proof")]
             Group::PathedMaterialQuadruplet(path, attachments) => {
-                let attachments = attachments
-                    .iter()
-                    .map(|s| s.to_cesr_str())
-                    .fold(String::new(), |a, b| a + &b);
-                let attached_text = path.to_cesr() + &attachments;
-                (
-                    GroupCode::PathedMaterialQuadruple((attached_text.len() / 4) as u16),
-                    attached_text,
-                )
-            }
-            Group::TSPPayload(tsp_payload) => {
-                let data_str = tsp_payload
-                    .iter()
-                    .map(|value| value.to_string())
-                    .collect::<Vec<_>>()
-                    .concat();
-                (GroupCode::TSPPayload((data_str.len() / 4) as u16), data_str)
+                path.encoded_len() + items_len(attachments)
             }
+            Group::TSPPayload(values) => items_len(values),
+            Group::BlindedStates(states) => items_len(states),
+            Group::BoundStates(states) => items_len(states),
+        }
+    }
+
+    /// Writes group with counters of code table, or with native counters if
+    /// there's none.
+    pub(crate) fn write_with<W: fmt::Write + ?Sized>(
+        &self,
+        table: Option<&CodeTable>,
+        writer: &mut W,
+    ) -> Result<(), Error> {
+        let (code, items) = self.code();
+        match table {
+            // Nested counters of code table that counts quadlets may be of
+            // other size than native ones, so content is written first
+            Some(table) if items.is_none() || table.count_kind(&code(0)) == CountKind::Quadlets => {
+                let mut content = String::with_capacity(self.content_len());
+                self.write_content(Some(table), &mut content)?;
+                write_counter(Some(table), code, items, content.len(), writer)?;
+                Ok(writer.write_str(&content)?)
+            }
+            _ => {
+                write_counter(table, code, items, self.content_len(), writer)?;
+                self.write_content(table, writer)
+            }
+        }
+    }
+
+    fn write_content<W: fmt::Write + ?Sized>(
+        &self,
+        table: Option<&CodeTable>,
+        writer: &mut W,
+    ) -> Result<(), Error> {
+        match self {
+            Group::IndexedControllerSignatures(sigs) | Group::IndexedWitnessSignatures(sigs) => {
+                write_items(sigs, writer)?
+            }
+            Group::NontransReceiptCouples(couples) => {
+                for (key, signature) in couples {
+                    key.write_cesr(writer)?;
+                    signature.write_cesr(writer)?;
+                }
+            }
+            Group::TransReceiptQuadruples(quadruples) => {
+                for (identifier, sn, digest, signature) in quadruples {
+                    identifier.write_cesr(writer)?;
+                    write_sn(*sn, writer)?;
+                    digest.write_cesr(writer)?;
+                    signature.write_cesr(writer)?;
+                }
+            }
+            Group::SourceSealCouples(seals) => write_items(seals, writer)?,
+            Group::FirstSeenReplyCouples(couples) => {
+                for (sn, dt) in couples {
+                    write_sn(*sn, writer)?;
+                    write_datetime(dt, writer)?;
+                }
+            }
+            Group::DigestSeals(seals) => write_items(seals, writer)?,
+            Group::MerkleRootSeals(seals) => write_items(seals, writer)?,
+            Group::AnchoringSeals(seals) => write_items(seals, writer)?,
+            Group::LastEstablishmentSeals(seals) => write_items(seals, writer)?,
+            Group::RegistrarSeals(seals) => write_items(seals, writer)?,
+            Group::TransIndexedSigGroups(groups) => {
+                for (identifier, sn, digest, signatures) in groups {
+                    identifier.write_cesr(writer)?;
+                    write_sn(*sn, writer)?;
+                    digest.write_cesr(writer)?;
+                    write_signatures(table, signatures, writer)?;
+                }
+            }
+            Group::TransLastIdxSigGroups(groups) => {
+                for (identifier, signatures) in groups {
+                    identifier.write_cesr(writer)?;
+                    write_signatures(table, signatures, writer)?;
+                }
+            }
+            #[cfg(feature = "cesr-proof")]
+            Group::PathedMaterialQuadruplet(path, attachments) => {
+                path.write_cesr(writer)?;
+                for attachment in attachments {
+                    attachment.write_with(table, writer)?;
+                }
+            }
+            Group::TSPPayload(values) => {
+                for value in values {
+                    value.write_with(table, writer)?;
+                }
+            }
+            Group::BlindedStates(states) => write_items(states, writer)?,
+            Group::BoundStates(states) => write_items(states, writer)?,
         };
-        [code.to_str(), value].concat()
+        Ok(())
+    }
+}
+
+impl Encode for Group {
+    fn encoded_len(&self) -> usize {
+        let content_len = self.content_len();
+        let count = self.code().1.unwrap_or(content_len / 4);
+        counter_len(count) + content_len
+    }
+
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        // Native code table defines all groups, so only writing can fail
+        self.write_with(None, writer).map_err(|_| fmt::Error)
+    }
+}
+
+impl TryFrom<&Value> for Group {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::SpecificGroup(group) => Ok(group.clone()),
+            other => Err(other.unexpected("specific group")),
+        }
+    }
+}
+
+impl TryFrom<Value> for Group {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::SpecificGroup(group) => Ok(group),
+            other => Err(other.unexpected("specific group")),
+        }
+    }
+}
+
+impl From<Group> for Value {
+    fn from(group: Group) -> Self {
+        Value::SpecificGroup(group)
+    }
+}
+
+/// Implements conversions between items of group variant and group, or value
+/// holding it. Error names group that was expected and the one found. Items
+/// given with `@try_from` convert only from group.
+macro_rules! group_items {
+    ($($variant:ident($item:ty)),* $(,)?) => {$(
+        group_items!(@try_from $variant($item));
+
+        impl From<Vec<$item>> for Value {
+            fn from(items: Vec<$item>) -> Self {
+                Value::SpecificGroup(Group::$variant(items))
+            }
+        }
+    )*};
+    (@try_from $variant:ident($item:ty)) => {
+        impl TryFrom<Group> for Vec<$item> {
+            type Error = Error;
+
+            fn try_from(group: Group) -> Result<Self, Self::Error> {
+                match group {
+                    Group::$variant(items) => Ok(items),
+                    other => Err(Error::UnexpectedValueError {
+                        expected: Group::$variant(vec![]).name().into(),
+                        found: other.name().into(),
+                    }),
+                }
+            }
+        }
+
+        impl TryFrom<Value> for Vec<$item> {
+            type Error = Error;
+
+            fn try_from(value: Value) -> Result<Self, Self::Error> {
+                match value {
+                    Value::SpecificGroup(group) => group.try_into(),
+                    other => Err(other.unexpected(Group::$variant(vec![]).name())),
+                }
+            }
+        }
+
+        impl TryFrom<&Value> for Vec<$item> {
+            type Error = Error;
+
+            fn try_from(value: &Value) -> Result<Self, Self::Error> {
+                match value {
+                    Value::SpecificGroup(Group::$variant(items)) => Ok(items.clone()),
+                    other => Err(other.unexpected(Group::$variant(vec![]).name())),
+                }
+            }
+        }
+    };
+}
+
+group_items!(
+    NontransReceiptCouples((PublicKey, Signature)),
+    TransReceiptQuadruples((Identifier, u64, Digest, IndexedSignature)),
+    SourceSealCouples(SourceSeal),
+    FirstSeenReplyCouples((u64, Timestamp)),
+    DigestSeals(DigestSeal),
+    MerkleRootSeals(MerkleRootSeal),
+    AnchoringSeals(EventSeal),
+    LastEstablishmentSeals(LastEstablishmentSeal),
+    RegistrarSeals(RegistrarSeal),
+    TransIndexedSigGroups((Identifier, u64, Digest, Vec<IndexedSignature>)),
+    TransLastIdxSigGroups((Identifier, Vec<IndexedSignature>)),
+    BlindedStates(BlindedState),
+    BoundStates(BoundState),
+);
+
+// Values are taken from TSP payload group. As universal groups hold values
+// too, there's no conversion the other way.
+group_items!(@try_from TSPPayload(Value));
+
+/// Pathed material group is taken as its path and attached groups.
+#[cfg(feature = "cesr-proof")]
+impl TryFrom<Group> for (MaterialPath, Vec<Group>) {
+    type Error = Error;
+
+    fn try_from(group: Group) -> Result<Self, Self::Error> {
+        match group {
+            Group::PathedMaterialQuadruplet(path, groups) => Ok((path, groups)),
+            other => Err(Error::UnexpectedValueError {
+                expected: GroupCode::PathedMaterialQuadruple(0).name().into(),
+                found: other.name().into(),
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "cesr-proof")]
+impl TryFrom<Value> for (MaterialPath, Vec<Group>) {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::SpecificGroup(group) => group.try_into(),
+            other => Err(other.unexpected(GroupCode::PathedMaterialQuadruple(0).name())),
+        }
+    }
+}
+
+#[cfg(feature = "cesr-proof")]
+impl TryFrom<&Value> for (MaterialPath, Vec<Group>) {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::SpecificGroup(Group::PathedMaterialQuadruplet(path, groups)) => {
+                Ok((path.clone(), groups.clone()))
+            }
+            other => Err(other.unexpected(GroupCode::PathedMaterialQuadruple(0).name())),
+        }
+    }
+}
+
+#[cfg(feature = "cesr-proof")]
+impl From<(MaterialPath, Vec<Group>)> for Value {
+    fn from((path, groups): (MaterialPath, Vec<Group>)) -> Self {
+        Value::SpecificGroup(Group::PathedMaterialQuadruplet(path, groups))
+    }
+}
+
+/// Indexed signatures are taken from controller or witness signatures
+/// group. As the two share item type, there's no conversion the other way.
+impl TryFrom<Group> for Vec<IndexedSignature> {
+    type Error = Error;
+
+    fn try_from(group: Group) -> Result<Self, Self::Error> {
+        match group {
+            Group::IndexedControllerSignatures(signatures)
+            | Group::IndexedWitnessSignatures(signatures) => Ok(signatures),
+            other => Err(Error::UnexpectedValueError {
+                expected: "indexed signatures".into(),
+                found: other.name().into(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<Value> for Vec<IndexedSignature> {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::SpecificGroup(group) => group.try_into(),
+            other => Err(other.unexpected("indexed signatures")),
+        }
+    }
+}
+
+impl TryFrom<&Value> for Vec<IndexedSignature> {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::SpecificGroup(
+                Group::IndexedControllerSignatures(signatures)
+                | Group::IndexedWitnessSignatures(signatures),
+            ) => Ok(signatures.clone()),
+            other => Err(other.unexpected("indexed signatures")),
+        }
+    }
+}
+
+fn items_len<T: Encode>(items: &[T]) -> usize {
+    items.iter().map(Encode::encoded_len).sum()
+}
+
+fn write_items<T: Encode, W: fmt::Write + ?Sized>(items: &[T], writer: &mut W) -> fmt::Result {
+    items.iter().try_for_each(|item| item.write_cesr(writer))
+}
+
+/// Writes counter of code table, or native counter if there's none. Group
+/// is counted in items, if table counts it so, or in quadlets of content.
+/// Counter takes big form if count doesn't fit into small one, except in
+/// tables without universal codes, where it fails.
+fn write_counter<W: fmt::Write + ?Sized>(
+    table: Option<&CodeTable>,
+    code: GroupCodeFn,
+    items: Option<usize>,
+    content_len: usize,
+    writer: &mut W,
+) -> Result<(), Error> {
+    let kind = table.map_or(CountKind::Items, |table| table.count_kind(&code(0)));
+    let count = match (items, kind) {
+        (Some(items), CountKind::Items) => items,
+        _ => content_len / 4,
+    };
+    let hard = match table {
+        None => code(0).hard_code(),
+        Some(table) => {
+            table
+                .counter_code(&code(0))
+                .ok_or_else(|| Error::NoEquivalentCodeError {
+                    code: code(count as u32).to_str(),
+                    table: table.name().to_string(),
+                })?
+        }
+    };
+    let big_allowed = table.is_none_or(CodeTable::has_universal_codes);
+    if count > BIG_COUNT_MAX || (count > SMALL_COUNT_MAX && !big_allowed) {
+        return Err(Error::NumberOverflowError(format!("{} count", hard)));
+    }
+    Ok(write_counter_code(hard, count as u64, writer)?)
+}
+
+/// Writes indexed controller signatures group, nested in other group.
+fn write_signatures<W: fmt::Write + ?Sized>(
+    table: Option<&CodeTable>,
+    signatures: &[IndexedSignature],
+    writer: &mut W,
+) -> Result<(), Error> {
+    write_counter(
+        table,
+        GroupCode::IndexedControllerSignatures,
+        Some(signatures.len()),
+        items_len(signatures),
+        writer,
+    )?;
+    Ok(write_items(signatures, writer)?)
+}
+
+#[test]
+fn test_group_value_conversions() -> Result<(), Error> {
+    use crate::{primitives::Matter, value::parse_value};
+
+    let prefix = "EKC8085pwSwzLwUGzh-HrEoFDwZnCJq27bVp5atdMT9o";
+    let signature =
+        "AABB5IVZOhEfcH4TBQgOCyMgyQrJujtBBjT8K_zTPk0-FLMtTZuBgXV7jnLw6fDe6FWtzshh2HGCL_H_j4i1b9kF";
+    let (_, value) = parse_value(&["-YAB", prefix, "-KAB", signature].concat()).unwrap();
+    let groups = Vec::<(Identifier, Vec<IndexedSignature>)>::try_from(&value)?;
+    assert_eq!(groups[0].0.qb64(), prefix);
+    assert_eq!(Value::from(groups), value);
+    assert_eq!(
+        Vec::<DigestSeal>::try_from(&value),
+        Err(Error::UnexpectedValueError {
+            expected: "DigestSealSingles".into(),
+            found: "TransLastIdxSigGroups".into()
+        })
+    );
+    assert!(Vec::<IndexedSignature>::try_from(&value).is_err());
+
+    // Indexed signatures are taken from both signatures groups
+    let (_, witness) = parse_value(&["-LAB", signature].concat()).unwrap();
+    let signatures = Vec::<IndexedSignature>::try_from(witness.clone())?;
+    assert_eq!(signatures[0].to_string(), signature);
+    assert_eq!(
+        Group::try_from(witness.clone())?.to_cesr_str(),
+        ["-LAB", signature].concat()
+    );
+
+    assert_eq!(
+        Vec::<DigestSeal>::try_from(Value::Null),
+        Err(Error::UnexpectedValueError {
+            expected: "DigestSealSingles".into(),
+            found: "null".into()
+        })
+    );
+
+    let tsp = Value::SpecificGroup(Group::TSPPayload(vec![Value::Null]));
+    assert_eq!(Vec::<Value>::try_from(&tsp)?, vec![Value::Null]);
+    assert!(Vec::<Value>::try_from(witness.clone()).is_err());
+
+    #[cfg(feature = "cesr-proof")]
+    {
+        let path = MaterialPath::create_from_str("-a".into());
+        let pathed = Value::from((path.clone(), vec![Group::try_from(witness.clone())?]));
+        let (parsed_path, groups) = <(MaterialPath, Vec<Group>)>::try_from(&pathed)?;
+        assert_eq!(parsed_path, path);
+        assert_eq!(groups.len(), 1);
+        assert!(<(MaterialPath, Vec<Group>)>::try_from(tsp).is_err());
     }
+    Ok(())
 }
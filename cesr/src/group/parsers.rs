@@ -2,33 +2,230 @@ use std::str::FromStr;
 
 use nom::{
     bytes::complete::take,
+    combinator::all_consuming,
     error::{make_error, ErrorKind},
     multi::{count, many0},
     sequence::tuple,
 };
 
 use crate::{
+    config::ParserConfig,
+    derivation_code::DerivationCode,
+    error::IResult,
+    genus::CountKind,
     primitives::{
         codes::{
             attached_signature_code::AttachedSignatureCode, basic::Basic,
-            self_addressing::SelfAddressing, self_signing::SelfSigning,
+            self_addressing::SelfAddressing, self_signing::SelfSigning, table,
         },
-        parsers::{anchoring_event_seal, parse_primitive, serial_number_parser, timestamp_parser},
+        parsers::{identifier_with, primitive_with, serial_number_parser_with, timestamp_parser},
+        IndexedSignature,
     },
-    value::parse_value,
+    seal::{
+        digest_seal_with, event_seal_with, last_establishment_seal_with, merkle_root_seal_with,
+        registrar_seal_with, source_seal_with,
+    },
+    value::parse_value_with,
+    variable_length::variable_length_size,
 };
 
-use super::{codes::GroupCode, Group};
+use super::{
+    blind::{blinded_state_with, bound_state_with},
+    codes::{counter_size, GroupCode},
+    Group,
+};
 
-pub fn group_code(s: &str) -> nom::IResult<&str, GroupCode> {
-    let (rest, payload_type) = take(4u8)(s)?;
+/// Parses group code, in small or big form.
+pub fn group_code(s: &str) -> IResult<&str, GroupCode> {
+    let (rest, payload_type) = take(counter_size(s))(s)?;
     let Ok(group_code) = GroupCode::from_str(payload_type) else {
         return Err(nom::Err::Error(make_error(s, ErrorKind::IsNot)));
     };
     Ok((rest, group_code))
 }
 
-pub fn parse_group(stream: &str) -> nom::IResult<&str, Group> {
+/// Parses group code with code table selected in configuration.
+fn configured_group_code<'a>(s: &'a str, config: &ParserConfig) -> IResult<&'a str, GroupCode> {
+    let Some(table) = config.code_table() else {
+        return group_code(s);
+    };
+    let (rest, payload_type) = take(counter_size(s))(s)?;
+    let Ok(group_code) = table.group_code(payload_type) else {
+        return Err(nom::Err::Error(make_error(s, ErrorKind::IsNot)));
+    };
+    Ok((rest, group_code))
+}
+
+/// Parses controller indexed signatures group nested in another group. Fails
+/// if nested group is of different type.
+fn nested_controller_signatures<'a>(
+    s: &'a str,
+    config: &ParserConfig,
+) -> IResult<&'a str, Vec<IndexedSignature>> {
+    let (rest, nested_code) = configured_group_code(s, config)?;
+    let GroupCode::IndexedControllerSignatures(n) = nested_code else {
+        return Err(nom::Err::Error(make_error(s, ErrorKind::Verify)));
+    };
+    let kind = count_kind(config, &nested_code);
+    framed(
+        rest,
+        n,
+        kind,
+        config,
+        primitive_with::<AttachedSignatureCode>(config.strict),
+    )
+}
+
+/// Returns meaning of group count in selected code table. Native table counts
+/// items.
+fn count_kind(config: &ParserConfig, group_code: &GroupCode) -> CountKind {
+    config
+        .code_table()
+        .map_or(CountKind::Items, |table| table.count_kind(group_code))
+}
+
+/// Parses `n` group items. For code tables that count quadlets, `n` is size
+/// of all group items in quadlets.
+/// Group size limits of configuration are checked before items are parsed,
+/// as far as count allows.
+pub(crate) fn framed<'a, O>(
+    s: &'a str,
+    n: u32,
+    kind: CountKind,
+    config: &ParserConfig,
+    parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> IResult<&'a str, Vec<O>> {
+    match kind {
+        CountKind::Items => {
+            config.check_group_items(s, n as usize)?;
+            let (rest, items) = count(parser, n as usize)(s)?;
+            config.check_group_quadlets(s, (s.len() - rest.len()) / 4)?;
+            Ok((rest, items))
+        }
+        CountKind::Quadlets => {
+            config.check_group_quadlets(s, n as usize)?;
+            let (rest, items) = take(n as usize * 4)(s)?;
+            let (_, items) = all_consuming(many0(parser))(items)?;
+            config.check_group_items(s, items.len())?;
+            Ok((rest, items))
+        }
+    }
+}
+
+/// Part of group item, as far as its size is concerned.
+#[derive(Clone, Copy)]
+enum ItemPart {
+    /// Primitive of master code table or variable length primitive
+    Matter,
+    IndexedSignature,
+    /// Nested controller indexed signatures group
+    ControllerSignatures,
+}
+
+/// Returns parts of group item. `None` means group is framed in quadlets in
+/// every code table.
+fn item_parts(group_code: &GroupCode) -> Option<&'static [ItemPart]> {
+    use ItemPart::*;
+
+    Some(match group_code {
+        GroupCode::IndexedControllerSignatures(_) | GroupCode::IndexedWitnessSignatures(_) => {
+            &[IndexedSignature]
+        }
+        GroupCode::TransReceiptQuadruples(_) => &[Matter, Matter, Matter, IndexedSignature],
+        GroupCode::TransIndexedSigGroups(_) => &[Matter, Matter, Matter, ControllerSignatures],
+        GroupCode::TransLastIdxSigGroups(_) => &[Matter, ControllerSignatures],
+        GroupCode::DigestSealSingles(_)
+        | GroupCode::MerkleRootSealSingles(_)
+        | GroupCode::SealSourceLastSingles(_) => &[Matter],
+        GroupCode::NontransferableReceiptCouples(_)
+        | GroupCode::FirstSeenReplyCouples(_)
+        | GroupCode::SealSourceCouples(_)
+        | GroupCode::BackerRegistrarSealCouples(_) => &[Matter, Matter],
+        GroupCode::AnchoringEventSeals(_) => &[Matter, Matter, Matter],
+        GroupCode::BlindedStateQuadruples(_) => &[Matter; 4],
+        GroupCode::BoundStateSextuples(_) => &[Matter; 6],
+        #[cfg(feature = "cesr-proof")]
+        GroupCode::PathedMaterialQuadruple(_) => return None,
+        GroupCode::TSPPayload(_) => return None,
+    })
+}
+
+/// Skips group item part, checking only its code.
+fn skip_part<'a>(s: &'a str, part: ItemPart, config: &ParserConfig) -> IResult<&'a str, ()> {
+    let size = match part {
+        ItemPart::Matter => table::lookup(s)
+            .map(|entry| entry.full)
+            .or_else(|| variable_length_size(s).map(|(code, value)| code + value)),
+        ItemPart::IndexedSignature => AttachedSignatureCode::from_str(s)
+            .ok()
+            .map(|code| code.full_size()),
+        ItemPart::ControllerSignatures => {
+            let (rest, nested_code) = configured_group_code(s, config)?;
+            let GroupCode::IndexedControllerSignatures(n) = nested_code else {
+                return Err(nom::Err::Error(make_error(s, ErrorKind::Verify)));
+            };
+            let kind = count_kind(config, &nested_code);
+            let (rest, _) = skip_items(rest, n, kind, &[ItemPart::IndexedSignature], config)?;
+            return Ok((rest, ()));
+        }
+    };
+    let Some(size) = size else {
+        return Err(nom::Err::Error(make_error(s, ErrorKind::IsNot)));
+    };
+    let (rest, _) = take(size)(s)?;
+    Ok((rest, ()))
+}
+
+/// Skips `n` group items, like `framed` parses them, and returns their text.
+fn skip_items<'a>(
+    s: &'a str,
+    n: u32,
+    kind: CountKind,
+    parts: &[ItemPart],
+    config: &ParserConfig,
+) -> IResult<&'a str, &'a str> {
+    match kind {
+        CountKind::Items => {
+            config.check_group_items(s, n as usize)?;
+            let mut rest = s;
+            for _ in 0..n {
+                for part in parts {
+                    (rest, _) = skip_part(rest, *part, config)?;
+                }
+            }
+            config.check_group_quadlets(s, (s.len() - rest.len()) / 4)?;
+            Ok((rest, &s[..s.len() - rest.len()]))
+        }
+        CountKind::Quadlets => {
+            config.check_group_quadlets(s, n as usize)?;
+            take(n as usize * 4)(s)
+        }
+    }
+}
+
+/// Finds end of group without decoding its items. Returns group code and
+/// whole group text, including the counter.
+pub(crate) fn group_extent<'a>(
+    stream: &'a str,
+    config: &ParserConfig,
+) -> IResult<&'a str, (GroupCode, &'a str)> {
+    let (rest, group_code) = configured_group_code(stream, config)?;
+    let n = group_code.count();
+    let (rest, _) = match item_parts(&group_code) {
+        Some(parts) => skip_items(rest, n, count_kind(config, &group_code), parts, config)?,
+        None => {
+            config.check_group_quadlets(rest, n as usize)?;
+            take(n as usize * 4)(rest)?
+        }
+    };
+    Ok((rest, (group_code, &stream[..stream.len() - rest.len()])))
+}
+
+pub fn parse_group(stream: &str) -> IResult<&str, Group> {
+    parse_group_with(stream, &ParserConfig::default())
+}
+
+pub fn parse_group_with<'a>(stream: &'a str, config: &ParserConfig) -> IResult<&'a str, Group> {
     // let first_byte = stream
     //     .first()
     //     .ok_or(nom::Err::Error(make_error(stream, ErrorKind::Eof)))?;
@@ -38,50 +235,136 @@ pub fn parse_group(stream: &str) -> nom::IResult<&str, Group> {
     //     return Err(nom::Err::Error(make_error(stream, ErrorKind::IsNot)));
     // }
 
-    let (rest, group_code) = group_code(stream)?;
+    let (rest, group_code) = configured_group_code(stream, config)?;
+    let kind = count_kind(config, &group_code);
     Ok(match group_code {
         GroupCode::IndexedControllerSignatures(n) => {
-            let (rest, signatures) =
-                count(parse_primitive::<AttachedSignatureCode>, n as usize)(rest)?;
+            let (rest, signatures) = framed(
+                rest,
+                n,
+                kind,
+                config,
+                primitive_with::<AttachedSignatureCode>(config.strict),
+            )?;
             (rest, Group::IndexedControllerSignatures(signatures))
         }
         GroupCode::IndexedWitnessSignatures(n) => {
-            let (rest, signatures) =
-                count(parse_primitive::<AttachedSignatureCode>, n as usize)(rest)?;
+            let (rest, signatures) = framed(
+                rest,
+                n,
+                kind,
+                config,
+                primitive_with::<AttachedSignatureCode>(config.strict),
+            )?;
             (rest, Group::IndexedWitnessSignatures(signatures))
         }
         GroupCode::NontransferableReceiptCouples(n) => {
-            let (rest, couple) = count(
-                tuple((parse_primitive::<Basic>, parse_primitive::<SelfSigning>)),
-                n as usize,
-            )(rest)?;
+            let (rest, couple) = framed(
+                rest,
+                n,
+                kind,
+                config,
+                tuple((
+                    primitive_with::<Basic>(config.strict),
+                    primitive_with::<SelfSigning>(config.strict),
+                )),
+            )?;
             (rest, Group::NontransReceiptCouples(couple))
         }
+        GroupCode::TransReceiptQuadruples(n) => {
+            let (rest, quadruples) = framed(
+                rest,
+                n,
+                kind,
+                config,
+                tuple((
+                    identifier_with(config.strict),
+                    serial_number_parser_with(config.strict),
+                    primitive_with::<SelfAddressing>(config.strict),
+                    primitive_with::<AttachedSignatureCode>(config.strict),
+                )),
+            )?;
+            (rest, Group::TransReceiptQuadruples(quadruples))
+        }
+        GroupCode::TransIndexedSigGroups(n) => {
+            let (rest, groups) = framed(
+                rest,
+                n,
+                kind,
+                config,
+                tuple((
+                    identifier_with(config.strict),
+                    serial_number_parser_with(config.strict),
+                    primitive_with::<SelfAddressing>(config.strict),
+                    |s| nested_controller_signatures(s, config),
+                )),
+            )?;
+            (rest, Group::TransIndexedSigGroups(groups))
+        }
+        GroupCode::TransLastIdxSigGroups(n) => {
+            let (rest, groups) = framed(
+                rest,
+                n,
+                kind,
+                config,
+                tuple((identifier_with(config.strict), |s| {
+                    nested_controller_signatures(s, config)
+                })),
+            )?;
+            (rest, Group::TransLastIdxSigGroups(groups))
+        }
         GroupCode::SealSourceCouples(n) => {
-            let (rest, couple) = count(
-                tuple((serial_number_parser, parse_primitive::<SelfAddressing>)),
-                n as usize,
-            )(rest)?;
-            (rest, Group::SourceSealCouples(couple))
+            let (rest, seals) = framed(rest, n, kind, config, source_seal_with(config.strict))?;
+            (rest, Group::SourceSealCouples(seals))
         }
         GroupCode::FirstSeenReplyCouples(n) => {
-            let (rest, couple) =
-                count(tuple((serial_number_parser, timestamp_parser)), n as usize)(rest)?;
+            let (rest, couple) = framed(
+                rest,
+                n,
+                kind,
+                config,
+                tuple((serial_number_parser_with(config.strict), timestamp_parser)),
+            )?;
             (rest, Group::FirstSeenReplyCouples(couple))
         }
         GroupCode::AnchoringEventSeals(n) => {
-            let (rest, quadruple) = count(anchoring_event_seal, n as usize)(rest)?;
-            (rest, Group::AnchoringSeals(quadruple))
+            let (rest, seals) = framed(rest, n, kind, config, event_seal_with(config.strict))?;
+            (rest, Group::AnchoringSeals(seals))
+        }
+        GroupCode::DigestSealSingles(n) => {
+            let (rest, seals) = framed(rest, n, kind, config, digest_seal_with(config.strict))?;
+            (rest, Group::DigestSeals(seals))
+        }
+        GroupCode::MerkleRootSealSingles(n) => {
+            let (rest, seals) =
+                framed(rest, n, kind, config, merkle_root_seal_with(config.strict))?;
+            (rest, Group::MerkleRootSeals(seals))
+        }
+        GroupCode::SealSourceLastSingles(n) => {
+            let (rest, seals) = framed(
+                rest,
+                n,
+                kind,
+                config,
+                last_establishment_seal_with(config.strict),
+            )?;
+            (rest, Group::LastEstablishmentSeals(seals))
+        }
+        GroupCode::BackerRegistrarSealCouples(n) => {
+            let (rest, seals) = framed(rest, n, kind, config, registrar_seal_with(config.strict))?;
+            (rest, Group::RegistrarSeals(seals))
         }
         #[cfg(feature = "cesr-proof")]
         GroupCode::PathedMaterialQuadruple(n) => {
             // n * 4 is all path and attachments length (?)
-            match nom::bytes::complete::take(n * 4)(rest) {
+            config.check_group_quadlets(rest, n as usize)?;
+            let config = &config.nested(rest)?;
+            match nom::bytes::complete::take(n as usize * 4)(rest) {
                 Ok((rest, total)) => {
                     use crate::variable_length::{
-                        variable_length_value, SmallVariableLengthCode, VariableLengthCode,
+                        variable_length_value_with, SmallVariableLengthCode, VariableLengthCode,
                     };
-                    let (extra, mp) = variable_length_value(total)?;
+                    let (extra, mp) = variable_length_value_with(total, config.strict)?;
                     let material_path = match mp.code() {
                         VariableLengthCode::Small {
                             code: SmallVariableLengthCode::Base64String,
@@ -95,7 +378,7 @@ pub fn parse_group(stream: &str) -> nom::IResult<&str, Group> {
                         }
                         _ => return Err(nom::Err::Error(make_error(total, ErrorKind::IsNot))),
                     };
-                    let (_extra, attachment) = many0(parse_group)(extra)?;
+                    let (_extra, attachment) = many0(|s| parse_group_with(s, config))(extra)?;
 
                     Ok((
                         rest,
@@ -105,16 +388,29 @@ pub fn parse_group(stream: &str) -> nom::IResult<&str, Group> {
                 Err(e) => Err(e),
             }?
         }
-        GroupCode::TSPPayload(n) => match nom::bytes::complete::take(n * 4)(rest) {
-            Ok((main_rest, total)) => {
-                let (rest, values) = many0(parse_value)(total)?;
-                if !rest.is_empty() {
-                    return Err(nom::Err::Error(make_error(total, ErrorKind::Many0)));
+        GroupCode::TSPPayload(n) => {
+            config.check_group_quadlets(rest, n as usize)?;
+            let config = &config.nested(rest)?;
+            match nom::bytes::complete::take(n as usize * 4)(rest) {
+                Ok((main_rest, total)) => {
+                    let (rest, values) = many0(|s| parse_value_with(s, config))(total)?;
+                    if !rest.is_empty() {
+                        return Err(nom::Err::Error(make_error(total, ErrorKind::Many0)));
+                    }
+                    config.check_group_items(total, values.len())?;
+                    Ok((main_rest, Group::TSPPayload(values)))
                 }
-                Ok((main_rest, Group::TSPPayload(values)))
-            }
-            Err(e) => Err(e),
-        }?,
+                Err(e) => Err(e),
+            }?
+        }
+        GroupCode::BlindedStateQuadruples(n) => {
+            let (rest, states) = framed(rest, n, kind, config, blinded_state_with(config.strict))?;
+            (rest, Group::BlindedStates(states))
+        }
+        GroupCode::BoundStateSextuples(n) => {
+            let (rest, states) = framed(rest, n, kind, config, bound_state_with(config.strict))?;
+            (rest, Group::BoundStates(states))
+        }
     })
 }
 
@@ -130,6 +426,55 @@ pub fn test_parse_group() {
             .unwrap(),
     );
     assert_eq!(group, Group::FirstSeenReplyCouples(vec![expected]));
+
+    // Serial numbers may be encoded with number codes as well
+    let legacy_str = "-TAB0AAAAAAAAAAAAAAAAAAAAAABEJtQndkvwnMpVGE5oVVbLWSCm-jLviGw1AOOkzBvNwsS";
+    let number_str = "-TABMAABEJtQndkvwnMpVGE5oVVbLWSCm-jLviGw1AOOkzBvNwsS";
+    let (_rest, legacy_group) = parse_group(legacy_str).unwrap();
+    let (rest, number_group) = parse_group(number_str).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(legacy_group, number_group);
+}
+
+#[test]
+pub fn test_parse_transferable_receipts() {
+    let prefix = "EKC8085pwSwzLwUGzh-HrEoFDwZnCJq27bVp5atdMT9o";
+    let sn = "0AAAAAAAAAAAAAAAAAAAAAAA";
+    let digest = "EJtQndkvwnMpVGE5oVVbLWSCm-jLviGw1AOOkzBvNwsS";
+    let signature =
+        "AABB5IVZOhEfcH4TBQgOCyMgyQrJujtBBjT8K_zTPk0-FLMtTZuBgXV7jnLw6fDe6FWtzshh2HGCL_H_j4i1b9kF";
+
+    let quadruples_str = ["-NAB", prefix, sn, digest, signature].concat();
+    let (rest, group) = parse_group(&quadruples_str).unwrap();
+    assert!(rest.is_empty());
+    assert!(matches!(&group, Group::TransReceiptQuadruples(q) if q.len() == 1));
+    assert_eq!(group.to_cesr_str(), quadruples_str);
+
+    let groups_str = ["-XAB", prefix, sn, digest, "-KAC", signature, signature].concat();
+    let (rest, group) = parse_group(&groups_str).unwrap();
+    assert!(rest.is_empty());
+    match &group {
+        Group::TransIndexedSigGroups(groups) => {
+            assert_eq!(groups.len(), 1);
+            assert_eq!(groups[0].1, 0);
+            assert_eq!(groups[0].3.len(), 2);
+        }
+        _ => panic!("Unexpected group"),
+    };
+    assert_eq!(group.to_cesr_str(), groups_str);
+
+    let last_str = ["-YAB", prefix, "-KAB", signature].concat();
+    let (rest, group) = parse_group(&last_str).unwrap();
+    assert!(rest.is_empty());
+    assert!(matches!(&group, Group::TransLastIdxSigGroups(g) if g[0].1.len() == 1));
+    assert_eq!(group.to_cesr_str(), last_str);
+
+    // Nested group has to be controller signatures group
+    let wrong_nested = ["-YAB", prefix, "-LAB", signature].concat();
+    assert!(parse_group(&wrong_nested).is_err());
+    // Nested count has to match signatures
+    let wrong_count = ["-YAB", prefix, "-KAC", signature].concat();
+    assert!(parse_group(&wrong_count).is_err());
 }
 
 #[cfg(feature = "cesr-proof")]
@@ -137,8 +482,11 @@ pub fn test_parse_group() {
 fn test_pathed_material() {
     use crate::cesr_proof::MaterialPath;
 
+    // Lead bits of signature aren't zero, as some encoders leave them
     let attached_str = "-PAZ5AABAA-a-KABAAFjjD99-xy7J0LGmCkSE_zYceED5uPF4q7l8J23nNQ64U-oWWulHI5dh3cFDWT4eICuEQCALdh8BO5ps-qx0qBA";
-    let (_rest, attached_material) = parse_group(attached_str).unwrap();
+    assert!(parse_group(attached_str).is_err());
+    let config = ParserConfig::default().with_strict(false);
+    let (_rest, attached_material) = parse_group_with(attached_str, &config).unwrap();
     let expected_path = MaterialPath::create_from_str("-a".into());
     if let Group::PathedMaterialQuadruplet(material_path, groups) = attached_material {
         assert_eq!(material_path, expected_path);
@@ -161,7 +509,7 @@ mod tests {
         let said = "ELC5L3iBVD77d_MYbYGGCUQgqQBju1o4x1Ud-z2sL-ux";
         let nounce = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
         let relation_dig = "ELC5L3iBVD77d_MYbYGGCUQgqQBju1o4x1Ud-z2sL-ux";
-        let (tag, _) = parse_primitive::<TagCode>(&msg_type).unwrap().1;
+        let (tag, _) = parse_primitive::<TagCode>(msg_type).unwrap().1;
 
         let tsp_payload = vec![
             Value::Tag(tag),
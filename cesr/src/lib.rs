@@ -1,28 +1,142 @@
+pub mod builder;
+pub mod catalog;
+pub mod config;
 pub mod derivation_code;
+pub mod encode;
 pub mod error;
+pub mod frame;
+pub mod genus;
 pub mod group;
+#[cfg(feature = "cesr-log")]
+pub mod log;
 pub mod payload;
 pub mod primitives;
+pub mod registry;
+pub mod seal;
+pub mod transcode;
 use std::sync::mpsc::Sender;
 
+use crate::config::ParserConfig;
 use crate::error::CESRError;
 use crate::error::ParsingError;
 use crate::value::parse_value;
+use crate::value::parse_value_with;
 use crate::value::Value;
+use crate::value_ref::{value_ref, ValueRef};
+use std::borrow::Cow;
 
 #[cfg(feature = "cesr-proof")]
 pub mod cesr_proof;
 pub mod conversion;
 pub mod universal_codes;
 pub mod value;
-mod variable_length;
+pub mod value_ref;
+pub mod variable_length;
+pub mod visit;
 
 pub fn parse_one(stream: &str) -> Result<(&str, Value), ParsingError> {
     Ok(parse_value(stream)?)
 }
 
 pub fn parse_all(stream: &str) -> Result<(&str, Vec<Value>), ParsingError> {
-    Ok(nom::multi::many0(parse_value)(stream)?)
+    parse_all_with(stream, &ParserConfig::default())
+}
+
+pub fn parse_one_with<'a>(
+    stream: &'a str,
+    config: &ParserConfig,
+) -> Result<(&'a str, Value), ParsingError> {
+    config.check_stream_size(stream)?;
+    Ok(parse_value_with(stream, config)?)
+}
+
+/// Parses values until stream ends or can't be parsed further. Genus version
+/// code in stream switches code table for the following values. Stream that
+/// exceeds limits of configuration fails with specific error, see
+/// `config::ParserLimits`.
+pub fn parse_all_with<'a>(
+    stream: &'a str,
+    config: &ParserConfig,
+) -> Result<(&'a str, Vec<Value>), ParsingError> {
+    config.check_stream_size(stream)?;
+    let mut config = config.clone();
+    let mut values = vec![];
+    let mut rest = stream;
+    while !rest.is_empty() {
+        let (more, value) = match parse_value_with(rest, &config) {
+            Ok(parsed) => parsed,
+            Err(nom::Err::Error(_)) => break,
+            Err(e) => return Err(e.into()),
+        };
+        if let Value::VersionGenus(genus) = &value {
+            config = config.with_genus(genus.clone());
+        }
+        values.push(value);
+        rest = more;
+    }
+    Ok((rest, values))
+}
+
+pub fn parse_all_ref(stream: &str) -> Result<(&str, Vec<ValueRef<'_>>), ParsingError> {
+    parse_all_ref_with(stream, &ParserConfig::default())
+}
+
+/// Parses values borrowed from stream, as `parse_all_with` does. Values are
+/// decoded only on demand, see `ValueRef`.
+pub fn parse_all_ref_with<'a>(
+    stream: &'a str,
+    config: &ParserConfig,
+) -> Result<(&'a str, Vec<ValueRef<'a>>), ParsingError> {
+    config.check_stream_size(stream)?;
+    let mut config: Cow<'a, ParserConfig> = Cow::Owned(config.clone());
+    let mut values = vec![];
+    let mut rest = stream;
+    while !rest.is_empty() {
+        let (more, value) = match value_ref(rest, &config) {
+            Ok(parsed) => parsed,
+            Err(nom::Err::Error(_)) => break,
+            Err(e) => return Err(e.into()),
+        };
+        if let ValueRef::VersionGenus(genus) = &value {
+            config = Cow::Owned(config.into_owned().with_genus(genus.clone()));
+        }
+        values.push(value);
+        rest = more;
+    }
+    Ok((rest, values))
+}
+
+/// Parses values as `parse_all_with` does, decoding message frames in
+/// parallel. Stream is split into frames by sequential scan first, see
+/// `frame::scan_frames`. Values are returned in stream order. Stream from
+/// the first frame that can't be decoded whole is parsed sequentially, so
+/// values and rest, or error, are the same as of `parse_all_with`.
+#[cfg(feature = "parallel")]
+pub fn parse_all_par_with<'a>(
+    stream: &'a str,
+    config: &ParserConfig,
+) -> Result<(&'a str, Vec<Value>), ParsingError> {
+    use rayon::prelude::*;
+
+    let (rest, frames) = frame::scan_frames(stream, config)?;
+    let parsed: Vec<_> = frames.par_iter().map(frame::Frame::parse).collect();
+    let mut values = vec![];
+    for (frame, frame_values) in frames.iter().zip(parsed) {
+        match frame_values {
+            Ok(frame_values) => values.extend(frame_values),
+            Err(_) => {
+                let (rest, tail_values) = frame.parse_tail()?;
+                values.extend(tail_values);
+                return Ok((rest, values));
+            }
+        }
+    }
+    Ok((rest, values))
+}
+
+#[cfg(feature = "parallel")]
+pub fn parse_all_par(stream: &str) -> Result<(&str, Vec<Value>), ParsingError> {
+    parse_all_par_with(stream, &ParserConfig::default())
 }
 
 pub fn parse_and_send(content: &str, tx: &Sender<Value>) -> Result<(), CESRError> {
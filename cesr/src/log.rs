@@ -0,0 +1,608 @@
+//! Reader of append-only CESR log files, with one message and its
+//! attachments after another. File is memory mapped, or read into memory,
+//! and split into messages by `frame::scan_frames`, so single message can be
+//! read without parsing preceding ones. Index of messages is kept next to
+//! the log, in file with `.idx` extension appended, so it's built only once.
+
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    str,
+};
+
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::ParserConfig,
+    error::{LogError, ParsingError},
+    frame::scan_frames,
+    parse_all_with,
+    universal_codes::GenusCountCode,
+    value::Value,
+};
+
+/// Size of the end of log read so far, that is read again on refresh to
+/// find out if log was rewritten.
+const CHECKED_TAIL: usize = 4096;
+
+/// Location of message in log, and fields of its payload that messages are
+/// looked up by.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageEntry {
+    /// Offset of message in bytes.
+    pub offset: usize,
+    /// Size of message with its attachments, in bytes.
+    pub len: usize,
+    /// Genus version selected by preceding genus version codes.
+    #[serde(default, with = "genus_text", skip_serializing_if = "Option::is_none")]
+    pub genus: Option<GenusCountCode>,
+    /// SAID of payload, its `d` field.
+    #[serde(rename = "d", default, skip_serializing_if = "Option::is_none")]
+    pub said: Option<String>,
+    /// Identifier prefix, payload `i` field.
+    #[serde(rename = "i", default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    /// Sequence number, payload `s` field.
+    #[serde(rename = "s", default, skip_serializing_if = "Option::is_none")]
+    pub sn: Option<String>,
+}
+
+/// Saved index, with size of log and text of the last message it was built
+/// from, so that index of rewritten log isn't used.
+#[derive(Serialize, Deserialize)]
+struct SavedIndex {
+    log_len: usize,
+    last: String,
+    entries: Vec<MessageEntry>,
+}
+
+/// Content of log file.
+enum Content {
+    Mapped(Mmap),
+    /// Content read so far.
+    Read(Vec<u8>),
+}
+
+impl Content {
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Content::Mapped(map) => map,
+            Content::Read(data) => data,
+        }
+    }
+}
+
+pub struct CesrLog {
+    file: File,
+    content: Content,
+    index_path: PathBuf,
+    config: ParserConfig,
+    entries: Vec<MessageEntry>,
+    saids: HashMap<String, Vec<usize>>,
+}
+
+impl CesrLog {
+    /// Opens log, reading it into memory. See `open_with`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, LogError> {
+        Self::open_with(path, ParserConfig::default())
+    }
+
+    /// Opens log and indexes messages appended since index was saved. Index
+    /// is saved if it changed. Index that can't be read, or doesn't fit the
+    /// log, is built again. Log is read into memory, and `refresh` reads
+    /// only appended bytes.
+    pub fn open_with(path: impl AsRef<Path>, config: ParserConfig) -> Result<Self, LogError> {
+        Self::open_content(path.as_ref(), config, |_| Ok(Content::Read(vec![])))
+    }
+
+    /// Opens log memory mapped, so that it isn't copied into memory. See
+    /// `open_mmap_with`.
+    ///
+    /// # Safety
+    ///
+    /// As for `open_mmap_with`.
+    pub unsafe fn open_mmap(path: impl AsRef<Path>) -> Result<Self, LogError> {
+        Self::open_mmap_with(path, ParserConfig::default())
+    }
+
+    /// Opens log memory mapped, and indexes it as `open_with` does. Log is
+    /// mapped again by `refresh` when it grows.
+    ///
+    /// # Safety
+    ///
+    /// Log file must not be truncated or changed, other than by appending,
+    /// while it's open, e.g. by other process. Mapped bytes that change are
+    /// undefined behavior, and access to truncated part of mapping can crash
+    /// the process.
+    pub unsafe fn open_mmap_with(
+        path: impl AsRef<Path>,
+        config: ParserConfig,
+    ) -> Result<Self, LogError> {
+        Self::open_content(path.as_ref(), config, |file| {
+            // SAFETY: contract of this function.
+            Ok(Content::Mapped(unsafe { Mmap::map(file)? }))
+        })
+    }
+
+    fn open_content(
+        path: &Path,
+        config: ParserConfig,
+        content: impl FnOnce(&File) -> io::Result<Content>,
+    ) -> Result<Self, LogError> {
+        let file = File::open(path)?;
+        let content = content(&file)?;
+        let mut index_path = OsString::from(path);
+        index_path.push(".idx");
+        let mut log = CesrLog {
+            file,
+            content,
+            index_path: index_path.into(),
+            config,
+            entries: vec![],
+            saids: HashMap::new(),
+        };
+        log.read_appended()?;
+        for entry in log.load_index().unwrap_or_default() {
+            log.push_entry(entry);
+        }
+        let indexed_len = log.indexed_len();
+        log.refresh()?;
+        if log.indexed_len() != indexed_len {
+            log.save_index()?;
+        }
+        Ok(log)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &[MessageEntry] {
+        &self.entries
+    }
+
+    /// Returns text of message at given position, with its attachments.
+    pub fn message_text(&self, position: usize) -> Result<&str, LogError> {
+        let entry = self
+            .entries
+            .get(position)
+            .ok_or(LogError::NoMessage(position))?;
+        self.entry_text(entry)
+    }
+
+    /// Parses message at given position, with its attachments.
+    pub fn message(&self, position: usize) -> Result<Vec<Value>, LogError> {
+        let text = self.message_text(position)?;
+        let config = self.config_at(&self.entries[position]);
+        match parse_all_with(text, &config)? {
+            ("", values) => Ok(values),
+            (rest, _) => Err(ParsingError::Error(rest.to_string()).into()),
+        }
+    }
+
+    /// Returns position of message that contains byte at given offset.
+    pub fn position_at(&self, offset: usize) -> Option<usize> {
+        let position = self
+            .entries
+            .partition_point(|entry| entry.offset + entry.len <= offset);
+        (position < self.entries.len()).then_some(position)
+    }
+
+    /// Parses messages in log order, starting at given position.
+    pub fn iter_from(
+        &self,
+        position: usize,
+    ) -> impl Iterator<Item = Result<Vec<Value>, LogError>> + '_ {
+        (position..self.entries.len()).map(|position| self.message(position))
+    }
+
+    /// Returns position of the first message with given SAID in `d` field.
+    pub fn find_said(&self, said: &str) -> Option<usize> {
+        self.positions_of_said(said).first().copied()
+    }
+
+    /// Returns positions of all messages with given SAID in `d` field, e.g.
+    /// of event and its receipts.
+    pub fn positions_of_said(&self, said: &str) -> &[usize] {
+        self.saids.get(said).map_or(&[], Vec::as_slice)
+    }
+
+    /// Reads and indexes messages appended to log since last refresh, and
+    /// returns how many were added. The last indexed message is scanned
+    /// again, as more attachments could have been appended to it. Message
+    /// that is only partly written isn't indexed until it's complete, so log
+    /// can be followed by calling `refresh` periodically.
+    ///
+    /// Log that was truncated or rewritten fails with `LogError::Truncated`
+    /// and its index is cleared, so the next refresh indexes it again.
+    pub fn refresh(&mut self) -> Result<usize, LogError> {
+        let rewritten = self.read_appended()?;
+        if rewritten || self.indexed_len() > self.content.bytes().len() {
+            self.entries.clear();
+            self.saids.clear();
+            return Err(LogError::Truncated);
+        }
+        let indexed = self.entries.len();
+        let last = self.entries.pop();
+        if let Some(said) = last.as_ref().and_then(|entry| entry.said.as_ref()) {
+            if let Some(positions) = self.saids.get_mut(said) {
+                positions.pop();
+            }
+        }
+        let (start, config) = match &last {
+            Some(entry) => (entry.offset, self.config_at(entry)),
+            None => (0, self.config.clone()),
+        };
+
+        // Bytes of char, that is only partly written, aren't scanned yet.
+        let bytes = &self.content.bytes()[start..];
+        let valid = match str::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(e) => str::from_utf8(&bytes[..e.valid_up_to()]).expect("valid UTF-8 prefix"),
+        };
+        let (_, frames) = scan_frames(valid, &config)?;
+        let mut offset = start;
+        let new_entries: Vec<_> = frames
+            .iter()
+            .map(|frame| {
+                let text = frame.text();
+                let fields = PayloadFields::parse(text);
+                let entry = MessageEntry {
+                    offset,
+                    len: text.len(),
+                    genus: frame.config().genus().cloned(),
+                    said: fields.d,
+                    prefix: fields.i,
+                    sn: fields.s,
+                };
+                offset += text.len();
+                entry
+            })
+            .collect();
+        for entry in new_entries {
+            self.push_entry(entry);
+        }
+        Ok(self.entries.len().saturating_sub(indexed))
+    }
+
+    /// Writes index next to the log, replacing previous one.
+    pub fn save_index(&self) -> Result<(), LogError> {
+        let last = match self.entries.last() {
+            Some(entry) => self.entry_text(entry)?,
+            None => "",
+        };
+        let index = SavedIndex {
+            log_len: self.indexed_len(),
+            last: last.to_string(),
+            entries: self.entries.clone(),
+        };
+        let mut tmp_path = self.index_path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        fs::write(&tmp_path, serde_json::to_vec(&index)?)?;
+        fs::rename(&tmp_path, &self.index_path)?;
+        Ok(())
+    }
+
+    /// Reads saved index. Index is used only if its messages follow one
+    /// another from the start of log, up to the size of log it was built
+    /// from, and its last message is the same as in the log.
+    fn load_index(&self) -> Option<Vec<MessageEntry>> {
+        let bytes = fs::read(&self.index_path).ok()?;
+        let index: SavedIndex = serde_json::from_slice(&bytes).ok()?;
+        let mut end = 0;
+        for entry in &index.entries {
+            if entry.offset != end || entry.len == 0 {
+                return None;
+            }
+            end += entry.len;
+        }
+        let last = match index.entries.last() {
+            Some(entry) => self.entry_text(entry).ok()?,
+            None => "",
+        };
+        (end == index.log_len && last == index.last).then_some(index.entries)
+    }
+
+    fn push_entry(&mut self, entry: MessageEntry) {
+        if let Some(said) = &entry.said {
+            self.saids
+                .entry(said.clone())
+                .or_default()
+                .push(self.entries.len());
+        }
+        self.entries.push(entry);
+    }
+
+    fn entry_text(&self, entry: &MessageEntry) -> Result<&str, LogError> {
+        let bytes = self
+            .content
+            .bytes()
+            .get(entry.offset..entry.offset + entry.len)
+            .ok_or(LogError::Truncated)?;
+        str::from_utf8(bytes).map_err(|e| LogError::NonUtf8(entry.offset + e.valid_up_to()))
+    }
+
+    /// Parser configuration in force at the start of message.
+    fn config_at(&self, entry: &MessageEntry) -> ParserConfig {
+        match &entry.genus {
+            Some(genus) => self.config.clone().with_genus(genus.clone()),
+            None => self.config.clone(),
+        }
+    }
+
+    /// Reads bytes appended to log file since it was last read, or maps log
+    /// again if its size changed. Returns whether log read into memory was
+    /// truncated or rewritten, which is found out by its size and its end
+    /// read so far. Such log is read again from the start.
+    fn read_appended(&mut self) -> io::Result<bool> {
+        let file_len = self.file.metadata()?.len();
+        match &mut self.content {
+            Content::Mapped(map) => {
+                if file_len != map.len() as u64 {
+                    // SAFETY: log was opened by `open_mmap_with`, which
+                    // caller guarantees that log is only appended to.
+                    *map = unsafe { Mmap::map(&self.file)? };
+                }
+                Ok(false)
+            }
+            Content::Read(data) => {
+                let rewritten = file_len < data.len() as u64 || !same_tail(&mut self.file, data)?;
+                if rewritten {
+                    data.clear();
+                }
+                self.file.seek(SeekFrom::Start(data.len() as u64))?;
+                self.file.read_to_end(data)?;
+                Ok(rewritten)
+            }
+        }
+    }
+
+    /// Size of indexed part of log.
+    fn indexed_len(&self) -> usize {
+        self.entries
+            .last()
+            .map_or(0, |entry| entry.offset + entry.len)
+    }
+}
+
+/// Checks if file has the same bytes as the end of data read from it.
+fn same_tail(file: &mut File, data: &[u8]) -> io::Result<bool> {
+    let start = data.len().saturating_sub(CHECKED_TAIL);
+    let mut tail = vec![0; data.len() - start];
+    file.seek(SeekFrom::Start(start as u64))?;
+    file.read_exact(&mut tail)?;
+    Ok(tail == data[start..])
+}
+
+/// Payload fields of message that are kept in index. Fields that aren't
+/// strings are ignored.
+#[derive(Default)]
+struct PayloadFields {
+    d: Option<String>,
+    i: Option<String>,
+    s: Option<String>,
+}
+
+impl PayloadFields {
+    fn parse(message: &str) -> Self {
+        #[derive(Deserialize)]
+        struct Fields {
+            d: Option<serde_json::Value>,
+            i: Option<serde_json::Value>,
+            s: Option<serde_json::Value>,
+        }
+
+        if !message.starts_with('{') {
+            return Self::default();
+        }
+        let text = |value: Option<serde_json::Value>| match value {
+            Some(serde_json::Value::String(text)) => Some(text),
+            _ => None,
+        };
+        // Payload is followed by attachments, so only the first JSON value
+        // is read.
+        match serde_json::Deserializer::from_str(message)
+            .into_iter::<Fields>()
+            .next()
+        {
+            Some(Ok(fields)) => PayloadFields {
+                d: text(fields.d),
+                i: text(fields.i),
+                s: text(fields.s),
+            },
+            _ => Self::default(),
+        }
+    }
+}
+
+mod genus_text {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use crate::universal_codes::GenusCountCode;
+
+    pub fn serialize<S: Serializer>(
+        genus: &Option<GenusCountCode>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match genus {
+            Some(genus) => serializer.serialize_str(&genus.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<GenusCountCode>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|text| text.parse().map_err(de::Error::custom))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{self, OpenOptions},
+        io::Write,
+        path::PathBuf,
+    };
+
+    use crate::{error::LogError, log::CesrLog, parse_all};
+
+    const ICP: &str = r#"{"v":"KERI10JSON000188_","t":"icp","d":"EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg","i":"EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg","s":"0","kt":"1","k":["DA4cgeFcpglZf6fQ7u1j8fMs7GbkOQBzVHhBJlaHQLC9"],"nt":"1","n":["EJMujtnS0x3RGp_kHC2bh3p6cAz_4nKp6E3Yrj2u-Lsh"],"bt":"2","b":["BJq7UABlttINuWJh1Xl2lkqZG4NTdUdqnbFJDa6ZyxCC","BDg1zxxf8u4Hx5IPraZzmStfSCZFZbDzMHjqVcFW5OfP"],"c":[],"a":[]}"#;
+    const RCT: &str = r#"{"v":"KERI10JSON000091_","t":"rct","d":"EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg","i":"EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg","s":"0"}"#;
+    const ICP_SAID: &str = "EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg";
+    const SIGNATURES: &str = "-KABAADZCv1YufmwIvFbzC9jNoVZx2ZgOF8hzrxcuP9vlhJ0tNAYIvNEh0yKIGtkk1bIhrLIAEScbBmxxPosX-rGSAsD";
+    const RECEIPT: &str = "-MABBDg1zxxf8u4Hx5IPraZzmStfSCZFZbDzMHjqVcFW5OfP0BCQwOrc3LZqdYs8OEKhQlP4LpB9AqCVpwyGHCB1nfjrBjSYiWtlcvSYI5Vugh3H3rh0gfDqGHUfRKEQrIXKTWAC";
+
+    /// Log file path unique for test, with no log and index left from
+    /// previous runs.
+    fn log_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("cesrox-{}-{}.cesr", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(path.with_extension("cesr.idx"));
+        path
+    }
+
+    fn append(path: &PathBuf, text: &str) {
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_random_access() -> Result<(), LogError> {
+        let path = log_path("random-access");
+        let stream = [ICP, SIGNATURES, RCT, RECEIPT, "-_AAABAA", ICP].concat();
+        append(&path, &stream);
+
+        let log = CesrLog::open(&path)?;
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.message_text(0)?, [ICP, SIGNATURES].concat());
+        // Genus version code belongs to the preceding message
+        assert_eq!(log.message_text(1)?, [RCT, RECEIPT, "-_AAABAA"].concat());
+        assert!(log.entries()[2].genus.is_some());
+        assert_eq!(log.entries()[1].sn.as_deref(), Some("0"));
+
+        assert_eq!(log.find_said(ICP_SAID), Some(0));
+        assert_eq!(log.positions_of_said(ICP_SAID), &[0, 1, 2]);
+        assert!(log.positions_of_said("E").is_empty());
+
+        let offset = log.entries()[1].offset;
+        assert_eq!(log.position_at(offset - 1), Some(0));
+        assert_eq!(log.position_at(offset), Some(1));
+        assert_eq!(log.position_at(stream.len()), None);
+
+        let values: Vec<_> = log.iter_from(0).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(values.concat(), parse_all(&stream).unwrap().1);
+        assert_eq!(log.iter_from(2).count(), 1);
+        assert!(matches!(log.message(3), Err(LogError::NoMessage(3))));
+
+        // Index is saved and used on next open
+        let index = path.with_extension("cesr.idx");
+        assert!(index.exists());
+        let reopened = CesrLog::open(&path)?;
+        assert_eq!(reopened.entries(), log.entries());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tail_following() -> Result<(), LogError> {
+        let path = log_path("tail-following");
+        append(&path, "");
+        let mut log = CesrLog::open(&path)?;
+        assert!(log.is_empty());
+
+        // Partly written message isn't indexed
+        append(&path, &[ICP, SIGNATURES, &RCT[..50]].concat());
+        assert_eq!(log.refresh()?, 1);
+        assert_eq!(log.message_text(0)?, [ICP, SIGNATURES].concat());
+
+        append(&path, &RCT[50..]);
+        assert_eq!(log.refresh()?, 1);
+        assert_eq!(log.message_text(1)?, RCT);
+
+        // Attachments appended to the last message are added to its entry
+        append(&path, &RECEIPT[..10]);
+        append(&path, &RECEIPT[10..]);
+        assert_eq!(log.refresh()?, 0);
+        assert_eq!(log.message_text(1)?, [RCT, RECEIPT].concat());
+        assert_eq!(log.message(1)?.len(), 2);
+
+        // Index saved before appends is refreshed on open
+        log.save_index()?;
+        append(&path, ICP);
+        let reopened = CesrLog::open(&path)?;
+        assert_eq!(reopened.len(), 3);
+        assert_eq!(reopened.positions_of_said(ICP_SAID), &[0, 1, 2]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_index() -> Result<(), LogError> {
+        let path = log_path("invalid-index");
+        append(&path, &[ICP, ICP].concat());
+        let index = path.with_extension("cesr.idx");
+        fs::write(
+            &index,
+            r#"{"log_len":8,"last":"","entries":[{"offset":3,"len":5}]}"#,
+        )?;
+        let log = CesrLog::open(&path)?;
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.entries()[1].offset, ICP.len());
+
+        fs::write(&index, "not an index")?;
+        assert_eq!(CesrLog::open(&path)?.entries(), log.entries());
+
+        // Index of rewritten log isn't used, even if it fits into the log
+        fs::write(&path, [ICP, RCT].concat())?;
+        CesrLog::open(&path)?;
+        fs::write(&path, [RCT, ICP, ICP].concat())?;
+        let rewritten = CesrLog::open(&path)?;
+        assert_eq!(rewritten.len(), 3);
+        assert_eq!(rewritten.message_text(0)?, RCT);
+
+        // Log shorter than its index
+        fs::write(&path, [ICP, ICP].concat())?;
+        let mut log = CesrLog::open(&path)?;
+        fs::write(&path, ICP)?;
+        assert!(matches!(log.refresh(), Err(LogError::Truncated)));
+        assert!(matches!(log.message_text(1), Err(LogError::NoMessage(1))));
+        assert_eq!(log.refresh()?, 1);
+        assert_eq!(log.message_text(0)?, ICP);
+
+        // Log truncated and grown again past its previous size
+        fs::write(&path, [RCT, ICP, ICP].concat())?;
+        assert!(matches!(log.refresh(), Err(LogError::Truncated)));
+        assert_eq!(log.refresh()?, 3);
+        assert_eq!(log.message_text(0)?, RCT);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mapped_log() -> Result<(), LogError> {
+        let path = log_path("mapped");
+        append(&path, &[ICP, SIGNATURES].concat());
+        // SAFETY: log is only appended to while it's open.
+        let mut log = unsafe { CesrLog::open_mmap(&path)? };
+        assert_eq!(log.message_text(0)?, [ICP, SIGNATURES].concat());
+
+        append(&path, RCT);
+        assert_eq!(log.refresh()?, 1);
+        assert_eq!(log.message_text(1)?, RCT);
+        assert_eq!(CesrLog::open(&path)?.entries(), log.entries());
+        Ok(())
+    }
+}
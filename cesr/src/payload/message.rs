@@ -1,41 +1,42 @@
 use std::{collections::HashMap, io::Cursor};
 
 use nom::error::{make_error, ErrorKind};
+
+use crate::error::IResult;
 use rmp_serde as serde_mgpk;
-use serde::Deserialize;
-use serde_json::Value;
+use serde::{de::IgnoredAny, Deserialize};
 
-use super::Payload;
+use super::PayloadRef;
 
-pub(crate) fn json_message(s: &[u8]) -> nom::IResult<&[u8], Payload> {
-    let mut stream = serde_json::Deserializer::from_slice(s).into_iter::<Value>();
+pub(crate) fn json_message(s: &[u8]) -> IResult<&[u8], PayloadRef<'_>> {
+    let mut stream = serde_json::Deserializer::from_slice(s).into_iter::<IgnoredAny>();
     match stream.next() {
         Some(Ok(_event)) => Ok((
             &s[stream.byte_offset()..],
-            Payload::JSON(s[..stream.byte_offset()].to_vec()),
+            PayloadRef::JSON(&s[..stream.byte_offset()]),
         )),
         _ => Err(nom::Err::Error(make_error(s, ErrorKind::IsNot))),
     }
 }
 
-pub(crate) fn cbor_message(s: &[u8]) -> nom::IResult<&[u8], Payload> {
-    let mut stream = serde_cbor::Deserializer::from_slice(s).into_iter::<serde_cbor::Value>();
+pub(crate) fn cbor_message(s: &[u8]) -> IResult<&[u8], PayloadRef<'_>> {
+    let mut stream = serde_cbor::Deserializer::from_slice(s).into_iter::<IgnoredAny>();
     match stream.next() {
         Some(Ok(_event)) => Ok((
             &s[stream.byte_offset()..],
-            Payload::CBOR(s[..stream.byte_offset()].to_vec()),
+            PayloadRef::CBOR(&s[..stream.byte_offset()]),
         )),
         _ => Err(nom::Err::Error(make_error(s, ErrorKind::IsNot))),
     }
 }
 
-pub(crate) fn mgpk_message(s: &[u8]) -> nom::IResult<&[u8], Payload> {
+pub(crate) fn mgpk_message(s: &[u8]) -> IResult<&[u8], PayloadRef<'_>> {
     let mut deser = serde_mgpk::Deserializer::new(Cursor::new(s));
     let deserialized: Result<HashMap<String, String>, _> = Deserialize::deserialize(&mut deser);
     match deserialized {
         Ok(_event) => Ok((
             &s[deser.get_ref().position() as usize..],
-            Payload::MGPK(s[..deser.get_ref().position() as usize].to_vec()),
+            PayloadRef::MGPK(&s[..deser.get_ref().position() as usize]),
         )),
         _ => Err(nom::Err::Error(make_error(s, ErrorKind::IsNot))),
     }
@@ -1,6 +1,10 @@
 use nom::error::{make_error, ErrorKind};
 
-use crate::conversion::check_first_three_bits;
+use crate::{
+    config::ParserConfig,
+    conversion::{b64_to_u64, check_first_three_bits},
+    error::{IResult, StreamError, StreamErrorKind},
+};
 
 use self::message::{cbor_message, json_message, mgpk_message};
 mod message;
@@ -18,10 +22,67 @@ impl Payload {
             Payload::JSON(data) | Payload::CBOR(data) | Payload::MGPK(data) => data.clone(),
         }
     }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Payload::JSON(data) | Payload::CBOR(data) | Payload::MGPK(data) => data,
+        }
+    }
+}
+
+/// Payload borrowed from parsed stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayloadRef<'a> {
+    JSON(&'a [u8]),
+    CBOR(&'a [u8]),
+    MGPK(&'a [u8]),
+}
+
+impl<'a> PayloadRef<'a> {
+    pub fn as_bytes(&self) -> &'a [u8] {
+        match self {
+            PayloadRef::JSON(data) | PayloadRef::CBOR(data) | PayloadRef::MGPK(data) => data,
+        }
+    }
+
+    pub fn to_owned(&self) -> Payload {
+        match self {
+            PayloadRef::JSON(data) => Payload::JSON(data.to_vec()),
+            PayloadRef::CBOR(data) => Payload::CBOR(data.to_vec()),
+            PayloadRef::MGPK(data) => Payload::MGPK(data.to_vec()),
+        }
+    }
+}
+
+/// Start of JSON payload with version string field
+const VERSION_FIELD: &str = r#"{"v":""#;
+
+/// Returns size of JSON payload declared in its version string, that must be
+/// the first field of payload. Both version 1 (`KERI10JSON000188_`) and
+/// version 2 (`KERICAACAAJSONAAAB.`) strings are recognized. Payload is not
+/// parsed, so size is not checked.
+pub(crate) fn declared_size(stream: &str) -> Option<usize> {
+    let version = stream.strip_prefix(VERSION_FIELD)?;
+    let terminator = |i| version.as_bytes().get(i).copied();
+    if terminator(16) == Some(b'_') && version.get(6..10)? == "JSON" {
+        usize::from_str_radix(version.get(10..16)?, 16).ok()
+    } else if terminator(18) == Some(b'.') && version.get(10..14)? == "JSON" {
+        b64_to_u64(version.get(14..18)?)
+            .ok()
+            .map(|size| size as usize)
+    } else {
+        None
+    }
 }
 
 /// Tries to parse each possible serialization until it succeeds
-pub fn parse_payload(stream: &[u8]) -> nom::IResult<&[u8], Payload> {
+pub fn parse_payload(stream: &[u8]) -> IResult<&[u8], Payload> {
+    let (rest, payload) = parse_payload_ref(stream)?;
+    Ok((rest, payload.to_owned()))
+}
+
+/// Parses payload without copying it out of the stream.
+pub fn parse_payload_ref(stream: &[u8]) -> IResult<&[u8], PayloadRef<'_>> {
     let first_byte = stream
         .first()
         .ok_or(nom::Err::Error(make_error(stream, ErrorKind::Eof)))?;
@@ -34,3 +95,44 @@ pub fn parse_payload(stream: &[u8]) -> nom::IResult<&[u8], Payload> {
         _ => Err(nom::Err::Error(make_error(stream, ErrorKind::IsNot))),
     }
 }
+
+/// Parses payload at the start of text stream, reading no more than payload
+/// size limit of configuration allows. Payload that can't be parsed within
+/// the limit fails as too large.
+pub(crate) fn payload_with<'a>(
+    stream: &'a str,
+    config: &ParserConfig,
+) -> IResult<&'a str, PayloadRef<'a>> {
+    let mut bytes = stream.as_bytes();
+    if let Some(max) = config.limits.max_payload_size {
+        if declared_size(stream).is_some_and(|size| size > max) {
+            return Err(StreamError::failure(
+                stream,
+                StreamErrorKind::PayloadSizeLimit,
+            ));
+        }
+        bytes = &bytes[..bytes.len().min(max)];
+    }
+    match parse_payload_ref(bytes) {
+        Ok((_, payload)) => Ok((&stream[payload.as_bytes().len()..], payload)),
+        Err(_) if bytes.len() < stream.len() => Err(StreamError::failure(
+            stream,
+            StreamErrorKind::PayloadSizeLimit,
+        )),
+        Err(e) => Err(e.map(|e| StreamError::new(&stream[stream.len() - e.input.len()..], e.kind))),
+    }
+}
+
+#[test]
+fn test_declared_size() {
+    let v1 = r#"{"v":"KERI10JSON000188_","t":"icp"}"#;
+    assert_eq!(declared_size(v1), Some(0x188));
+    let v2 = r#"{"v":"KERICAACAAJSONAAAB.","t":"icp"}"#;
+    assert_eq!(declared_size(v2), Some(1));
+    assert_eq!(declared_size(r#"{"v":"KERI10CBOR000188_"}"#), None);
+    assert_eq!(
+        declared_size(r#"{"t":"icp","v":"KERI10JSON000188_"}"#),
+        None
+    );
+    assert_eq!(declared_size(r#"{"v":"KERI10JSON00"#), None);
+}
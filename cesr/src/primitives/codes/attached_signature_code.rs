@@ -1,12 +1,12 @@
 use crate::{
-    conversion::{adjust_with_num, b64_to_num},
+    conversion::{adjust_with_num, b64_to_num, write_b64_num},
     derivation_code::DerivationCode,
     error::Error,
     primitives::codes::self_signing::SelfSigning,
 };
-use core::str::FromStr;
+use core::{fmt, str::FromStr};
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum Index {
     BothSame(u16),
     Dual(u16, u16),
@@ -34,7 +34,7 @@ impl Index {
 /// Attached Signature Derivation Codes
 ///
 /// A self signing prefix derivation outputs a signature as its derivative (2.3.5)
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct AttachedSignatureCode {
     pub index: Index,
     pub code: SelfSigning,
@@ -110,7 +110,55 @@ impl DerivationCode for AttachedSignatureCode {
     }
 
     fn to_str(&self) -> String {
-        let code = match (self.code, self.index) {
+        let indexes_str = match self.index {
+            Index::BothSame(i) | Index::CurrentOnly(i) | Index::BigCurrentOnly(i) => {
+                adjust_with_num(i, self.soft_size())
+            }
+            Index::Dual(i, pi) | Index::BigDual(i, pi) => [
+                adjust_with_num(i, self.soft_size() / 2),
+                adjust_with_num(pi, self.soft_size() / 2),
+            ]
+            .join(""),
+        };
+        [self.hard_code(), &indexes_str].join("")
+    }
+
+    /// Fails if index doesn't fit into soft part of code.
+    fn write_code<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str(self.hard_code())?;
+        match self.index {
+            Index::BothSame(i) | Index::CurrentOnly(i) | Index::BigCurrentOnly(i) => {
+                write_b64_num(i as u64, self.soft_size(), writer)
+            }
+            Index::Dual(i, pi) | Index::BigDual(i, pi) => {
+                write_b64_num(i as u64, self.soft_size() / 2, writer)?;
+                write_b64_num(pi as u64, self.soft_size() / 2, writer)
+            }
+        }
+    }
+}
+
+impl AttachedSignatureCode {
+    /// Checks if indexes fit into soft part of code, so that code can be
+    /// encoded.
+    pub fn check_index(&self) -> Result<(), Error> {
+        let (indexes, size) = match self.index {
+            Index::BothSame(i) | Index::CurrentOnly(i) | Index::BigCurrentOnly(i) => {
+                (vec![i], self.soft_size())
+            }
+            Index::Dual(i, pi) | Index::BigDual(i, pi) => (vec![i, pi], self.soft_size() / 2),
+        };
+        match indexes.iter().find(|i| u64::from(**i) >> (6 * size) != 0) {
+            Some(i) => Err(Error::NumberOverflowError(format!(
+                "index {i} doesn't fit into {size} chars of {}",
+                self.hard_code()
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn hard_code(&self) -> &'static str {
+        match (self.code, self.index) {
             (SelfSigning::Ed25519Sha512, Index::BothSame(_)) => "A",
             (SelfSigning::Ed25519Sha512, Index::Dual(_, _))
             | (SelfSigning::Ed25519Sha512, Index::BigDual(_, _)) => "2A",
@@ -126,18 +174,7 @@ impl DerivationCode for AttachedSignatureCode {
             (SelfSigning::Ed448, Index::BigDual(_, _)) => "2C",
             (SelfSigning::Ed448, Index::CurrentOnly(_)) => "0B",
             (SelfSigning::Ed448, Index::BigCurrentOnly(_)) => "2D",
-        };
-        let indexes_str = match self.index {
-            Index::BothSame(i) | Index::CurrentOnly(i) | Index::BigCurrentOnly(i) => {
-                adjust_with_num(i, self.soft_size())
-            }
-            Index::Dual(i, pi) | Index::BigDual(i, pi) => [
-                adjust_with_num(i, self.soft_size() / 2),
-                adjust_with_num(pi, self.soft_size() / 2),
-            ]
-            .join(""),
-        };
-        [code, &indexes_str].join("")
+        }
     }
 }
 
@@ -145,44 +182,52 @@ impl FromStr for AttachedSignatureCode {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match &s[..1] {
+        match s.get(..1).ok_or(Error::EmptyCodeError)? {
             "A" => Ok(Self::new(
                 SelfSigning::Ed25519Sha512,
-                Index::BothSame(b64_to_num(&s[1..2])?),
+                Index::BothSame(b64_to_num(s.get(1..2).ok_or(Error::EmptyCodeError)?)?),
             )),
             "B" => Ok(Self::new(
                 SelfSigning::Ed25519Sha512,
-                Index::CurrentOnly(b64_to_num(&s[1..2])?),
+                Index::CurrentOnly(b64_to_num(s.get(1..2).ok_or(Error::EmptyCodeError)?)?),
             )),
             "C" => Ok(Self::new(
                 SelfSigning::ECDSAsecp256k1Sha256,
-                Index::BothSame(b64_to_num(&s[1..2])?),
+                Index::BothSame(b64_to_num(s.get(1..2).ok_or(Error::EmptyCodeError)?)?),
             )),
             "D" => Ok(Self::new(
                 SelfSigning::ECDSAsecp256k1Sha256,
-                Index::CurrentOnly(b64_to_num(&s[1..2])?),
+                Index::CurrentOnly(b64_to_num(s.get(1..2).ok_or(Error::EmptyCodeError)?)?),
             )),
-            "0" => match &s[1..2] {
+            "0" => match s.get(1..2).ok_or(Error::EmptyCodeError)? {
                 "A" => Ok(Self::new(
                     SelfSigning::Ed448,
-                    Index::Dual(b64_to_num(&s[2..3])?, b64_to_num(&s[3..4])?),
+                    Index::Dual(
+                        b64_to_num(s.get(2..3).ok_or(Error::EmptyCodeError)?)?,
+                        b64_to_num(s.get(3..4).ok_or(Error::EmptyCodeError)?)?,
+                    ),
                 )),
                 "B" => Ok(Self::new(
                     SelfSigning::Ed448,
-                    Index::CurrentOnly(b64_to_num(&s[2..4])?),
+                    Index::CurrentOnly(b64_to_num(s.get(2..4).ok_or(Error::EmptyCodeError)?)?),
                 )),
                 _ => Err(Error::UnknownCodeError),
             },
-            "2" => match &s[1..2] {
+            "2" => match s.get(1..2).ok_or(Error::EmptyCodeError)? {
                 "A" => Ok(Self::new(
                     SelfSigning::Ed25519Sha512,
-                    Index::BigDual(b64_to_num(&s[2..4])?, b64_to_num(&s[4..6])?),
+                    Index::BigDual(
+                        b64_to_num(s.get(2..4).ok_or(Error::EmptyCodeError)?)?,
+                        b64_to_num(s.get(4..6).ok_or(Error::EmptyCodeError)?)?,
+                    ),
                 )),
                 "B" => {
-                    if b64_to_num(&s[4..6])? == 0 {
+                    if b64_to_num(s.get(4..6).ok_or(Error::EmptyCodeError)?)? == 0 {
                         Ok(Self::new(
                             SelfSigning::Ed25519Sha512,
-                            Index::BigCurrentOnly(b64_to_num(&s[2..4])?),
+                            Index::BigCurrentOnly(b64_to_num(
+                                s.get(2..4).ok_or(Error::EmptyCodeError)?,
+                            )?),
                         ))
                     } else {
                         Err(Error::EmptyCodeError)
@@ -190,22 +235,25 @@ impl FromStr for AttachedSignatureCode {
                 }
                 "C" => Ok(Self::new(
                     SelfSigning::ECDSAsecp256k1Sha256,
-                    Index::BigDual(b64_to_num(&s[2..4])?, b64_to_num(&s[4..6])?),
+                    Index::BigDual(
+                        b64_to_num(s.get(2..4).ok_or(Error::EmptyCodeError)?)?,
+                        b64_to_num(s.get(4..6).ok_or(Error::EmptyCodeError)?)?,
+                    ),
                 )),
                 "D" => Ok(Self::new(
                     SelfSigning::ECDSAsecp256k1Sha256,
-                    Index::BigCurrentOnly(b64_to_num(&s[2..6])?),
+                    Index::BigCurrentOnly(b64_to_num(s.get(2..6).ok_or(Error::EmptyCodeError)?)?),
                 )),
                 _ => Err(Error::UnknownCodeError),
             },
-            "3" => match &s[1..2] {
+            "3" => match s.get(1..2).ok_or(Error::EmptyCodeError)? {
                 "A" => Ok(Self::new(
                     SelfSigning::Ed448,
-                    Index::BothSame(b64_to_num(&s[2..6])?),
+                    Index::BothSame(b64_to_num(s.get(2..6).ok_or(Error::EmptyCodeError)?)?),
                 )),
                 "B" => Ok(Self::new(
                     SelfSigning::Ed448,
-                    Index::CurrentOnly(b64_to_num(&s[2..10])?),
+                    Index::CurrentOnly(b64_to_num(s.get(2..10).ok_or(Error::EmptyCodeError)?)?),
                 )),
                 _ => Err(Error::UnknownCodeError),
             },
@@ -1,5 +1,6 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
+use super::table::{self, CodeEntry};
 use crate::{derivation_code::DerivationCode, error::Error};
 
 #[derive(Debug, PartialEq, Clone, Copy, Hash, Eq)]
@@ -14,42 +15,45 @@ pub enum Basic {
     X448,
 }
 
-impl DerivationCode for Basic {
-    fn value_size(&self) -> usize {
+impl Basic {
+    /// Entry of master code table with code and its sizes.
+    fn entry(&self) -> &'static CodeEntry {
         match self {
-            Self::Ed25519Nontrans | Self::Ed25519 | Self::X25519 => 43,
-            Self::X448 => 75,
-            Self::ECDSAsecp256k1Nontrans | Self::ECDSAsecp256k1 => 44,
-            Self::Ed448Nontrans | Self::Ed448 => 76,
+            Self::Ed25519Nontrans => const { table::entry("B") },
+            Self::X25519 => const { table::entry("C") },
+            Self::Ed25519 => const { table::entry("D") },
+            Self::X448 => const { table::entry("L") },
+            Self::ECDSAsecp256k1Nontrans => const { table::entry("1AAA") },
+            Self::ECDSAsecp256k1 => const { table::entry("1AAB") },
+            Self::Ed448Nontrans => const { table::entry("1AAC") },
+            Self::Ed448 => const { table::entry("1AAD") },
         }
     }
 
-    fn soft_size(&self) -> usize {
-        0
+    fn as_str(&self) -> &'static str {
+        self.entry().code
     }
+}
 
+impl DerivationCode for Basic {
     fn hard_size(&self) -> usize {
-        match self {
-            Self::Ed25519Nontrans | Self::X25519 | Self::Ed25519 | Self::X448 => 1,
-            Self::ECDSAsecp256k1Nontrans
-            | Self::ECDSAsecp256k1
-            | Self::Ed448Nontrans
-            | Self::Ed448 => 4,
-        }
+        self.entry().hard()
+    }
+
+    fn soft_size(&self) -> usize {
+        self.entry().soft
+    }
+
+    fn value_size(&self) -> usize {
+        self.entry().value_size()
     }
 
     fn to_str(&self) -> String {
-        match self {
-            Self::Ed25519Nontrans => "B",
-            Self::X25519 => "C",
-            Self::Ed25519 => "D",
-            Self::X448 => "L",
-            Self::ECDSAsecp256k1Nontrans => "1AAA",
-            Self::ECDSAsecp256k1 => "1AAB",
-            Self::Ed448Nontrans => "1AAC",
-            Self::Ed448 => "1AAD",
-        }
-        .into()
+        self.as_str().into()
+    }
+
+    fn write_code<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str(self.as_str())
     }
 }
 
@@ -62,7 +66,7 @@ impl FromStr for Basic {
             "C" => Ok(Self::X25519),
             "D" => Ok(Self::Ed25519),
             "L" => Ok(Self::X448),
-            "1" => match &s[1..4] {
+            "1" => match s.get(1..4).ok_or(Error::EmptyCodeError)? {
                 "AAA" => Ok(Self::ECDSAsecp256k1Nontrans),
                 "AAB" => Ok(Self::ECDSAsecp256k1),
                 "AAC" => Ok(Self::Ed448Nontrans),
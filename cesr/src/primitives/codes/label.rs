@@ -0,0 +1,80 @@
+use std::{fmt, str::FromStr};
+
+use super::table::{self, CodeEntry};
+use crate::{derivation_code::DerivationCode, error::Error};
+
+/// Codes for short labels which are not Base64 text. Raw value contains lead
+/// bytes, so `Label1` raw is a zero byte followed by one label byte.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum LabelCode {
+    // 1 byte label with 1 lead byte
+    Label1,
+    // 2 bytes label
+    Label2,
+}
+
+impl LabelCode {
+    /// Size of label in bytes.
+    pub fn raw_size(&self) -> usize {
+        match self {
+            LabelCode::Label1 => 1,
+            LabelCode::Label2 => 2,
+        }
+    }
+
+    /// Number of zero bytes prepended to label.
+    pub fn lead_size(&self) -> usize {
+        match self {
+            LabelCode::Label1 => 1,
+            LabelCode::Label2 => 0,
+        }
+    }
+}
+
+impl LabelCode {
+    /// Entry of master code table with code and its sizes.
+    fn entry(&self) -> &'static CodeEntry {
+        match self {
+            LabelCode::Label1 => const { table::entry("V") },
+            LabelCode::Label2 => const { table::entry("W") },
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        self.entry().code
+    }
+}
+
+impl DerivationCode for LabelCode {
+    fn hard_size(&self) -> usize {
+        self.entry().hard()
+    }
+
+    fn soft_size(&self) -> usize {
+        self.entry().soft
+    }
+
+    fn value_size(&self) -> usize {
+        self.entry().value_size()
+    }
+
+    fn to_str(&self) -> String {
+        self.as_str().into()
+    }
+
+    fn write_code<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str(self.as_str())
+    }
+}
+
+impl FromStr for LabelCode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.get(..1).ok_or(Error::EmptyCodeError)? {
+            "V" => Ok(LabelCode::Label1),
+            "W" => Ok(LabelCode::Label2),
+            _ => Err(Error::UnknownCodeError),
+        }
+    }
+}
@@ -1,21 +1,48 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use crate::{derivation_code::DerivationCode, error::Error};
 
 use self::{
-    attached_signature_code::AttachedSignatureCode, basic::Basic, rand_128::Rand128Code,
-    seed::SeedCode, self_addressing::SelfAddressing, self_signing::SelfSigning,
-    timestamp::TimestampCode,
+    attached_signature_code::AttachedSignatureCode, basic::Basic, label::LabelCode,
+    number::NumberCode, rand_128::Rand128Code, seed::SeedCode, self_addressing::SelfAddressing,
+    self_signing::SelfSigning, special::SpecialCode, timestamp::TimestampCode,
 };
 
 pub mod attached_signature_code;
 pub mod basic;
+pub mod label;
+pub mod number;
 pub mod rand_128;
 pub mod seed;
 pub mod self_addressing;
 pub mod self_signing;
+pub mod special;
+pub mod table;
+pub mod tag;
 pub mod timestamp;
 
+pub use self::tag::TagCode;
+
+use self::table::CodeKind;
+
+/// Returns code and value sizes of fixed size primitive with code unknown to
+/// this crate. Sizes are taken from master code table.
+pub(crate) fn unknown_fixed_size(s: &str) -> Option<(usize, usize)> {
+    table::lookup(s)
+        .filter(|entry| entry.kind == CodeKind::Unsupported)
+        .map(|entry| (entry.code_size(), entry.full - entry.code_size()))
+}
+
+/// Meaning of `0A` code, which is shared by random salt and serial number.
+/// It can't be told from the code itself, so it's up to the caller to say
+/// what is expected in place of the primitive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RandomContext {
+    #[default]
+    Salt,
+    SerialNumber,
+}
+
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub enum PrimitiveCode {
     Seed(SeedCode),
@@ -27,60 +54,40 @@ pub enum PrimitiveCode {
     IndexedSignature(AttachedSignatureCode),
     Timestamp(TimestampCode),
     Tag(TagCode),
+    Number(NumberCode),
+    Label(LabelCode),
+    Special(SpecialCode),
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
-pub enum TagCode {
-    // 3 B64 encoded chars for special values
-    Tag3([char; 3]),
-    // 7 B64 encoded chars for special values
-    Tag7([char; 7]),
-}
-
-impl DerivationCode for TagCode {
-    fn hard_size(&self) -> usize {
-        1
-    }
-
-    fn soft_size(&self) -> usize {
-        match self {
-            TagCode::Tag3(_) => 3,
-            TagCode::Tag7(_) => 7,
-        }
+impl From<Basic> for PrimitiveCode {
+    fn from(code: Basic) -> Self {
+        PrimitiveCode::Basic(code)
     }
+}
 
-    fn value_size(&self) -> usize {
-        0
+impl From<SelfAddressing> for PrimitiveCode {
+    fn from(code: SelfAddressing) -> Self {
+        PrimitiveCode::SelfAddressing(code)
     }
+}
 
-    fn to_str(&self) -> String {
-        match self {
-            TagCode::Tag3(chars) => format!("X{}", chars.iter().collect::<String>()),
-            TagCode::Tag7(chars) => format!("Y{}", chars.iter().collect::<String>()),
-        }
+impl From<SelfSigning> for PrimitiveCode {
+    fn from(code: SelfSigning) -> Self {
+        PrimitiveCode::SelfSigning(code)
     }
 }
 
-fn str_to_char_array<const N: usize>(s: &str) -> Option<[char; N]> {
-    let chars: Vec<char> = s.chars().collect();
-    chars.try_into().ok()
+impl From<AttachedSignatureCode> for PrimitiveCode {
+    fn from(code: AttachedSignatureCode) -> Self {
+        PrimitiveCode::IndexedSignature(code)
+    }
 }
 
-impl FromStr for TagCode {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match &s[..1] {
-            "X" => {
-                let chars = &s[1..4];
-                Ok(TagCode::Tag3(str_to_char_array(chars).unwrap()))
-            }
-            "Y" => {
-                let chars = &s[1..8];
-                Ok(TagCode::Tag7(str_to_char_array(chars).unwrap()))
-            }
-            _ => Err(Error::UnknownCodeError),
-        }
+/// Random code is ambiguous, it is read as random value rather than serial
+/// number.
+impl From<Rand128Code> for PrimitiveCode {
+    fn from(code: Rand128Code) -> Self {
+        PrimitiveCode::Random(code)
     }
 }
 
@@ -95,38 +102,40 @@ impl PrimitiveCode {
             PrimitiveCode::IndexedSignature(code) => code.to_str(),
             PrimitiveCode::Timestamp(code) => code.to_str(),
             PrimitiveCode::Tag(code) => code.to_str(),
+            PrimitiveCode::Number(code) => code.to_str(),
+            PrimitiveCode::Label(code) => code.to_str(),
+            PrimitiveCode::Special(code) => code.to_str(),
         }
     }
 }
 
+impl PrimitiveCode {
+    /// Parses code at the start of `s`, resolving `0A` according to
+    /// `context`. Code is found in master code table by its selector, and
+    /// taken from its entry. Only tags, which code includes soft part, are
+    /// parsed further.
+    pub fn from_str_with_context(s: &str, context: RandomContext) -> Result<Self, Error> {
+        let entry = table::lookup(s).ok_or(Error::UnknownCodeError)?;
+        match (&entry.primitive, entry.kind) {
+            (Some(PrimitiveCode::Random(code)), _) => Ok(match context {
+                RandomContext::Salt => PrimitiveCode::Random(code.clone()),
+                RandomContext::SerialNumber => PrimitiveCode::SerialNumber(code.clone()),
+            }),
+            (Some(code), _) => Ok(code.clone()),
+            (None, CodeKind::Tag) => TagCode::from_str(s).map(PrimitiveCode::Tag),
+            (None, _) => Err(Error::UnknownCodeError),
+        }
+    }
+}
+
+/// Indexed signature codes come from a separate code table, so they are not
+/// parsed here. `0A` is read as random salt, see
+/// `PrimitiveCode::from_str_with_context`.
 impl FromStr for PrimitiveCode {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use PrimitiveCode::*;
-
-        type ParserFn = fn(&str) -> Result<PrimitiveCode, Error>;
-
-        let parsers: &[ParserFn] = &[
-            |s| Rand128Code::from_str(s).map(Random),
-            |s| SeedCode::from_str(s).map(Seed),
-            |s| AttachedSignatureCode::from_str(s).map(IndexedSignature),
-            |s| basic::Basic::from_str(s).map(Basic),
-            |s| self_addressing::SelfAddressing::from_str(s).map(SelfAddressing),
-            |s| self_signing::SelfSigning::from_str(s).map(SelfSigning),
-            |s| Rand128Code::from_str(s).map(SerialNumber),
-            |s| SeedCode::from_str(s).map(Seed),
-            |s| TimestampCode::from_str(s).map(Timestamp),
-            |s| TagCode::from_str(s).map(Tag),
-        ];
-
-        for parser in parsers {
-            if let Ok(code) = parser(s) {
-                return Ok(code);
-            }
-        }
-
-        Err(Error::UnknownCodeError)
+        Self::from_str_with_context(s, RandomContext::default())
     }
 }
 
@@ -141,6 +150,9 @@ impl DerivationCode for PrimitiveCode {
             PrimitiveCode::IndexedSignature(i) => i.hard_size(),
             PrimitiveCode::Timestamp(code) => code.hard_size(),
             PrimitiveCode::Tag(tag_code) => tag_code.hard_size(),
+            PrimitiveCode::Number(code) => code.hard_size(),
+            PrimitiveCode::Label(code) => code.hard_size(),
+            PrimitiveCode::Special(code) => code.hard_size(),
         }
     }
 
@@ -154,6 +166,9 @@ impl DerivationCode for PrimitiveCode {
             PrimitiveCode::IndexedSignature(i) => i.soft_size(),
             PrimitiveCode::Timestamp(code) => code.soft_size(),
             PrimitiveCode::Tag(tag_code) => tag_code.soft_size(),
+            PrimitiveCode::Number(code) => code.soft_size(),
+            PrimitiveCode::Label(code) => code.soft_size(),
+            PrimitiveCode::Special(code) => code.soft_size(),
         }
     }
 
@@ -167,6 +182,27 @@ impl DerivationCode for PrimitiveCode {
             PrimitiveCode::IndexedSignature(i) => i.value_size(),
             PrimitiveCode::Timestamp(code) => code.value_size(),
             PrimitiveCode::Tag(tag_code) => tag_code.value_size(),
+            PrimitiveCode::Number(code) => code.value_size(),
+            PrimitiveCode::Label(code) => code.value_size(),
+            PrimitiveCode::Special(code) => code.value_size(),
+        }
+    }
+
+    fn write_code<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        match self {
+            PrimitiveCode::Seed(s) => s.write_code(writer),
+            PrimitiveCode::Basic(b) => b.write_code(writer),
+            PrimitiveCode::SelfAddressing(sa) => sa.write_code(writer),
+            PrimitiveCode::SelfSigning(ss) => ss.write_code(writer),
+            PrimitiveCode::SerialNumber(code) | PrimitiveCode::Random(code) => {
+                code.write_code(writer)
+            }
+            PrimitiveCode::IndexedSignature(i) => i.write_code(writer),
+            PrimitiveCode::Timestamp(code) => code.write_code(writer),
+            PrimitiveCode::Tag(tag_code) => tag_code.write_code(writer),
+            PrimitiveCode::Number(code) => code.write_code(writer),
+            PrimitiveCode::Label(code) => code.write_code(writer),
+            PrimitiveCode::Special(code) => code.write_code(writer),
         }
     }
 
@@ -180,6 +216,69 @@ impl DerivationCode for PrimitiveCode {
             PrimitiveCode::IndexedSignature(i) => i.to_str(),
             PrimitiveCode::Timestamp(code) => code.to_str(),
             PrimitiveCode::Tag(tag_code) => tag_code.to_str(),
+            PrimitiveCode::Number(code) => code.to_str(),
+            PrimitiveCode::Label(code) => code.to_str(),
+            PrimitiveCode::Special(code) => code.to_str(),
+        }
+    }
+}
+
+#[test]
+fn test_unsupported_codes_are_unknown() {
+    for entry in table::MASTER_CODES
+        .iter()
+        .filter(|entry| entry.kind == CodeKind::Unsupported)
+    {
+        let text = [entry.code, &"A".repeat(entry.full - entry.hard())].concat();
+        assert!(PrimitiveCode::from_str(&text).is_err());
+        assert_eq!(
+            unknown_fixed_size(&text),
+            Some((entry.hard(), entry.full - entry.hard()))
+        );
+    }
+}
+
+#[test]
+fn test_code_table_sizes() {
+    for entry in table::MASTER_CODES
+        .iter()
+        .filter(|entry| entry.kind != CodeKind::Unsupported)
+    {
+        let text = [entry.code, &"A".repeat(entry.full - entry.hard())].concat();
+        let code = PrimitiveCode::from_str(&text)
+            .or_else(|_| {
+                // Tags of odd length are prepended with pad char
+                let padded = [entry.code, "_", &text[entry.hard() + 1..]].concat();
+                PrimitiveCode::from_str(&padded)
+            })
+            .unwrap();
+        assert_eq!(table::lookup(&text), Some(entry));
+        if let Some(primitive) = &entry.primitive {
+            assert_eq!(primitive, &code, "{}", entry.code);
         }
+        assert_eq!(code.hard_size(), entry.hard(), "{}", entry.code);
+        assert_eq!(code.soft_size(), entry.soft, "{}", entry.code);
+        assert_eq!(code.full_size(), entry.full, "{}", entry.code);
     }
+    assert_eq!(table::lookup("2AAA"), None);
+    assert_eq!(table::lookup("1ABA"), None);
+    assert_eq!(table::lookup(""), None);
+}
+
+#[test]
+fn test_random_context() {
+    let text = "0AAAAAAAAAAAAAAAAAAAAAAB";
+    assert_eq!(
+        PrimitiveCode::from_str(text).unwrap(),
+        PrimitiveCode::Random(Rand128Code)
+    );
+    assert_eq!(
+        PrimitiveCode::from_str_with_context(text, RandomContext::SerialNumber).unwrap(),
+        PrimitiveCode::SerialNumber(Rand128Code)
+    );
+    // Context doesn't affect other codes
+    assert_eq!(
+        PrimitiveCode::from_str_with_context("0BAA", RandomContext::SerialNumber).unwrap(),
+        PrimitiveCode::SelfSigning(SelfSigning::Ed25519Sha512)
+    );
 }
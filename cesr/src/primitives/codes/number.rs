@@ -0,0 +1,191 @@
+use std::{fmt, str::FromStr};
+
+use super::table::{self, CodeEntry};
+use crate::{conversion::write_b64, derivation_code::DerivationCode, error::Error};
+
+/// Number codes of increasing raw width, used for serial numbers, thresholds
+/// and weights.
+#[derive(Debug, PartialEq, Clone, Copy, Hash, Eq)]
+pub enum NumberCode {
+    /// 2 byte number
+    Short,
+    /// 4 byte number
+    Long,
+    /// 5 byte number
+    Tall,
+    /// 8 byte number
+    Big,
+    /// 11 byte number
+    Large,
+    /// 14 byte number
+    Great,
+    /// 17 byte number
+    Vast,
+}
+
+impl NumberCode {
+    /// Size of raw number in bytes.
+    pub fn raw_size(&self) -> usize {
+        match self {
+            NumberCode::Short => 2,
+            NumberCode::Long => 4,
+            NumberCode::Tall => 5,
+            NumberCode::Big => 8,
+            NumberCode::Large => 11,
+            NumberCode::Great => 14,
+            NumberCode::Vast => 17,
+        }
+    }
+
+    /// Returns the smallest code that can hold provided number.
+    pub fn for_value(num: u128) -> Self {
+        let significant_bytes = 16 - (num.leading_zeros() / 8) as usize;
+        [
+            NumberCode::Short,
+            NumberCode::Long,
+            NumberCode::Tall,
+            NumberCode::Big,
+            NumberCode::Large,
+            NumberCode::Great,
+        ]
+        .into_iter()
+        .find(|code| code.raw_size() >= significant_bytes)
+        .unwrap_or(NumberCode::Vast)
+    }
+
+    /// Left pads big endian bytes of number with zeros to code raw size.
+    pub fn to_raw(&self, num: u128) -> Result<Vec<u8>, Error> {
+        let be_bytes = num.to_be_bytes();
+        let significant_bytes = 16 - (num.leading_zeros() / 8) as usize;
+        if significant_bytes > self.raw_size() {
+            return Err(Error::NumberOverflowError(format!(
+                "{} doesn't fit into {} bytes",
+                num,
+                self.raw_size()
+            )));
+        }
+        Ok(std::iter::repeat_n(0, self.raw_size() - significant_bytes)
+            .chain(be_bytes[16 - significant_bytes..].iter().copied())
+            .collect())
+    }
+}
+
+impl NumberCode {
+    /// Entry of master code table with code and its sizes.
+    fn entry(&self) -> &'static CodeEntry {
+        match self {
+            NumberCode::Short => const { table::entry("M") },
+            NumberCode::Long => const { table::entry("0H") },
+            NumberCode::Tall => const { table::entry("R") },
+            NumberCode::Big => const { table::entry("N") },
+            NumberCode::Large => const { table::entry("S") },
+            NumberCode::Great => const { table::entry("T") },
+            NumberCode::Vast => const { table::entry("U") },
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        self.entry().code
+    }
+}
+
+impl DerivationCode for NumberCode {
+    fn hard_size(&self) -> usize {
+        self.entry().hard()
+    }
+
+    fn soft_size(&self) -> usize {
+        self.entry().soft
+    }
+
+    fn value_size(&self) -> usize {
+        self.entry().value_size()
+    }
+
+    fn to_str(&self) -> String {
+        self.as_str().into()
+    }
+
+    fn write_code<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str(self.as_str())
+    }
+}
+
+impl FromStr for NumberCode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.get(..1).ok_or(Error::EmptyCodeError)? {
+            "M" => Ok(NumberCode::Short),
+            "R" => Ok(NumberCode::Tall),
+            "N" => Ok(NumberCode::Big),
+            "S" => Ok(NumberCode::Large),
+            "T" => Ok(NumberCode::Great),
+            "U" => Ok(NumberCode::Vast),
+            "0" => match s.get(1..2).ok_or(Error::EmptyCodeError)? {
+                "H" => Ok(NumberCode::Long),
+                _ => Err(Error::UnknownCodeError),
+            },
+            _ => Err(Error::UnknownCodeError),
+        }
+    }
+}
+
+/// Encodes number using the smallest fitting number code.
+pub fn pack_number(num: impl Into<u128>) -> String {
+    let num = num.into();
+    let mut packed = String::with_capacity(NumberCode::for_value(num).full_size());
+    write_number(num, &mut packed).expect("writing to String");
+    packed
+}
+
+/// Writes number as `pack_number` encodes it.
+pub(crate) fn write_number<W: fmt::Write + ?Sized>(num: u128, writer: &mut W) -> fmt::Result {
+    let code = NumberCode::for_value(num);
+    // Widest code holds 17 bytes, big endian bytes of number are left padded
+    let mut raw = [0u8; 17];
+    raw[1..].copy_from_slice(&num.to_be_bytes());
+    code.write_code(writer)?;
+    write_b64(&raw[17 - code.raw_size()..], code.code_size() % 4, writer)
+}
+
+/// Decodes raw number bytes. Fails if number doesn't fit into `u128`.
+pub fn unpack_number(raw: &[u8]) -> Result<u128, Error> {
+    let leading_zeros = raw.iter().take_while(|b| **b == 0).count();
+    let significant = &raw[leading_zeros..];
+    if significant.len() > 16 {
+        return Err(Error::NumberOverflowError(format!(
+            "{} significant bytes exceed u128",
+            significant.len()
+        )));
+    }
+    let mut be_bytes = [0u8; 16];
+    be_bytes[16 - significant.len()..].copy_from_slice(significant);
+    Ok(u128::from_be_bytes(be_bytes))
+}
+
+#[test]
+pub fn test_pack_number() {
+    assert_eq!(pack_number(0u8), "MAAA");
+    assert_eq!(pack_number(1u16), "MAAB");
+    assert_eq!(pack_number(1000u32), "MAPo");
+    assert_eq!(pack_number(u16::MAX as u32 + 1), "0HAAAQAA");
+    assert_eq!(pack_number(u32::MAX as u64 + 1), "RAEAAAAA");
+    assert_eq!(pack_number(u64::MAX), "NP__________");
+    assert_eq!(pack_number(u64::MAX as u128 + 1), "SAAAAQAAAAAAAAAA");
+    assert_eq!(pack_number(u128::MAX), "UAD_____________________");
+
+    for num in [0, 1, 4096, u32::MAX as u128, u64::MAX as u128, u128::MAX] {
+        let packed = pack_number(num);
+        let code: NumberCode = packed.parse().unwrap();
+        assert_eq!(packed.len(), code.full_size());
+    }
+}
+
+#[test]
+pub fn test_number_overflow() {
+    assert_eq!(unpack_number(&[0, 0, 1]), Ok(1));
+    assert_eq!(unpack_number(&[0; 17]), Ok(0));
+    assert!(unpack_number(&[1; 17]).is_err());
+    assert!(NumberCode::Short.to_raw(u16::MAX as u128 + 1).is_err());
+}
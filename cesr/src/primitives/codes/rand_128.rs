@@ -1,27 +1,32 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
-use crate::{conversion::from_bytes_to_text, derivation_code::DerivationCode, error::Error};
+use super::table;
+use crate::{conversion::write_b64, derivation_code::DerivationCode, error::Error};
 
 // Random salt, seed, nonce, private key, or sequence number of length 128 bits
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Hash)]
 pub struct Rand128Code;
 
 impl DerivationCode for Rand128Code {
     fn hard_size(&self) -> usize {
-        2
+        const { table::entry("0A") }.hard()
     }
 
     fn soft_size(&self) -> usize {
-        0
+        const { table::entry("0A") }.soft
     }
 
     fn value_size(&self) -> usize {
-        22
+        const { table::entry("0A") }.value_size()
     }
 
     fn to_str(&self) -> String {
         "0A".into()
     }
+
+    fn write_code<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str("0A")
+    }
 }
 
 impl FromStr for Rand128Code {
@@ -38,20 +43,18 @@ impl FromStr for Rand128Code {
 }
 
 pub fn pack_sn(sn: u64) -> String {
-    let payload_type = Rand128Code;
-    let sn_raw: Vec<u8> = sn.to_be_bytes().into();
+    let mut packed = String::with_capacity(Rand128Code.full_size());
+    write_sn(sn, &mut packed).expect("writing to String");
+    packed
+}
 
-    // Calculate how many zeros are missing to achieve expected base64 string
-    // length. Master code size is expected padding size.
-    let missing_zeros = payload_type.full_size() / 4 * 3 - payload_type.code_size() - sn_raw.len();
-    let sn_vec: Vec<u8> = std::iter::repeat_n(0, missing_zeros)
-        .chain(sn_raw)
-        .collect();
-    [
-        payload_type.to_str(),
-        from_bytes_to_text(&sn_vec)[2..].to_string(),
-    ]
-    .join("")
+/// Writes serial number as 128 bits number of `Rand128Code`, as `pack_sn`
+/// encodes it.
+pub(crate) fn write_sn<W: fmt::Write + ?Sized>(sn: u64, writer: &mut W) -> fmt::Result {
+    let mut raw = [0u8; 16];
+    raw[8..].copy_from_slice(&sn.to_be_bytes());
+    Rand128Code.write_code(writer)?;
+    write_b64(&raw, Rand128Code.code_size(), writer)
 }
 
 #[test]
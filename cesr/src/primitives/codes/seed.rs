@@ -1,5 +1,6 @@
 use std::str::FromStr;
 
+use super::table::{self, CodeEntry};
 use crate::{derivation_code::DerivationCode, error::Error};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -9,33 +10,36 @@ pub enum SeedCode {
     RandomSeed448,
 }
 
-impl DerivationCode for SeedCode {
-    fn value_size(&self) -> usize {
+impl SeedCode {
+    /// Entry of master code table with code and its sizes.
+    fn entry(&self) -> &'static CodeEntry {
         match self {
-            SeedCode::RandomSeed256Ed25519 => 43,
-            SeedCode::RandomSeed256ECDSAsecp256k1 => 75,
-            SeedCode::RandomSeed448 => 22,
+            Self::RandomSeed256Ed25519 => const { table::entry("A") },
+            Self::RandomSeed256ECDSAsecp256k1 => const { table::entry("J") },
+            Self::RandomSeed448 => const { table::entry("K") },
         }
     }
 
-    fn soft_size(&self) -> usize {
-        0
+    fn as_str(&self) -> &'static str {
+        self.entry().code
     }
+}
 
+impl DerivationCode for SeedCode {
     fn hard_size(&self) -> usize {
-        match self {
-            SeedCode::RandomSeed256Ed25519 => 1,
-            SeedCode::RandomSeed256ECDSAsecp256k1 => 1,
-            SeedCode::RandomSeed448 => 2,
-        }
+        self.entry().hard()
+    }
+
+    fn soft_size(&self) -> usize {
+        self.entry().soft
+    }
+
+    fn value_size(&self) -> usize {
+        self.entry().value_size()
     }
 
     fn to_str(&self) -> String {
-        match self {
-            Self::RandomSeed256Ed25519 => "A".to_string(),
-            Self::RandomSeed256ECDSAsecp256k1 => "J".to_string(),
-            Self::RandomSeed448 => "K".to_string(),
-        }
+        self.as_str().into()
     }
 }
 
@@ -43,7 +47,7 @@ impl FromStr for SeedCode {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match &s[..1] {
+        match s.get(..1).ok_or(Error::EmptyCodeError)? {
             "A" => Ok(Self::RandomSeed256Ed25519),
             "J" => Ok(Self::RandomSeed256ECDSAsecp256k1),
             "K" => Ok(Self::RandomSeed448),
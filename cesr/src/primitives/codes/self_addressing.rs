@@ -1,5 +1,6 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
+use super::table::{self, CodeEntry};
 use crate::{derivation_code::DerivationCode, error::Error};
 
 #[derive(Debug, PartialEq, Clone, Hash, Eq)]
@@ -15,46 +16,75 @@ pub enum SelfAddressing {
     SHA2_512,
 }
 
-impl DerivationCode for SelfAddressing {
-    fn value_size(&self) -> usize {
+impl SelfAddressing {
+    /// Entry of master code table with code and its sizes.
+    fn entry(&self) -> &'static CodeEntry {
         match self {
-            Self::Blake3_256
-            | Self::Blake2B256
-            | Self::Blake2S256
-            | Self::SHA3_256
-            | Self::SHA2_256 => 43,
-            Self::Blake3_512 | Self::SHA3_512 | Self::Blake2B512 | Self::SHA2_512 => 86,
+            Self::Blake3_256 => const { table::entry("E") },
+            Self::Blake2B256 => const { table::entry("F") },
+            Self::Blake2S256 => const { table::entry("G") },
+            Self::SHA3_256 => const { table::entry("H") },
+            Self::SHA2_256 => const { table::entry("I") },
+            Self::Blake3_512 => const { table::entry("0D") },
+            Self::SHA3_512 => const { table::entry("0E") },
+            Self::Blake2B512 => const { table::entry("0F") },
+            Self::SHA2_512 => const { table::entry("0G") },
         }
     }
 
-    fn soft_size(&self) -> usize {
-        0
+    fn as_str(&self) -> &'static str {
+        self.entry().code
     }
+}
+
+#[cfg(feature = "digest")]
+impl SelfAddressing {
+    /// Hashes data with algorithm of the code, into raw digest of the size
+    /// the code requires.
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        use blake2::{digest::consts::U32, Blake2b, Blake2b512, Blake2s256, Digest};
+        use sha2::{Sha256, Sha512};
+        use sha3::{Sha3_256, Sha3_512};
 
-    fn hard_size(&self) -> usize {
         match self {
-            Self::Blake3_256
-            | Self::Blake2B256
-            | Self::Blake2S256
-            | Self::SHA3_256
-            | Self::SHA2_256 => 1,
-            Self::Blake3_512 | Self::SHA3_512 | Self::Blake2B512 | Self::SHA2_512 => 2,
+            Self::Blake3_256 => blake3::hash(data).as_bytes().to_vec(),
+            Self::Blake2B256 => Blake2b::<U32>::digest(data).to_vec(),
+            Self::Blake2S256 => Blake2s256::digest(data).to_vec(),
+            Self::SHA3_256 => Sha3_256::digest(data).to_vec(),
+            Self::SHA2_256 => Sha256::digest(data).to_vec(),
+            Self::Blake3_512 => {
+                let mut out = vec![0; 64];
+                let mut hasher = blake3::Hasher::new();
+                hasher.update(data);
+                hasher.finalize_xof().fill(&mut out);
+                out
+            }
+            Self::SHA3_512 => Sha3_512::digest(data).to_vec(),
+            Self::Blake2B512 => Blake2b512::digest(data).to_vec(),
+            Self::SHA2_512 => Sha512::digest(data).to_vec(),
         }
     }
+}
+
+impl DerivationCode for SelfAddressing {
+    fn hard_size(&self) -> usize {
+        self.entry().hard()
+    }
+
+    fn soft_size(&self) -> usize {
+        self.entry().soft
+    }
+
+    fn value_size(&self) -> usize {
+        self.entry().value_size()
+    }
 
     fn to_str(&self) -> String {
-        match self {
-            Self::Blake3_256 => "E",
-            Self::Blake2B256 => "F",
-            Self::Blake2S256 => "G",
-            Self::SHA3_256 => "H",
-            Self::SHA2_256 => "I",
-            Self::Blake3_512 => "0D",
-            Self::SHA3_512 => "0E",
-            Self::Blake2B512 => "0F",
-            Self::SHA2_512 => "0G",
-        }
-        .into()
+        self.as_str().into()
+    }
+
+    fn write_code<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str(self.as_str())
     }
 }
 
@@ -68,7 +98,7 @@ impl FromStr for SelfAddressing {
             "G" => Ok(Self::Blake2S256),
             "H" => Ok(Self::SHA3_256),
             "I" => Ok(Self::SHA2_256),
-            "0" => match &s[1..2] {
+            "0" => match s.get(1..2).ok_or(Error::EmptyCodeError)? {
                 "D" => Ok(Self::Blake3_512),
                 "E" => Ok(Self::SHA3_512),
                 "F" => Ok(Self::Blake2B512),
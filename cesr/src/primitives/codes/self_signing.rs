@@ -1,5 +1,6 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
+use super::table::{self, CodeEntry};
 use crate::{derivation_code::DerivationCode, error::Error};
 
 #[derive(Debug, PartialEq, Clone, Copy, Hash, Eq)]
@@ -9,32 +10,40 @@ pub enum SelfSigning {
     Ed448,
 }
 
-impl DerivationCode for SelfSigning {
-    fn value_size(&self) -> usize {
+impl SelfSigning {
+    /// Entry of master code table with code and its sizes.
+    fn entry(&self) -> &'static CodeEntry {
         match self {
-            Self::Ed25519Sha512 | Self::ECDSAsecp256k1Sha256 => 86,
-            Self::Ed448 => 152,
+            Self::Ed25519Sha512 => const { table::entry("0B") },
+            Self::ECDSAsecp256k1Sha256 => const { table::entry("0C") },
+            Self::Ed448 => const { table::entry("1AAE") },
         }
     }
 
-    fn soft_size(&self) -> usize {
-        0
+    fn as_str(&self) -> &'static str {
+        self.entry().code
     }
+}
 
+impl DerivationCode for SelfSigning {
     fn hard_size(&self) -> usize {
-        match self {
-            Self::Ed25519Sha512 | Self::ECDSAsecp256k1Sha256 => 2,
-            Self::Ed448 => 4,
-        }
+        self.entry().hard()
+    }
+
+    fn soft_size(&self) -> usize {
+        self.entry().soft
+    }
+
+    fn value_size(&self) -> usize {
+        self.entry().value_size()
     }
 
     fn to_str(&self) -> String {
-        match self {
-            Self::Ed25519Sha512 => "0B",
-            Self::ECDSAsecp256k1Sha256 => "0C",
-            Self::Ed448 => "1AAE",
-        }
-        .into()
+        self.as_str().into()
+    }
+
+    fn write_code<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str(self.as_str())
     }
 }
 
@@ -43,12 +52,12 @@ impl FromStr for SelfSigning {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.get(..1).ok_or(Error::EmptyCodeError)? {
-            "0" => match &s[1..2] {
+            "0" => match s.get(1..2).ok_or(Error::EmptyCodeError)? {
                 "B" => Ok(Self::Ed25519Sha512),
                 "C" => Ok(Self::ECDSAsecp256k1Sha256),
                 _ => Err(Error::UnknownCodeError),
             },
-            "1" => match &s[1..4] {
+            "1" => match s.get(1..4).ok_or(Error::EmptyCodeError)? {
                 "AAE" => Ok(Self::Ed448),
                 _ => Err(Error::UnknownCodeError),
             },
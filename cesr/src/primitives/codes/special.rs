@@ -0,0 +1,73 @@
+use std::{fmt, str::FromStr};
+
+use super::table::{self, CodeEntry};
+use crate::{derivation_code::DerivationCode, error::Error};
+
+/// Codes for special values, which have no raw part.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum SpecialCode {
+    /// Null or None value
+    Null,
+    /// Falsey boolean value
+    No,
+    /// Truthy boolean value
+    Yes,
+    /// Escape code for escaping special map fields
+    Escape,
+    /// Empty value for nonce, UUID or related fields
+    Empty,
+}
+
+impl SpecialCode {
+    /// Entry of master code table with code and its sizes.
+    fn entry(&self) -> &'static CodeEntry {
+        match self {
+            SpecialCode::Null => const { table::entry("1AAK") },
+            SpecialCode::No => const { table::entry("1AAL") },
+            SpecialCode::Yes => const { table::entry("1AAM") },
+            SpecialCode::Escape => const { table::entry("1AAO") },
+            SpecialCode::Empty => const { table::entry("1AAP") },
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        self.entry().code
+    }
+}
+
+impl DerivationCode for SpecialCode {
+    fn hard_size(&self) -> usize {
+        self.entry().hard()
+    }
+
+    fn soft_size(&self) -> usize {
+        self.entry().soft
+    }
+
+    fn value_size(&self) -> usize {
+        self.entry().value_size()
+    }
+
+    fn to_str(&self) -> String {
+        self.as_str().into()
+    }
+
+    fn write_code<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SpecialCode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.get(..4).ok_or(Error::EmptyCodeError)? {
+            "1AAK" => Ok(SpecialCode::Null),
+            "1AAL" => Ok(SpecialCode::No),
+            "1AAM" => Ok(SpecialCode::Yes),
+            "1AAO" => Ok(SpecialCode::Escape),
+            "1AAP" => Ok(SpecialCode::Empty),
+            _ => Err(Error::UnknownCodeError),
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use serde::Serialize;
+
+use super::{
+    basic::Basic as B, label::LabelCode, number::NumberCode, rand_128::Rand128Code, seed::SeedCode,
+    self_addressing::SelfAddressing as SA, self_signing::SelfSigning as SS, special::SpecialCode,
+    timestamp::TimestampCode, PrimitiveCode as P,
+};
+
+/// Kind of primitive that code of master code table stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CodeKind {
+    Seed,
+    Basic,
+    SelfAddressing,
+    SelfSigning,
+    Random,
+    Timestamp,
+    Tag,
+    Number,
+    Label,
+    Special,
+    /// Code of known size, which primitive this crate doesn't decode
+    Unsupported,
+}
+
+/// Entry of master code table: hard part of code and sizes in chars.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeEntry {
+    pub code: &'static str,
+    /// Name of code in CESR specification.
+    pub name: &'static str,
+    pub kind: CodeKind,
+    pub soft: usize,
+    pub full: usize,
+    /// Code that this crate decodes primitive with. `None` for tags, which
+    /// code includes soft part, and for unsupported codes.
+    pub primitive: Option<P>,
+}
+
+impl CodeEntry {
+    const fn new(
+        code: &'static str,
+        name: &'static str,
+        kind: CodeKind,
+        soft: usize,
+        full: usize,
+    ) -> Self {
+        Self {
+            code,
+            name,
+            kind,
+            soft,
+            full,
+            primitive: None,
+        }
+    }
+
+    /// Entry of code without soft part, of primitive this crate decodes.
+    const fn fixed(code: &'static str, name: &'static str, primitive: P, full: usize) -> Self {
+        let kind = match &primitive {
+            P::Seed(_) => Seed,
+            P::Basic(_) => Basic,
+            P::SelfAddressing(_) => SelfAddressing,
+            P::SelfSigning(_) => SelfSigning,
+            P::Random(_) | P::SerialNumber(_) => Random,
+            P::Timestamp(_) => Timestamp,
+            P::Tag(_) => Tag,
+            P::Number(_) => Number,
+            P::Label(_) => Label,
+            P::Special(_) => Special,
+            P::IndexedSignature(_) => panic!("indexed code is not in master code table"),
+        };
+        Self {
+            code,
+            name,
+            kind,
+            soft: 0,
+            full,
+            primitive: Some(primitive),
+        }
+    }
+
+    pub fn hard(&self) -> usize {
+        self.code.len()
+    }
+
+    pub fn code_size(&self) -> usize {
+        self.hard() + self.soft
+    }
+
+    /// Size in chars of value that follows code.
+    pub fn value_size(&self) -> usize {
+        self.full - self.code_size()
+    }
+
+    /// Size of raw value in bytes, without lead bytes. Codes take place of
+    /// lead bytes, so there are as many lead bytes as code chars over whole
+    /// quadlets.
+    pub fn raw_size(&self) -> usize {
+        let lead = self.code_size() % 4;
+        (self.full - self.code_size() + lead) / 4 * 3 - lead
+    }
+}
+
+use CodeKind::*;
+
+/// Fixed size codes of CESR master code table, with their soft and full
+/// sizes, and codes of this crate they stand for. Hard size follows from
+/// selector: one char for letters, two chars for `0` and four chars for `1`.
+pub static MASTER_CODES: [CodeEntry; 58] = [
+    CodeEntry::fixed(
+        "A",
+        "Ed25519_Seed",
+        P::Seed(SeedCode::RandomSeed256Ed25519),
+        44,
+    ),
+    CodeEntry::fixed("B", "Ed25519N", P::Basic(B::Ed25519Nontrans), 44),
+    CodeEntry::fixed("C", "X25519", P::Basic(B::X25519), 44),
+    CodeEntry::fixed("D", "Ed25519", P::Basic(B::Ed25519), 44),
+    CodeEntry::fixed("E", "Blake3_256", P::SelfAddressing(SA::Blake3_256), 44),
+    CodeEntry::fixed("F", "Blake2b_256", P::SelfAddressing(SA::Blake2B256), 44),
+    CodeEntry::fixed("G", "Blake2s_256", P::SelfAddressing(SA::Blake2S256), 44),
+    CodeEntry::fixed("H", "SHA3_256", P::SelfAddressing(SA::SHA3_256), 44),
+    CodeEntry::fixed("I", "SHA2_256", P::SelfAddressing(SA::SHA2_256), 44),
+    CodeEntry::fixed(
+        "J",
+        "ECDSA_256k1_Seed",
+        P::Seed(SeedCode::RandomSeed256ECDSAsecp256k1),
+        44,
+    ),
+    CodeEntry::fixed("K", "Ed448_Seed", P::Seed(SeedCode::RandomSeed448), 76),
+    CodeEntry::fixed("L", "X448", P::Basic(B::X448), 76),
+    CodeEntry::fixed("M", "Short", P::Number(NumberCode::Short), 4),
+    CodeEntry::fixed("N", "Big", P::Number(NumberCode::Big), 12),
+    CodeEntry::new("O", "X25519_Private", Unsupported, 0, 44),
+    CodeEntry::new("P", "X25519_Cipher_Seed", Unsupported, 0, 124),
+    CodeEntry::new("Q", "ECDSA_256r1_Seed", Unsupported, 0, 44),
+    CodeEntry::fixed("R", "Tall", P::Number(NumberCode::Tall), 8),
+    CodeEntry::fixed("S", "Large", P::Number(NumberCode::Large), 16),
+    CodeEntry::fixed("T", "Great", P::Number(NumberCode::Great), 20),
+    CodeEntry::fixed("U", "Vast", P::Number(NumberCode::Vast), 24),
+    CodeEntry::fixed("V", "Label1", P::Label(LabelCode::Label1), 4),
+    CodeEntry::fixed("W", "Label2", P::Label(LabelCode::Label2), 4),
+    CodeEntry::new("X", "Tag3", Tag, 3, 4),
+    CodeEntry::new("Y", "Tag7", Tag, 7, 8),
+    CodeEntry::new("Z", "Tag11", Tag, 11, 12),
+    CodeEntry::new("a", "Blind", Unsupported, 0, 44),
+    CodeEntry::fixed("0A", "Salt_128", P::Random(Rand128Code), 24),
+    CodeEntry::fixed("0B", "Ed25519_Sig", P::SelfSigning(SS::Ed25519Sha512), 88),
+    CodeEntry::fixed(
+        "0C",
+        "ECDSA_256k1_Sig",
+        P::SelfSigning(SS::ECDSAsecp256k1Sha256),
+        88,
+    ),
+    CodeEntry::fixed("0D", "Blake3_512", P::SelfAddressing(SA::Blake3_512), 88),
+    CodeEntry::fixed("0E", "SHA3_512", P::SelfAddressing(SA::SHA3_512), 88),
+    CodeEntry::fixed("0F", "Blake2b_512", P::SelfAddressing(SA::Blake2B512), 88),
+    CodeEntry::fixed("0G", "SHA2_512", P::SelfAddressing(SA::SHA2_512), 88),
+    CodeEntry::fixed("0H", "Long", P::Number(NumberCode::Long), 8),
+    CodeEntry::new("0I", "ECDSA_256r1_Sig", Unsupported, 0, 88),
+    CodeEntry::new("0J", "Tag1", Tag, 2, 4),
+    CodeEntry::new("0K", "Tag2", Tag, 2, 4),
+    CodeEntry::new("0L", "Tag5", Tag, 6, 8),
+    CodeEntry::new("0M", "Tag6", Tag, 6, 8),
+    CodeEntry::new("0N", "Tag9", Tag, 10, 12),
+    CodeEntry::new("0O", "Tag10", Tag, 10, 12),
+    CodeEntry::fixed(
+        "1AAA",
+        "ECDSA_256k1N",
+        P::Basic(B::ECDSAsecp256k1Nontrans),
+        48,
+    ),
+    CodeEntry::fixed("1AAB", "ECDSA_256k1", P::Basic(B::ECDSAsecp256k1), 48),
+    CodeEntry::fixed("1AAC", "Ed448N", P::Basic(B::Ed448Nontrans), 80),
+    CodeEntry::fixed("1AAD", "Ed448", P::Basic(B::Ed448), 80),
+    CodeEntry::fixed("1AAE", "Ed448_Sig", P::SelfSigning(SS::Ed448), 156),
+    CodeEntry::new("1AAF", "Tag4", Tag, 4, 8),
+    CodeEntry::fixed("1AAG", "DateTime", P::Timestamp(TimestampCode), 36),
+    CodeEntry::new("1AAH", "X25519_Cipher_Salt", Unsupported, 0, 100),
+    CodeEntry::new("1AAI", "ECDSA_256r1N", Unsupported, 0, 48),
+    CodeEntry::new("1AAJ", "ECDSA_256r1", Unsupported, 0, 48),
+    CodeEntry::fixed("1AAK", "Null", P::Special(SpecialCode::Null), 4),
+    CodeEntry::fixed("1AAL", "No", P::Special(SpecialCode::No), 4),
+    CodeEntry::fixed("1AAM", "Yes", P::Special(SpecialCode::Yes), 4),
+    CodeEntry::new("1AAN", "Tag8", Tag, 8, 12),
+    CodeEntry::fixed("1AAO", "Escape", P::Special(SpecialCode::Escape), 4),
+    CodeEntry::fixed("1AAP", "Empty", P::Special(SpecialCode::Empty), 4),
+];
+
+/// Indexes of `MASTER_CODES`, by Base64 index of the last hard char. One
+/// array per selector: letters, `0`, and `1AA`.
+struct Lookup {
+    one: [Option<usize>; 64],
+    zero: [Option<usize>; 64],
+    one_aa: [Option<usize>; 64],
+}
+
+const fn b64_index(c: u8) -> Option<usize> {
+    match c {
+        b'A'..=b'Z' => Some((c - b'A') as usize),
+        b'a'..=b'z' => Some((c - b'a') as usize + 26),
+        b'0'..=b'9' => Some((c - b'0') as usize + 52),
+        b'-' => Some(62),
+        b'_' => Some(63),
+        _ => None,
+    }
+}
+
+const fn build_lookup() -> Lookup {
+    let mut lookup = Lookup {
+        one: [None; 64],
+        zero: [None; 64],
+        one_aa: [None; 64],
+    };
+    let mut i = 0;
+    while i < MASTER_CODES.len() {
+        let code = MASTER_CODES[i].code.as_bytes();
+        let last = match b64_index(code[code.len() - 1]) {
+            Some(index) => index,
+            None => panic!("code is not Base64"),
+        };
+        match code.len() {
+            1 => lookup.one[last] = Some(i),
+            2 if code[0] == b'0' => lookup.zero[last] = Some(i),
+            4 if code[0] == b'1' && code[1] == b'A' && code[2] == b'A' => {
+                lookup.one_aa[last] = Some(i)
+            }
+            _ => panic!("unexpected selector"),
+        }
+        i += 1;
+    }
+    lookup
+}
+
+static LOOKUP: Lookup = build_lookup();
+
+/// Entry of hard code, that code enums of this crate take their sizes from.
+/// It's meant for `const` blocks, so that code missing from `MASTER_CODES`
+/// fails to compile, and sizes are found once.
+pub(crate) const fn entry(hard: &str) -> &'static CodeEntry {
+    let mut i = 0;
+    while i < MASTER_CODES.len() {
+        if same_code(MASTER_CODES[i].code.as_bytes(), hard.as_bytes()) {
+            return &MASTER_CODES[i];
+        }
+        i += 1;
+    }
+    panic!("code is not in master code table")
+}
+
+const fn same_code(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Finds master code table entry of code at the start of `s`, with a single
+/// array access.
+pub fn lookup(s: &str) -> Option<&'static CodeEntry> {
+    let bytes = s.as_bytes();
+    let index = match *bytes.first()? {
+        b'0' => LOOKUP.zero[b64_index(*bytes.get(1)?)?],
+        b'1' if bytes.get(1..3)? == b"AA" => LOOKUP.one_aa[b64_index(*bytes.get(3)?)?],
+        b'1'..=b'9' | b'-' | b'_' => None,
+        first => LOOKUP.one[b64_index(first)?],
+    }?;
+    Some(&MASTER_CODES[index])
+}
@@ -0,0 +1,206 @@
+use std::{fmt, str::FromStr};
+
+use super::table::{self, CodeEntry};
+use crate::{conversion::is_base64, derivation_code::DerivationCode, error::Error};
+
+/// Pad character prepended to soft part of tags with odd number of chars
+const TAG_PAD: char = '_';
+
+#[derive(PartialEq, Eq, Debug, Clone, Hash)]
+pub enum TagCode {
+    // 1 B64 encoded char with pre pad for special values
+    Tag1([char; 1]),
+    // 2 B64 encoded chars for special values
+    Tag2([char; 2]),
+    // 3 B64 encoded chars for special values
+    Tag3([char; 3]),
+    // 4 B64 encoded chars for special values
+    Tag4([char; 4]),
+    // 5 B64 encoded chars with pre pad for special values
+    Tag5([char; 5]),
+    // 6 B64 encoded chars for special values
+    Tag6([char; 6]),
+    // 7 B64 encoded chars for special values
+    Tag7([char; 7]),
+    // 8 B64 encoded chars for special values
+    Tag8([char; 8]),
+    // 9 B64 encoded chars with pre pad for special values
+    Tag9([char; 9]),
+    // 10 B64 encoded chars for special values
+    Tag10([char; 10]),
+    // 11 B64 encoded chars for special values
+    Tag11([char; 11]),
+}
+
+impl TagCode {
+    /// Creates tag of provided Base64 text, picking the code that matches its
+    /// length.
+    pub fn new(tag: &str) -> Result<Self, Error> {
+        if !is_base64(tag) {
+            return Err(Error::NonBase64TextError(tag.to_string()));
+        }
+        let invalid_length = || Error::IncorrectLengthError(tag.to_string());
+        Ok(match tag.len() {
+            1 => TagCode::Tag1(str_to_char_array(tag).ok_or_else(invalid_length)?),
+            2 => TagCode::Tag2(str_to_char_array(tag).ok_or_else(invalid_length)?),
+            3 => TagCode::Tag3(str_to_char_array(tag).ok_or_else(invalid_length)?),
+            4 => TagCode::Tag4(str_to_char_array(tag).ok_or_else(invalid_length)?),
+            5 => TagCode::Tag5(str_to_char_array(tag).ok_or_else(invalid_length)?),
+            6 => TagCode::Tag6(str_to_char_array(tag).ok_or_else(invalid_length)?),
+            7 => TagCode::Tag7(str_to_char_array(tag).ok_or_else(invalid_length)?),
+            8 => TagCode::Tag8(str_to_char_array(tag).ok_or_else(invalid_length)?),
+            9 => TagCode::Tag9(str_to_char_array(tag).ok_or_else(invalid_length)?),
+            10 => TagCode::Tag10(str_to_char_array(tag).ok_or_else(invalid_length)?),
+            11 => TagCode::Tag11(str_to_char_array(tag).ok_or_else(invalid_length)?),
+            _ => return Err(invalid_length()),
+        })
+    }
+
+    /// Returns tag text without code and pad.
+    pub fn tag(&self) -> String {
+        self.chars().iter().collect()
+    }
+
+    fn chars(&self) -> &[char] {
+        match self {
+            TagCode::Tag1(chars) => chars,
+            TagCode::Tag2(chars) => chars,
+            TagCode::Tag3(chars) => chars,
+            TagCode::Tag4(chars) => chars,
+            TagCode::Tag5(chars) => chars,
+            TagCode::Tag6(chars) => chars,
+            TagCode::Tag7(chars) => chars,
+            TagCode::Tag8(chars) => chars,
+            TagCode::Tag9(chars) => chars,
+            TagCode::Tag10(chars) => chars,
+            TagCode::Tag11(chars) => chars,
+        }
+    }
+
+    /// Entry of master code table with hard code and sizes.
+    fn entry(&self) -> &'static CodeEntry {
+        match self {
+            TagCode::Tag1(_) => const { table::entry("0J") },
+            TagCode::Tag2(_) => const { table::entry("0K") },
+            TagCode::Tag3(_) => const { table::entry("X") },
+            TagCode::Tag4(_) => const { table::entry("1AAF") },
+            TagCode::Tag5(_) => const { table::entry("0L") },
+            TagCode::Tag6(_) => const { table::entry("0M") },
+            TagCode::Tag7(_) => const { table::entry("Y") },
+            TagCode::Tag8(_) => const { table::entry("1AAN") },
+            TagCode::Tag9(_) => const { table::entry("0N") },
+            TagCode::Tag10(_) => const { table::entry("0O") },
+            TagCode::Tag11(_) => const { table::entry("Z") },
+        }
+    }
+
+    fn hard_code(&self) -> &'static str {
+        self.entry().code
+    }
+
+    /// Number of pad chars prepended to tag in soft part of code
+    fn pad_size(&self) -> usize {
+        self.soft_size() - self.chars().len()
+    }
+}
+
+impl DerivationCode for TagCode {
+    fn hard_size(&self) -> usize {
+        self.hard_code().len()
+    }
+
+    fn soft_size(&self) -> usize {
+        self.entry().soft
+    }
+
+    fn value_size(&self) -> usize {
+        self.entry().value_size()
+    }
+
+    fn to_str(&self) -> String {
+        let mut code = String::with_capacity(self.code_size());
+        self.write_code(&mut code).expect("writing to String");
+        code
+    }
+
+    fn write_code<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str(self.hard_code())?;
+        for _ in 0..self.pad_size() {
+            writer.write_char(TAG_PAD)?;
+        }
+        self.chars().iter().try_for_each(|c| writer.write_char(*c))
+    }
+}
+
+fn str_to_char_array<const N: usize>(s: &str) -> Option<[char; N]> {
+    let chars: Vec<char> = s.chars().collect();
+    chars.try_into().ok()
+}
+
+impl FromStr for TagCode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (hard_size, soft_size, pad_size) = match s.get(..1).ok_or(Error::EmptyCodeError)? {
+            "X" => (1, 3, 0),
+            "Y" => (1, 7, 0),
+            "Z" => (1, 11, 0),
+            "0" => match s.get(1..2).ok_or(Error::EmptyCodeError)? {
+                "J" => (2, 2, 1),
+                "K" => (2, 2, 0),
+                "L" => (2, 6, 1),
+                "M" => (2, 6, 0),
+                "N" => (2, 10, 1),
+                "O" => (2, 10, 0),
+                _ => return Err(Error::UnknownCodeError),
+            },
+            "1" => match s.get(1..4).ok_or(Error::EmptyCodeError)? {
+                "AAF" => (4, 4, 0),
+                "AAN" => (4, 8, 0),
+                _ => return Err(Error::UnknownCodeError),
+            },
+            _ => return Err(Error::UnknownCodeError),
+        };
+        let soft = s
+            .get(hard_size..hard_size + soft_size)
+            .ok_or_else(|| Error::IncorrectLengthError(s.to_string()))?;
+        let (pad, tag) = soft.split_at(pad_size);
+        if pad.chars().any(|c| c != TAG_PAD) {
+            return Err(Error::IncorrectLengthError(s.to_string()));
+        }
+        TagCode::new(tag)
+    }
+}
+
+#[test]
+fn test_tags() -> Result<(), Error> {
+    let tags = [
+        ("A", "0J_A"),
+        ("AB", "0KAB"),
+        ("ABC", "XABC"),
+        ("ABCD", "1AAFABCD"),
+        ("ABCDE", "0L_ABCDE"),
+        ("ABCDEF", "0MABCDEF"),
+        ("ABCDEFG", "YABCDEFG"),
+        ("ABCDEFGH", "1AANABCDEFGH"),
+        ("ABCDEFGHI", "0N_ABCDEFGHI"),
+        ("ABCDEFGHIJ", "0OABCDEFGHIJ"),
+        ("ABCDEFGHIJK", "ZABCDEFGHIJK"),
+    ];
+    for (tag, expected) in tags {
+        let code = TagCode::new(tag)?;
+        assert_eq!(code.to_str(), expected);
+        assert_eq!(code.code_size() % 4, 0);
+        assert_eq!(code.tag(), tag);
+        assert_eq!(TagCode::from_str(expected)?, code);
+    }
+
+    assert!(TagCode::new("").is_err());
+    assert!(TagCode::new("ABCDEFGHIJKL").is_err());
+    assert!(TagCode::new("a b").is_err());
+    // Too short input doesn't panic
+    assert!(TagCode::from_str("XA").is_err());
+    assert!(TagCode::from_str("0").is_err());
+    assert!(TagCode::from_str("0JAA").is_err());
+    Ok(())
+}
@@ -1,7 +1,6 @@
-use std::str::FromStr;
-
-use chrono::SecondsFormat;
+use std::{fmt, str::FromStr};
 
+use super::table;
 use crate::{derivation_code::DerivationCode, error::Error, primitives::Timestamp};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -9,20 +8,24 @@ pub struct TimestampCode;
 
 impl DerivationCode for TimestampCode {
     fn hard_size(&self) -> usize {
-        4
+        const { table::entry("1AAG") }.hard()
     }
 
     fn soft_size(&self) -> usize {
-        0
+        const { table::entry("1AAG") }.soft
     }
 
     fn value_size(&self) -> usize {
-        32
+        const { table::entry("1AAG") }.value_size()
     }
 
     fn to_str(&self) -> String {
         "1AAG".into()
     }
+
+    fn write_code<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str("1AAG")
+    }
 }
 
 impl FromStr for TimestampCode {
@@ -39,14 +42,40 @@ impl FromStr for TimestampCode {
 }
 
 pub fn pack_datetime(dt: &Timestamp) -> String {
-    [
-        TimestampCode.to_str(),
-        dt.to_rfc3339_opts(SecondsFormat::Micros, false)
-            .replace(':', "c")
-            .replace('.', "d")
-            .replace('+', "p"),
-    ]
-    .concat()
+    let mut packed = String::with_capacity(TimestampCode.full_size());
+    write_datetime(dt, &mut packed).expect("writing to String");
+    packed
+}
+
+/// Writes timestamp as RFC 3339 text with microseconds, with `:`, `.` and
+/// `+` replaced by Base64 chars, as `pack_datetime` encodes it.
+pub(crate) fn write_datetime<W: fmt::Write + ?Sized>(
+    dt: &Timestamp,
+    writer: &mut W,
+) -> fmt::Result {
+    TimestampCode.write_code(writer)?;
+    fmt::Write::write_fmt(
+        &mut Base64Chars(writer),
+        format_args!("{}", dt.format("%Y-%m-%dT%H:%M:%S%.6f%:z")),
+    )
+}
+
+/// Writer replacing chars of RFC 3339 text that are not Base64.
+struct Base64Chars<'a, W: ?Sized>(&'a mut W);
+
+impl<W: fmt::Write + ?Sized> fmt::Write for Base64Chars<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        s.chars().try_for_each(|c| self.write_char(c))
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.0.write_char(match c {
+            ':' => 'c',
+            '.' => 'd',
+            '+' => 'p',
+            c => c,
+        })
+    }
 }
 
 #[test]
@@ -57,3 +86,13 @@ pub fn test_pack_datetime() {
     let expected_str = "1AAG2022-10-25T12c04c30d175309p00c00";
     assert_eq!(expected_str, pack_datetime(&dt));
 }
+
+#[test]
+pub fn test_pack_datetime_offset() {
+    let dt = "2020-08-22T17:50:09.988921-05:30"
+        .parse::<Timestamp>()
+        .unwrap();
+    let packed = pack_datetime(&dt);
+    assert_eq!(packed, "1AAG2020-08-22T17c50c09d988921-05c30");
+    assert_eq!(packed.len(), TimestampCode.full_size());
+}
@@ -0,0 +1,144 @@
+use std::str::FromStr;
+
+use nom::{
+    bytes::complete::take,
+    error::{make_error, ErrorKind},
+};
+
+use crate::{
+    conversion::{decode_lead, from_bytes_to_text, from_text_to_bytes},
+    derivation_code::DerivationCode,
+    error::{Error, IResult, StreamError, StreamErrorKind},
+};
+
+/// Fixed size primitive: code and raw value, in the style of KERIpy's
+/// `Matter`. Text (qb64) and binary (qb2) encodings, and parsing, follow from
+/// the code sizes.
+pub trait Matter: Sized {
+    type Code: DerivationCode + FromStr<Err = Error>;
+
+    /// Creates primitive, checking raw value size against code.
+    fn from_raw(code: Self::Code, raw: Vec<u8>) -> Result<Self, Error>;
+
+    fn code(&self) -> &Self::Code;
+
+    /// Raw value, without lead bytes.
+    fn raw(&self) -> &[u8];
+
+    /// Size in chars of qb64 encoding.
+    fn full_size(&self) -> usize {
+        self.code().full_size()
+    }
+
+    fn qb64(&self) -> String {
+        encode_qb64(&self.code().to_str(), self.raw())
+    }
+
+    fn qb64b(&self) -> Vec<u8> {
+        self.qb64().into_bytes()
+    }
+
+    /// Binary encoding of qb64 text. Fails if raw value size doesn't match
+    /// code, so qb64 text isn't code's full size.
+    fn qb2(&self) -> Result<Vec<u8>, Error> {
+        let qb64 = self.qb64();
+        if qb64.len() != self.full_size() {
+            return Err(Error::IncorrectLengthError(qb64));
+        }
+        from_text_to_bytes(&qb64)
+    }
+
+    /// Decodes primitive from its whole qb64 text.
+    fn from_qb64(s: &str) -> Result<Self, Error> {
+        let code = Self::Code::from_str(s)?;
+        if s.len() != code.full_size() {
+            return Err(Error::IncorrectLengthError(s.to_string()));
+        }
+        let raw = decode_raw(code.code_size(), &s[code.code_size()..], true)?;
+        Self::from_raw(code, raw)
+    }
+
+    /// Decodes primitive from its whole qb2 bytes.
+    fn from_qb2(bytes: &[u8]) -> Result<Self, Error> {
+        if !bytes.len().is_multiple_of(3) {
+            return Err(Error::IncorrectLengthError(format!(
+                "{} bytes of qb2",
+                bytes.len()
+            )));
+        }
+        Self::from_qb64(&from_bytes_to_text(bytes))
+    }
+}
+
+/// Size in bytes of raw value of fixed size code, without lead bytes.
+pub fn raw_size(code: &impl DerivationCode) -> usize {
+    code.full_size() * 3 / 4 - (code.code_size() * 3).div_ceil(4)
+}
+
+/// Encodes raw value with code. Lead chars of Base64 value are replaced by
+/// code.
+pub(crate) fn encode_qb64(code: &str, raw: &[u8]) -> String {
+    let lead_size = code.len() % 4;
+    [code, &from_bytes_to_text(raw)[lead_size..]].concat()
+}
+
+/// Decodes raw value from value part of qb64, that follows code of given
+/// size. Lead bytes are dropped. In `strict` mode they have to be zero, so
+/// that each raw value has exactly one qb64 text.
+pub(crate) fn decode_raw(code_size: usize, value: &str, strict: bool) -> Result<Vec<u8>, Error> {
+    decode_lead(value, code_size % 4, code_size % 4, strict)
+}
+
+/// Parses primitive of any `Matter` type. Only canonical text is accepted.
+pub fn parse_matter<M: Matter>(stream: &str) -> IResult<&str, M> {
+    parse_matter_with(stream, true)
+}
+
+/// Parses primitive of any `Matter` type, accepting text with non-zero lead
+/// bits unless `strict`.
+pub(crate) fn parse_matter_with<M: Matter>(stream: &str, strict: bool) -> IResult<&str, M> {
+    let Ok(code) = M::Code::from_str(stream) else {
+        return Err(nom::Err::Error(make_error(stream, ErrorKind::IsNot)));
+    };
+    let (rest, _parsed_code) = take(code.code_size())(stream)?;
+    let (rest, data) = take(code.value_size())(rest)?;
+    let raw = match decode_raw(code.code_size(), data, strict) {
+        Ok(raw) => raw,
+        Err(Error::NonZeroPadBitsError(_)) => {
+            return Err(StreamError::failure(
+                stream,
+                StreamErrorKind::NonZeroPadBits,
+            ))
+        }
+        Err(_) => return Err(nom::Err::Error(make_error(rest, ErrorKind::IsNot))),
+    };
+    let Ok(matter) = M::from_raw(code, raw) else {
+        return Err(nom::Err::Error(make_error(stream, ErrorKind::Verify)));
+    };
+    Ok((rest, matter))
+}
+
+/// Code and raw value pair, as kept in `Value::Primitive` and `Identifier`.
+impl<C: DerivationCode + FromStr<Err = Error>> Matter for (C, Vec<u8>) {
+    type Code = C;
+
+    fn from_raw(code: C, raw: Vec<u8>) -> Result<Self, Error> {
+        if raw.len() != raw_size(&code) {
+            return Err(Error::IncorrectLengthError(format!(
+                "{} requires {} bytes, got {}",
+                code.to_str(),
+                raw_size(&code),
+                raw.len()
+            )));
+        }
+        Ok((code, raw))
+    }
+
+    fn code(&self) -> &C {
+        &self.0
+    }
+
+    fn raw(&self) -> &[u8] {
+        &self.1
+    }
+}
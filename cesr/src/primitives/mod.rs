@@ -1,8 +1,25 @@
 pub mod codes;
+pub mod matter;
 pub mod parsers;
+pub mod text;
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
 use chrono::{DateTime, FixedOffset};
 
-use crate::{conversion::from_bytes_to_text, primitives::codes::rand_128::Rand128Code};
+use crate::{
+    conversion::{from_bytes_to_text, from_text_to_bytes},
+    derivation_code::DerivationCode,
+    error::Error,
+    primitives::codes::{
+        number::{unpack_number, NumberCode},
+        rand_128::Rand128Code,
+        timestamp::{pack_datetime, TimestampCode},
+    },
+    value::Value,
+};
+
+use self::matter::encode_qb64;
+pub use self::matter::Matter;
 
 use self::codes::{
     attached_signature_code::AttachedSignatureCode, basic::Basic, self_addressing::SelfAddressing,
@@ -24,93 +41,545 @@ impl From<IdentifierCode> for PrimitiveCode {
     }
 }
 
+impl DerivationCode for IdentifierCode {
+    fn hard_size(&self) -> usize {
+        match self {
+            IdentifierCode::Basic(code) => code.hard_size(),
+            IdentifierCode::SelfAddressing(code) => code.hard_size(),
+        }
+    }
+
+    fn soft_size(&self) -> usize {
+        match self {
+            IdentifierCode::Basic(code) => code.soft_size(),
+            IdentifierCode::SelfAddressing(code) => code.soft_size(),
+        }
+    }
+
+    fn value_size(&self) -> usize {
+        match self {
+            IdentifierCode::Basic(code) => code.value_size(),
+            IdentifierCode::SelfAddressing(code) => code.value_size(),
+        }
+    }
+
+    fn to_str(&self) -> String {
+        match self {
+            IdentifierCode::Basic(code) => code.to_str(),
+            IdentifierCode::SelfAddressing(code) => code.to_str(),
+        }
+    }
+}
+
+impl FromStr for IdentifierCode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SelfAddressing::from_str(s)
+            .map(IdentifierCode::SelfAddressing)
+            .or_else(|_| Basic::from_str(s).map(IdentifierCode::Basic))
+    }
+}
+
 pub type Identifier = (IdentifierCode, Vec<u8>);
-pub type PublicKey = (Basic, Vec<u8>);
-pub type Digest = (SelfAddressing, Vec<u8>);
-pub type Signature = (SelfSigning, Vec<u8>);
-pub type IndexedSignature = (AttachedSignatureCode, Vec<u8>);
+pub type PublicKey = Primitive<Basic>;
+pub type Digest = Primitive<SelfAddressing>;
+pub type Signature = Primitive<SelfSigning>;
+pub type IndexedSignature = Primitive<AttachedSignatureCode>;
 pub type Timestamp = DateTime<FixedOffset>;
-pub type AnchoringEventSeal = (Identifier, u64, Digest);
-pub type SaltyNounce = (Rand128Code, Vec<u8>);
+pub type AnchoringEventSeal = crate::seal::EventSeal;
+pub type SaltyNounce = Primitive<Rand128Code>;
 
-pub trait CesrPrimitive {
-    fn derivative(&self) -> Vec<u8>;
-    fn derivation_code(&self) -> PrimitiveCode;
-    fn to_str(&self) -> String {
-        match self.derivative().len() {
-            // empty data cannot be prefixed!
-            0 => "".to_string(),
-            _ => {
-                let dc = self.derivation_code().to_str();
-                let lead_bytes = if !dc.len().is_multiple_of(4) { dc.len() % 4 } else { 0 };
-                // replace lead bytes with code
-                let derivative_text =
-                    from_bytes_to_text(&self.derivative())[lead_bytes..].to_string();
-                [dc, derivative_text].join("")
+/// Code of fixed size primitive, that is one of `PrimitiveCode` variants.
+pub trait FixedSizeCode:
+    DerivationCode + FromStr<Err = Error> + Clone + Into<PrimitiveCode>
+{
+    /// Kind of primitive, used in error messages.
+    const KIND: &'static str;
+
+    fn from_primitive_code(code: &PrimitiveCode) -> Option<Self>;
+
+    /// Size in bytes of raw value, without lead bytes.
+    fn raw_size(&self) -> usize {
+        matter::raw_size(self)
+    }
+
+    /// Checks if code can be encoded, e.g. if its index fits into soft part
+    /// of code.
+    fn check(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl FixedSizeCode for Basic {
+    const KIND: &'static str = "basic";
+
+    fn from_primitive_code(code: &PrimitiveCode) -> Option<Self> {
+        match code {
+            PrimitiveCode::Basic(code) => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+impl FixedSizeCode for SelfAddressing {
+    const KIND: &'static str = "self addressing";
+
+    fn from_primitive_code(code: &PrimitiveCode) -> Option<Self> {
+        match code {
+            PrimitiveCode::SelfAddressing(code) => Some(code.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FixedSizeCode for SelfSigning {
+    const KIND: &'static str = "self signing";
+
+    fn from_primitive_code(code: &PrimitiveCode) -> Option<Self> {
+        match code {
+            PrimitiveCode::SelfSigning(code) => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+impl FixedSizeCode for AttachedSignatureCode {
+    const KIND: &'static str = "indexed signature";
+
+    fn from_primitive_code(code: &PrimitiveCode) -> Option<Self> {
+        match code {
+            PrimitiveCode::IndexedSignature(code) => Some(*code),
+            _ => None,
+        }
+    }
+
+    fn check(&self) -> Result<(), Error> {
+        self.check_index()
+    }
+}
+
+impl FixedSizeCode for Rand128Code {
+    const KIND: &'static str = "random";
+
+    fn from_primitive_code(code: &PrimitiveCode) -> Option<Self> {
+        match code {
+            PrimitiveCode::Random(code) | PrimitiveCode::SerialNumber(code) => Some(code.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Primitive of fixed size code. Raw value is checked to be of the size the
+/// code requires, so primitive always encodes into valid qb64.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Primitive<C> {
+    code: C,
+    raw: Vec<u8>,
+}
+
+impl<C: FixedSizeCode> Primitive<C> {
+    /// Creates primitive, checking that raw value is of the size the code
+    /// requires and that code can be encoded.
+    pub fn new(code: C, raw: Vec<u8>) -> Result<Self, Error> {
+        code.check()?;
+        if raw.len() != code.raw_size() {
+            return Err(Error::IncorrectLengthError(format!(
+                "{} requires {} bytes, got {}",
+                code.to_str(),
+                code.raw_size(),
+                raw.len()
+            )));
+        }
+        Ok(Self { code, raw })
+    }
+
+    /// Creates primitive of value which size was already checked.
+    #[cfg(feature = "digest")]
+    pub(crate) fn new_unchecked(code: C, raw: Vec<u8>) -> Self {
+        Self { code, raw }
+    }
+
+    pub fn into_parts(self) -> (C, Vec<u8>) {
+        (self.code, self.raw)
+    }
+}
+
+impl<C: FixedSizeCode> Matter for Primitive<C> {
+    type Code = C;
+
+    fn from_raw(code: C, raw: Vec<u8>) -> Result<Self, Error> {
+        Self::new(code, raw)
+    }
+
+    fn code(&self) -> &C {
+        &self.code
+    }
+
+    fn raw(&self) -> &[u8] {
+        &self.raw
+    }
+}
+
+impl<C: FixedSizeCode> TryFrom<(C, Vec<u8>)> for Primitive<C> {
+    type Error = Error;
+
+    fn try_from((code, raw): (C, Vec<u8>)) -> Result<Self, Self::Error> {
+        Self::new(code, raw)
+    }
+}
+
+impl<C> From<Primitive<C>> for (C, Vec<u8>) {
+    fn from(primitive: Primitive<C>) -> Self {
+        (primitive.code, primitive.raw)
+    }
+}
+
+impl<C: FixedSizeCode> Display for Primitive<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.qb64())
+    }
+}
+
+impl<C: FixedSizeCode> FromStr for Primitive<C> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_qb64(s)
+    }
+}
+
+/// Primitives are ordered by their qb64 text.
+impl<C: FixedSizeCode + Eq> Ord for Primitive<C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.qb64().cmp(&other.qb64())
+    }
+}
+
+impl<C: FixedSizeCode + Eq> PartialOrd for Primitive<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<C: FixedSizeCode> TryFrom<Value> for Primitive<C> {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Primitive(code, raw) => Self::new(primitive_code(&code)?, raw),
+            other => Err(other.unexpected(C::KIND)),
+        }
+    }
+}
+
+impl<C: FixedSizeCode> TryFrom<&Value> for Primitive<C> {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Primitive(code, raw) => Self::new(primitive_code(code)?, raw.clone()),
+            other => Err(other.unexpected(C::KIND)),
+        }
+    }
+}
+
+/// Returns code of expected kind, or error naming the code found.
+fn primitive_code<C: FixedSizeCode>(code: &PrimitiveCode) -> Result<C, Error> {
+    C::from_primitive_code(code).ok_or_else(|| Error::UnexpectedValueError {
+        expected: C::KIND.into(),
+        found: code.to_str(),
+    })
+}
+
+impl<C: FixedSizeCode> From<Primitive<C>> for Value {
+    fn from(primitive: Primitive<C>) -> Self {
+        Value::Primitive(primitive.code.into(), primitive.raw)
+    }
+}
+
+/// Identifier is either basic or self addressing primitive.
+impl TryFrom<&Value> for Identifier {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Primitive(PrimitiveCode::Basic(code), raw) => {
+                Identifier::from_raw(IdentifierCode::Basic(*code), raw.clone())
+            }
+            Value::Primitive(PrimitiveCode::SelfAddressing(code), raw) => {
+                Identifier::from_raw(IdentifierCode::SelfAddressing(code.clone()), raw.clone())
             }
+            Value::Primitive(code, _) => Err(Error::UnexpectedValueError {
+                expected: "identifier".into(),
+                found: code.to_str(),
+            }),
+            other => Err(other.unexpected("identifier")),
         }
     }
 }
 
-impl CesrPrimitive for Digest {
-    fn derivative(&self) -> Vec<u8> {
-        self.1.clone()
+impl TryFrom<Value> for Identifier {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Identifier::try_from(&value)
     }
+}
 
-    fn derivation_code(&self) -> PrimitiveCode {
-        PrimitiveCode::SelfAddressing(self.0.clone())
+impl From<Identifier> for Value {
+    fn from((code, raw): Identifier) -> Self {
+        Value::Primitive(code.into(), raw)
     }
 }
 
-impl CesrPrimitive for Signature {
-    fn derivative(&self) -> Vec<u8> {
-        self.1.clone()
+/// Timestamp is decoded from its Base64 text, with `c`, `d` and `p` standing
+/// for `:`, `.` and `+`.
+impl TryFrom<&Value> for Timestamp {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Primitive(PrimitiveCode::Timestamp(code), raw) => {
+                let text = [code.to_str(), from_bytes_to_text(raw)].concat();
+                match parsers::timestamp_parser(&text) {
+                    Ok((_, timestamp)) => Ok(timestamp),
+                    Err(_) => Err(Error::UnexpectedValueError {
+                        expected: "timestamp".into(),
+                        found: text,
+                    }),
+                }
+            }
+            Value::Primitive(code, _) => Err(Error::UnexpectedValueError {
+                expected: "timestamp".into(),
+                found: code.to_str(),
+            }),
+            other => Err(other.unexpected("timestamp")),
+        }
     }
+}
 
-    fn derivation_code(&self) -> PrimitiveCode {
-        PrimitiveCode::SelfSigning(self.0)
+impl TryFrom<Value> for Timestamp {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Timestamp::try_from(&value)
     }
 }
 
-impl CesrPrimitive for IndexedSignature {
-    fn derivative(&self) -> Vec<u8> {
-        self.1.clone()
+impl From<Timestamp> for Value {
+    fn from(timestamp: Timestamp) -> Self {
+        let text = pack_datetime(&timestamp);
+        let raw = from_text_to_bytes(&text[TimestampCode.code_size()..])
+            .expect("timestamp text is Base64");
+        Value::Primitive(PrimitiveCode::Timestamp(TimestampCode), raw)
     }
+}
 
-    fn derivation_code(&self) -> PrimitiveCode {
-        PrimitiveCode::IndexedSignature(self.0)
+/// Number is read from number codes, and from serial number of `0A` code.
+impl TryFrom<&Value> for u128 {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Primitive(PrimitiveCode::Number(_) | PrimitiveCode::SerialNumber(_), raw) => {
+                unpack_number(raw)
+            }
+            Value::Primitive(code, _) => Err(Error::UnexpectedValueError {
+                expected: "number".into(),
+                found: code.to_str(),
+            }),
+            other => Err(other.unexpected("number")),
+        }
     }
 }
 
-impl CesrPrimitive for PublicKey {
-    fn derivative(&self) -> Vec<u8> {
-        self.1.clone()
+impl TryFrom<Value> for u128 {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        u128::try_from(&value)
     }
+}
 
-    fn derivation_code(&self) -> PrimitiveCode {
-        PrimitiveCode::Basic(self.0)
+impl TryFrom<&Value> for u64 {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        let number = u128::try_from(value)?;
+        u64::try_from(number).map_err(|_| Error::NumberOverflowError(number.to_string()))
     }
 }
 
-impl CesrPrimitive for Identifier {
-    fn derivative(&self) -> Vec<u8> {
-        self.1.clone()
+impl TryFrom<Value> for u64 {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        u64::try_from(&value)
     }
+}
 
-    fn derivation_code(&self) -> PrimitiveCode {
-        match &self.0 {
-            IdentifierCode::Basic(b) => PrimitiveCode::Basic(*b),
-            IdentifierCode::SelfAddressing(sa) => PrimitiveCode::SelfAddressing(sa.clone()),
+/// Number is encoded with the smallest fitting number code.
+impl From<u128> for Value {
+    fn from(number: u128) -> Self {
+        let code = NumberCode::for_value(number);
+        let raw = code.to_raw(number).expect("number fits into its code");
+        Value::Primitive(PrimitiveCode::Number(code), raw)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(number: u64) -> Self {
+        Value::from(number as u128)
+    }
+}
+
+pub trait CesrPrimitive {
+    fn derivative(&self) -> Vec<u8>;
+    fn derivation_code(&self) -> PrimitiveCode;
+    fn to_str(&self) -> String {
+        match self.derivative().len() {
+            // empty data cannot be prefixed!
+            0 => "".to_string(),
+            _ => encode_qb64(&self.derivation_code().to_str(), &self.derivative()),
         }
     }
 }
 
-impl CesrPrimitive for SaltyNounce {
+impl<M> CesrPrimitive for M
+where
+    M: Matter,
+    M::Code: Clone + Into<PrimitiveCode>,
+{
     fn derivative(&self) -> Vec<u8> {
-        self.1.clone()
+        self.raw().to_vec()
     }
 
     fn derivation_code(&self) -> PrimitiveCode {
-        PrimitiveCode::Random(self.0.clone())
+        self.code().clone().into()
     }
 }
+
+#[test]
+fn test_primitive_size_check() -> Result<(), Error> {
+    assert!(Digest::new(SelfAddressing::Blake3_256, vec![0; 32]).is_ok());
+    assert!(Digest::new(SelfAddressing::Blake3_256, vec![0; 64]).is_err());
+    assert!(PublicKey::new(Basic::Ed25519Nontrans, vec![0; 31]).is_err());
+    assert!(SaltyNounce::try_from((Rand128Code, vec![0; 16])).is_ok());
+    assert_eq!(
+        Basic::ECDSAsecp256k1.raw_size(),
+        33,
+        "four chars code takes whole quadlet"
+    );
+
+    let qb64 = "EJtQndkvwnMpVGE5oVVbLWSCm-jLviGw1AOOkzBvNwsS";
+    let digest: Digest = qb64.parse()?;
+    assert_eq!(digest.code(), &SelfAddressing::Blake3_256);
+    assert_eq!(digest.to_string(), qb64);
+    assert!(qb64[..40].parse::<Digest>().is_err());
+    assert!([qb64, "A"].concat().parse::<Digest>().is_err());
+    assert!(qb64.parse::<PublicKey>().is_err());
+
+    let other: Digest = "EKC8085pwSwzLwUGzh-HrEoFDwZnCJq27bVp5atdMT9o".parse()?;
+    assert!(digest < other);
+
+    let value = Value::from(digest.clone());
+    assert_eq!(Digest::try_from(value.clone())?, digest);
+    assert!(Signature::try_from(value).is_err());
+    assert!(Digest::try_from(Value::Null).is_err());
+
+    let (code, raw): (SelfAddressing, Vec<u8>) = digest.into();
+    assert_eq!((code, raw.len()), (SelfAddressing::Blake3_256, 32));
+    Ok(())
+}
+
+#[test]
+fn test_matter() -> Result<(), Error> {
+    let qb64 = "DDg3H7Sr-eES0XWXiO8nvMxW6mD_1LxLeE1nuiZxhGp4";
+    let key = PublicKey::from_qb64(qb64)?;
+    assert_eq!(key.code(), &Basic::Ed25519);
+    assert_eq!((key.raw().len(), key.full_size()), (32, 44));
+    assert_eq!(key.qb64b(), qb64.as_bytes());
+    let qb2 = key.qb2()?;
+    assert_eq!(qb2.len(), 33);
+    assert_eq!(PublicKey::from_qb2(&qb2)?, key);
+    assert!(PublicKey::from_qb2(&qb2[1..]).is_err());
+    // Pair with raw value of wrong size has no binary encoding
+    assert!(matches!(
+        (Basic::Ed25519, vec![0; 5]).qb2(),
+        Err(Error::IncorrectLengthError(_))
+    ));
+
+    // Code and raw value pairs are primitives as well
+    let identifier = Identifier::from_qb64(qb64)?;
+    assert_eq!(identifier.0, IdentifierCode::Basic(Basic::Ed25519));
+    assert_eq!(identifier.qb64(), qb64);
+    assert_eq!(identifier.to_str(), qb64);
+    let digest = "EJtQndkvwnMpVGE5oVVbLWSCm-jLviGw1AOOkzBvNwsS";
+    let (_, (code, raw)) = matter::parse_matter::<(PrimitiveCode, Vec<u8>)>(digest).unwrap();
+    assert_eq!(
+        code,
+        PrimitiveCode::SelfAddressing(SelfAddressing::Blake3_256)
+    );
+    assert_eq!(raw, Digest::from_qb64(digest)?.raw());
+
+    let four_chars = (Basic::ECDSAsecp256k1, vec![2; 33]);
+    assert_eq!(four_chars.qb64().len(), 48);
+    assert_eq!(
+        <(Basic, Vec<u8>)>::from_qb64(&four_chars.qb64())?,
+        four_chars
+    );
+    Ok(())
+}
+
+#[test]
+fn test_value_conversions() -> Result<(), Error> {
+    use crate::value::parse_value;
+
+    let digest = "EJtQndkvwnMpVGE5oVVbLWSCm-jLviGw1AOOkzBvNwsS";
+    let (_, value) = parse_value(digest).unwrap();
+    let identifier = Identifier::try_from(&value)?;
+    assert_eq!(identifier.qb64(), digest);
+    assert_eq!(Value::from(identifier), value);
+    assert_eq!(Digest::try_from(&value)?.to_string(), digest);
+    let short = Value::Primitive(
+        PrimitiveCode::SelfAddressing(SelfAddressing::Blake3_256),
+        vec![0; 31],
+    );
+    assert!(matches!(
+        Identifier::try_from(short),
+        Err(Error::IncorrectLengthError(_))
+    ));
+    assert_eq!(
+        PublicKey::try_from(&value),
+        Err(Error::UnexpectedValueError {
+            expected: Basic::KIND.into(),
+            found: "E".into()
+        })
+    );
+
+    let (_, value) = parse_value("1AAG2020-08-22T17c50c09d988921p00c00").unwrap();
+    let timestamp = Timestamp::try_from(&value)?;
+    assert_eq!(timestamp.to_rfc3339(), "2020-08-22T17:50:09.988921+00:00");
+    assert_eq!(Value::from(timestamp), value);
+    assert!(u64::try_from(&value).is_err());
+
+    let (_, value) = parse_value("MAAF").unwrap();
+    assert_eq!(u64::try_from(&value)?, 5);
+    assert_eq!(Value::from(5u64), value);
+    let big = Value::from(u128::MAX);
+    assert_eq!(u128::try_from(&big)?, u128::MAX);
+    assert!(matches!(
+        u64::try_from(big),
+        Err(Error::NumberOverflowError(_))
+    ));
+
+    assert_eq!(
+        Timestamp::try_from(Value::Null),
+        Err(Error::UnexpectedValueError {
+            expected: "timestamp".into(),
+            found: "null".into()
+        })
+    );
+    Ok(())
+}
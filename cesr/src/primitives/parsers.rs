@@ -2,60 +2,112 @@ use std::str::FromStr;
 
 use chrono::{DateTime, FixedOffset};
 use nom::error::make_error;
-use nom::{bytes::complete::take, error::ErrorKind, sequence::tuple};
+use nom::{bytes::complete::take, error::ErrorKind};
 
 use crate::derivation_code::DerivationCode;
-use crate::error::Error;
+use crate::error::{Error, IResult};
 
-use crate::conversion::from_text_to_bytes;
-use crate::primitives::{AnchoringEventSeal, Identifier, IdentifierCode};
+use crate::primitives::matter::{parse_matter, parse_matter_with};
+use crate::primitives::{AnchoringEventSeal, FixedSizeCode, Identifier, IdentifierCode, Primitive};
 
 use super::codes::basic::Basic;
+use super::codes::number::{unpack_number, NumberCode};
 use super::codes::rand_128::Rand128Code;
 use super::codes::self_addressing::SelfAddressing;
 use super::codes::timestamp::TimestampCode;
+use super::codes::TagCode;
 
 pub fn parse_primitive<C: DerivationCode + FromStr<Err = Error>>(
     stream: &str,
-) -> nom::IResult<&str, (C, Vec<u8>)> {
-    let Ok(code) = C::from_str(stream) else {
-        return Err(nom::Err::Error(make_error(stream, ErrorKind::IsNot)));
-    };
-    let (rest, _parsed_code) = take(code.code_size())(stream)?;
-    let (rest, data) = take(code.value_size())(rest)?;
-    let Ok(decoded) = from_text_to_bytes(data) else {
-        return Err(nom::Err::Error(make_error(rest, ErrorKind::IsNot)));
-    };
-    let decoded = decoded[code.code_size() % 4..].to_vec();
-    Ok((rest, (code, decoded)))
+) -> IResult<&str, (C, Vec<u8>)> {
+    parse_matter(stream)
+}
+
+/// Parses primitive as `parse_primitive` does, accepting text with non-zero
+/// lead bits unless `strict`.
+pub(crate) fn parse_primitive_with<C: DerivationCode + FromStr<Err = Error>>(
+    strict: bool,
+) -> impl Fn(&str) -> IResult<&str, (C, Vec<u8>)> {
+    move |s| parse_matter_with(s, strict)
+}
+
+/// Parses fixed size primitive of code `C`.
+pub fn primitive<C: FixedSizeCode>(stream: &str) -> IResult<&str, Primitive<C>> {
+    parse_matter(stream)
+}
+
+/// Parses fixed size primitive of code `C`, accepting text with non-zero
+/// lead bits unless `strict`.
+pub(crate) fn primitive_with<C: FixedSizeCode>(
+    strict: bool,
+) -> impl Fn(&str) -> IResult<&str, Primitive<C>> {
+    move |s| parse_matter_with(s, strict)
 }
 
 // Parsers for specific primitive. Meant to be used to parse group elements of
 // expected type.
-pub fn identifier(s: &str) -> nom::IResult<&str, Identifier> {
-    let (rest, identifier) = match parse_primitive::<SelfAddressing>(s) {
-        Ok(sap) => Ok((sap.0, (IdentifierCode::SelfAddressing(sap.1 .0), sap.1 .1))),
-        Err(_) => match parse_primitive::<Basic>(s) {
-            Ok(bp) => Ok((bp.0, (IdentifierCode::Basic(bp.1 .0), bp.1 .1))),
-            Err(e) => Err(e),
-        },
-    }?;
-    Ok((rest, identifier))
+pub fn identifier(s: &str) -> IResult<&str, Identifier> {
+    identifier_with(true)(s)
 }
 
-pub fn serial_number_parser(s: &str) -> nom::IResult<&str, u64> {
-    let (rest, (_code, value)) = parse_primitive::<Rand128Code>(s)?;
+pub(crate) fn identifier_with(strict: bool) -> impl Fn(&str) -> IResult<&str, Identifier> {
+    move |s| match parse_primitive_with::<SelfAddressing>(strict)(s) {
+        Ok((rest, (code, raw))) => Ok((rest, (IdentifierCode::SelfAddressing(code), raw))),
+        Err(nom::Err::Error(_)) => {
+            let (rest, (code, raw)) = parse_primitive_with::<Basic>(strict)(s)?;
+            Ok((rest, (IdentifierCode::Basic(code), raw)))
+        }
+        Err(e) => Err(e),
+    }
+}
 
-    let sn = {
-        let mut sn_array: [u8; 8] = [0; 8];
-        sn_array.copy_from_slice(&value[8..]);
-        u64::from_be_bytes(sn_array)
-    };
+pub fn tag_parser(s: &str) -> IResult<&str, TagCode> {
+    let (rest, (code, _value)) = parse_primitive::<TagCode>(s)?;
+    Ok((rest, code))
+}
 
-    Ok((rest, sn))
+/// Parses number encoded with one of number codes. Fails if number doesn't
+/// fit into `u128`.
+pub fn number_parser(s: &str) -> IResult<&str, u128> {
+    number_parser_with(true)(s)
 }
 
-pub fn timestamp_parser(s: &str) -> nom::IResult<&str, DateTime<FixedOffset>> {
+pub(crate) fn number_parser_with(strict: bool) -> impl Fn(&str) -> IResult<&str, u128> {
+    move |s| {
+        let (rest, (_code, value)) = parse_primitive_with::<NumberCode>(strict)(s)?;
+        let Ok(num) = unpack_number(&value) else {
+            return Err(nom::Err::Error(make_error(s, ErrorKind::TooLarge)));
+        };
+        Ok((rest, num))
+    }
+}
+
+/// Parses serial number encoded either with legacy 128 bit salt code (`0A`)
+/// or with one of number codes.
+pub fn serial_number_parser(s: &str) -> IResult<&str, u64> {
+    serial_number_parser_with(true)(s)
+}
+
+pub(crate) fn serial_number_parser_with(strict: bool) -> impl Fn(&str) -> IResult<&str, u64> {
+    move |s| {
+        let (rest, sn) = match parse_primitive_with::<Rand128Code>(strict)(s) {
+            Ok((rest, (_code, value))) => {
+                let Ok(sn) = unpack_number(&value) else {
+                    return Err(nom::Err::Error(make_error(s, ErrorKind::TooLarge)));
+                };
+                (rest, sn)
+            }
+            Err(nom::Err::Error(_)) => number_parser_with(strict)(s)?,
+            Err(e) => return Err(e),
+        };
+        let Ok(sn) = u64::try_from(sn) else {
+            return Err(nom::Err::Error(make_error(s, ErrorKind::TooLarge)));
+        };
+        Ok((rest, sn))
+    }
+}
+
+pub fn timestamp_parser(s: &str) -> IResult<&str, DateTime<FixedOffset>> {
     let (more, type_c) = take(4u8)(s)?;
     let Ok(code) = TimestampCode::from_str(type_c) else {
         return Err(nom::Err::Error(make_error(s, ErrorKind::IsNot)));
@@ -77,14 +129,8 @@ pub fn timestamp_parser(s: &str) -> nom::IResult<&str, DateTime<FixedOffset>> {
     Ok((rest, timestamp))
 }
 
-pub fn anchoring_event_seal(s: &str) -> nom::IResult<&str, AnchoringEventSeal> {
-    let (rest, (identifier, serial_number, digest)) = tuple((
-        identifier,
-        serial_number_parser,
-        parse_primitive::<SelfAddressing>,
-    ))(s)?;
-
-    Ok((rest, (identifier, serial_number, digest)))
+pub fn anchoring_event_seal(s: &str) -> IResult<&str, AnchoringEventSeal> {
+    crate::seal::event_seal(s)
 }
 #[cfg(test)]
 pub mod tests {
@@ -100,7 +146,10 @@ pub mod tests {
                 self_addressing::SelfAddressing,
                 self_signing::SelfSigning,
             },
-            parsers::{parse_primitive, serial_number_parser, timestamp_parser},
+            parsers::{
+                number_parser, parse_primitive, parse_primitive_with, serial_number_parser,
+                timestamp_parser,
+            },
         },
     };
 
@@ -203,7 +252,7 @@ pub mod tests {
             99, 187, 90, 56, 199, 85, 29, 251, 61, 172, 47, 235, 177,
         ];
         let sai_str = "ELC5L3iBVD77d_MYbYGGCUQgqQBju1o4x1Ud-z2sL-ux";
-        let str_to_parse = [&sai_str, "more"].join("");
+        let str_to_parse = [sai_str, "more"].join("");
         assert_eq!(
             parse_primitive::<SelfAddressing>(&str_to_parse),
             Ok(("more", (SelfAddressing::Blake3_256, digest_raw)))
@@ -214,7 +263,7 @@ pub mod tests {
     fn test_signature() {
         let signature_string =
         "0Bq1UBr1QD5TokdcnO_FmnoYsd8rB4_-oaQtk0dfFSSXPcxAu7pSaQIVfkhzckCVmTIgrdxyXS21uZgs7NxoyZAQ";
-        let string_to_parse = [&signature_string, "more"].join("");
+        let string_to_parse = [signature_string, "more"].join("");
 
         let signature_raw = vec![
             181, 80, 26, 245, 64, 62, 83, 162, 71, 92, 156, 239, 197, 154, 122, 24, 177, 223, 43,
@@ -223,16 +272,46 @@ pub mod tests {
             236, 220, 104, 201, 144, 16,
         ];
 
+        // Lead bits of signature aren't zero, so only lenient parser reads it
         assert_eq!(
-            parse_primitive::<SelfSigning>(&string_to_parse),
+            parse_primitive_with::<SelfSigning>(false)(&string_to_parse),
             Ok(("more", (SelfSigning::Ed25519Sha512, signature_raw)))
         );
+        assert!(matches!(
+            parse_primitive::<SelfSigning>(&string_to_parse),
+            Err(nom::Err::Failure(_))
+        ));
     }
 
     #[test]
     fn test_sn_parse() {
         let sn = serial_number_parser("0AAAAAAAAAAAAAAAAAAAAAAD").unwrap();
         assert_eq!(sn, ("", 3));
+
+        let sn = serial_number_parser("MAADmore").unwrap();
+        assert_eq!(sn, ("more", 3));
+
+        let sn = serial_number_parser("NP__________").unwrap();
+        assert_eq!(sn, ("", u64::MAX));
+
+        // Doesn't fit into u64
+        assert!(serial_number_parser("SAAAAQAAAAAAAAAA").is_err());
+        assert!(serial_number_parser("0AAQAAAAAAAAAAAAAAAAAAAA").is_err());
+    }
+
+    #[test]
+    fn test_number_parse() {
+        use crate::primitives::codes::number::pack_number;
+
+        for num in [0, 7, 70_000, u64::MAX as u128 + 1, u128::MAX] {
+            let packed = pack_number(num);
+            assert_eq!(number_parser(&packed), Ok(("", num)));
+            let (rest, value) = parse_one(&packed).unwrap();
+            assert!(rest.is_empty());
+            assert_eq!(value.to_string(), packed);
+        }
+        // 17 byte number bigger than u128::MAX
+        assert!(number_parser("UAEAAAAAAAAAAAAAAAAAAAAA").is_err());
     }
 
     #[test]
@@ -265,4 +344,87 @@ pub mod tests {
         assert!(rest.is_empty());
         assert_eq!(parsed_random.to_string(), random);
     }
+
+    #[test]
+    fn test_non_canonical() {
+        use crate::{
+            config::ParserConfig,
+            error::{Error, ParsingError},
+            parse_all, parse_all_ref, parse_all_ref_with, parse_all_with,
+            primitives::{Matter, PublicKey},
+        };
+
+        // Lead bits of the third char aren't part of code, so `q` encodes
+        // the same signature as canonical `C`
+        let canonical =
+            "0BC1UBr1QD5TokdcnO_FmnoYsd8rB4_-oaQtk0dfFSSXPcxAu7pSaQIVfkhzckCVmTIgrdxyXS21uZgs7NxoyZAQ";
+        let padded = canonical.replacen('C', "q", 1);
+        assert!(parse_primitive::<SelfSigning>(canonical).is_ok());
+        assert_eq!(
+            parse_all(&padded),
+            Err(ParsingError::NonZeroPadBits(padded.clone()))
+        );
+        let (_, values) = parse_all_ref(&padded).unwrap();
+        assert_eq!(
+            values[0].to_owned(),
+            Err(ParsingError::NonZeroPadBits(padded.clone()))
+        );
+
+        let key = "DA4cgeFcpglZf6fQ7u1j8fMs7GbkOQBzVHhBJlaHQLC9";
+        assert!(PublicKey::from_qb64(key).is_ok());
+        assert!(matches!(
+            PublicKey::from_qb64(&key.replacen('A', "Q", 1)),
+            Err(Error::NonZeroPadBitsError(_))
+        ));
+        // Padding chars would shorten the value
+        assert!(matches!(
+            PublicKey::from_qb64(&[&key[..42], "=="].concat()),
+            Err(Error::NonBase64TextError(_))
+        ));
+
+        // Lead bytes of variable length value have to be zero
+        assert!(parse_all("5BACAGhlbGxv").is_ok());
+        assert!(matches!(
+            parse_all("5BACBGhlbGxv"),
+            Err(ParsingError::NonZeroPadBits(_))
+        ));
+
+        // Lenient parser drops lead bits, so both texts give the same values
+        let lenient = ParserConfig::default().with_strict(false);
+        assert_eq!(
+            parse_all_with(&padded, &lenient).unwrap(),
+            parse_all(canonical).unwrap()
+        );
+        let (_, values) = parse_all_ref_with(&padded, &lenient).unwrap();
+        assert_eq!(
+            values[0].to_owned().unwrap(),
+            parse_all(canonical).unwrap().1[0]
+        );
+        assert_eq!(
+            parse_all_with("5BACBGhlbGxv", &lenient).unwrap(),
+            parse_all("5BACAGhlbGxv").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decode_lead() {
+        use crate::{conversion::decode_lead, error::Error};
+
+        assert_eq!(decode_lead("AAB", 1, 1, true), Ok(vec![0, 1]));
+        assert_eq!(decode_lead("AQID", 0, 0, true), Ok(vec![1, 2, 3]));
+        assert!(matches!(
+            decode_lead("AAB", 0, 0, true),
+            Err(Error::MisalignedTextError(_))
+        ));
+        assert!(matches!(
+            decode_lead("QAB", 1, 1, true),
+            Err(Error::NonZeroPadBitsError(_))
+        ));
+        // Lenient decoding drops lead bits whatever they are
+        assert_eq!(decode_lead("QAB", 1, 1, false), Ok(vec![0, 1]));
+        assert!(matches!(
+            decode_lead("AQ==", 0, 0, false),
+            Err(Error::NonBase64TextError(_))
+        ));
+    }
 }
@@ -0,0 +1,262 @@
+use std::fmt;
+
+use nom::error::{make_error, ErrorKind};
+
+use crate::{
+    conversion::{from_bytes_to_text, is_base64, write_b64_with_lead},
+    derivation_code::DerivationCode,
+    encode::{qb64_len, Encode},
+    error::{Error, IResult},
+    value::{primitive_value, Value},
+    variable_length::{
+        LargeVariableLengthCode, LeadBytes, SmallVariableLengthCode, VariableLengthCode,
+        VariableLengthPrimitive,
+    },
+};
+
+use super::codes::{label::LabelCode, PrimitiveCode, TagCode};
+
+/// Base64 text of any length, encoded with variable length Base64 string
+/// codes.
+///
+/// Text of length divisible by 4 that starts with `A` can't be recovered
+/// exactly, because leading `A` is indistinguishable from zero pad.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Bext {
+    primitive: VariableLengthPrimitive,
+}
+
+impl Bext {
+    pub fn new(text: &str) -> Result<Self, Error> {
+        if !is_base64(text) {
+            return Err(Error::NonBase64TextError(text.to_string()));
+        }
+        Ok(Self {
+            primitive: VariableLengthPrimitive::create_from_str(
+                SmallVariableLengthCode::Base64String,
+                text,
+            ),
+        })
+    }
+
+    /// Returns Base64 text without lead bytes.
+    pub fn text(&self) -> String {
+        let encoded = from_bytes_to_text(self.primitive.value());
+        let wad_size = match self.primitive.code().lead_bytes() {
+            LeadBytes::Zero if encoded.starts_with('A') => 1,
+            LeadBytes::Zero => 0,
+            LeadBytes::One => 2,
+            LeadBytes::Two => 3,
+        };
+        encoded[wad_size..].to_string()
+    }
+
+    pub fn to_cesr(&self) -> String {
+        self.primitive.to_cesr()
+    }
+}
+
+impl TryFrom<VariableLengthPrimitive> for Bext {
+    type Error = Error;
+
+    fn try_from(primitive: VariableLengthPrimitive) -> Result<Self, Self::Error> {
+        match primitive.code() {
+            VariableLengthCode::Small {
+                code: SmallVariableLengthCode::Base64String,
+                ..
+            }
+            | VariableLengthCode::Large {
+                code: LargeVariableLengthCode::Base64String,
+                ..
+            } => Ok(Self { primitive }),
+            _ => Err(Error::UnknownCodeError),
+        }
+    }
+}
+
+impl From<Bext> for VariableLengthPrimitive {
+    fn from(bext: Bext) -> Self {
+        bext.primitive
+    }
+}
+
+/// Field label. Picks the most compact encoding for provided text: tag for
+/// short Base64 text, Base64 string for longer Base64 text, label codes for
+/// short non Base64 text and bytes for longer non Base64 text. Base64 text
+/// that Base64 string can't recover, of length divisible by 4 starting with
+/// `A`, is encoded as bytes.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Label {
+    Tag(TagCode),
+    Bext(Bext),
+    // Label bytes without lead bytes
+    Raw(LabelCode, Vec<u8>),
+    Bytes(VariableLengthPrimitive),
+}
+
+impl Label {
+    pub fn new(label: &str) -> Result<Self, Error> {
+        if is_base64(label) && !label.is_empty() {
+            if label.len() <= 11 {
+                Ok(Label::Tag(TagCode::new(label)?))
+            } else if label.len().is_multiple_of(4) && label.starts_with('A') {
+                Ok(Label::Bytes(VariableLengthPrimitive::from_string(label)))
+            } else {
+                Ok(Label::Bext(Bext::new(label)?))
+            }
+        } else {
+            match label.len() {
+                1 => Ok(Label::Raw(LabelCode::Label1, label.as_bytes().to_vec())),
+                2 => Ok(Label::Raw(LabelCode::Label2, label.as_bytes().to_vec())),
+                _ => Ok(Label::Bytes(VariableLengthPrimitive::from_string(label))),
+            }
+        }
+    }
+
+    /// Returns label text.
+    pub fn text(&self) -> Result<String, Error> {
+        match self {
+            Label::Tag(tag_code) => Ok(tag_code.tag()),
+            Label::Bext(bext) => Ok(bext.text()),
+            Label::Raw(_, raw) => {
+                String::from_utf8(raw.clone()).map_err(|_| Error::Utf8DecodingError)
+            }
+            Label::Bytes(primitive) => primitive.as_str().map(|text| text.to_string()),
+        }
+    }
+
+    pub fn to_cesr(&self) -> String {
+        self.to_cesr_string()
+    }
+}
+
+impl Encode for Label {
+    fn encoded_len(&self) -> usize {
+        match self {
+            Label::Tag(tag_code) => tag_code.full_size(),
+            Label::Bext(bext) => bext.primitive.encoded_len(),
+            Label::Raw(code, raw) => qb64_len(code.code_size(), code.lead_size() + raw.len()),
+            Label::Bytes(primitive) => primitive.encoded_len(),
+        }
+    }
+
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        match self {
+            Label::Tag(tag_code) => tag_code.write_code(writer),
+            Label::Bext(bext) => bext.primitive.write_cesr(writer),
+            Label::Raw(code, raw) => {
+                code.write_code(writer)?;
+                write_b64_with_lead(raw, code.lead_size(), code.code_size() % 4, writer)
+            }
+            Label::Bytes(primitive) => primitive.write_cesr(writer),
+        }
+    }
+}
+
+impl From<Label> for Value {
+    fn from(label: Label) -> Self {
+        match label {
+            Label::Tag(tag_code) => Value::Tag(tag_code),
+            Label::Bext(bext) => Value::VariableLengthRaw(bext.into()),
+            Label::Bytes(primitive) => Value::VariableLengthRaw(primitive),
+            Label::Raw(code, raw) => {
+                let raw = std::iter::repeat_n(0, code.lead_size())
+                    .chain(raw)
+                    .collect();
+                Value::Primitive(PrimitiveCode::Label(code), raw)
+            }
+        }
+    }
+}
+
+impl TryFrom<Value> for Label {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Tag(tag_code) => Ok(Label::Tag(tag_code)),
+            Value::VariableLengthRaw(primitive) => match primitive.code() {
+                VariableLengthCode::Small {
+                    code: SmallVariableLengthCode::Bytes,
+                    ..
+                }
+                | VariableLengthCode::Large {
+                    code: LargeVariableLengthCode::Bytes,
+                    ..
+                } => Ok(Label::Bytes(primitive)),
+                _ => Ok(Label::Bext(primitive.try_into()?)),
+            },
+            Value::Primitive(PrimitiveCode::Label(code), raw) => {
+                if raw.len() != code.lead_size() + code.raw_size() {
+                    return Err(Error::IncorrectLengthError(format!("{:?}", raw)));
+                }
+                Ok(Label::Raw(code, raw[code.lead_size()..].to_vec()))
+            }
+            _ => Err(Error::UnknownCodeError),
+        }
+    }
+}
+
+pub fn label_parser(s: &str) -> IResult<&str, Label> {
+    label_parser_with(true)(s)
+}
+
+/// Parses label, accepting text with non-zero lead bits unless `strict`.
+pub(crate) fn label_parser_with(strict: bool) -> impl Fn(&str) -> IResult<&str, Label> {
+    move |s| {
+        let (rest, value) = primitive_value(s, strict)?;
+        let Ok(label) = Label::try_from(value) else {
+            return Err(nom::Err::Error(make_error(s, ErrorKind::IsNot)));
+        };
+        Ok((rest, label))
+    }
+}
+
+#[test]
+fn test_bext() -> Result<(), Error> {
+    for (text, expected) in [
+        ("-", "6AABAAA-"),
+        ("-A", "5AABAA-A"),
+        ("-A-", "4AABA-A-"),
+        ("-A-B", "4AAB-A-B"),
+        ("-field0-field1-field3", "6AAGAAA-field0-field1-field3"),
+    ] {
+        let bext = Bext::new(text)?;
+        assert_eq!(bext.to_cesr(), expected);
+        assert_eq!(bext.text(), text);
+    }
+    assert!(Bext::new("not base64!").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_label() -> Result<(), Error> {
+    for (text, expected) in [
+        ("i", "0J_i"),
+        ("dt", "0Kdt"),
+        ("field_name", "0Ofield_name"),
+        ("longer_field_name", "6AAFAAAlonger_field_name"),
+        ("@", "VABA"),
+        ("a@", "WGFA"),
+        ("a@b", "4BABYUBi"),
+    ] {
+        let label = Label::new(text)?;
+        assert_eq!(label.to_cesr(), expected);
+        let (rest, parsed) = label_parser(expected).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parsed, label);
+        assert_eq!(parsed.text()?, text);
+    }
+
+    // Leading `A` of Base64 string would be taken for pad
+    let label = Label::new("Aaaa_field_0")?;
+    assert!(matches!(label, Label::Bytes(_)));
+    let cesr = label.to_cesr();
+    let (rest, parsed) = label_parser(&cesr).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(parsed.text()?, "Aaaa_field_0");
+    let label = Label::new("Aaaa_field_01")?;
+    assert!(matches!(label, Label::Bext(_)));
+    assert_eq!(label.text()?, "Aaaa_field_01");
+    Ok(())
+}
@@ -0,0 +1,548 @@
+//! Codes defined by applications. Primitives and groups of registered codes
+//! are parsed into `Value::CustomPrimitive` and `Value::CustomGroup`, so new
+//! codes can be used without changes to code enums of this crate.
+
+use std::{fmt, str::FromStr, sync::Arc};
+
+use nom::{
+    bytes::complete::take,
+    error::{make_error, ErrorKind},
+};
+
+use crate::{
+    config::ParserConfig,
+    conversion::is_base64,
+    encode::{qb64_len, write_qb64_value, Encode},
+    error::{Error, IResult, StreamError, StreamErrorKind},
+    genus::{check_counter, CountKind},
+    group::{
+        codes::{counter_len, counter_size, split_counter, write_counter_code, GroupCode},
+        parsers::framed,
+    },
+    primitives::{
+        codes::table::{self, CodeKind},
+        matter::decode_raw,
+    },
+    universal_codes::is_universal_counter,
+    value::{parse_value_with, Value},
+};
+
+/// Parser of one item of custom group. Item is a tuple of values.
+pub type ItemParser = for<'a> fn(&'a str, &ParserConfig) -> IResult<&'a str, Vec<Value>>;
+
+/// Encoder of one item of custom group.
+pub type ItemEncoder = fn(&[Value], &mut dyn fmt::Write) -> fmt::Result;
+
+/// Fixed size primitive code registered by application.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomPrimitiveCode {
+    name: String,
+    hard: String,
+    soft_size: usize,
+    raw_size: usize,
+}
+
+impl CustomPrimitiveCode {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Hard part of code, starting with selector.
+    pub fn hard(&self) -> &str {
+        &self.hard
+    }
+
+    pub fn soft_size(&self) -> usize {
+        self.soft_size
+    }
+
+    pub fn raw_size(&self) -> usize {
+        self.raw_size
+    }
+
+    pub fn code_size(&self) -> usize {
+        self.hard.len() + self.soft_size
+    }
+
+    pub fn full_size(&self) -> usize {
+        qb64_len(self.code_size(), self.raw_size)
+    }
+}
+
+/// Counter code registered by application, with parser and encoder of group
+/// items.
+#[derive(Clone)]
+pub struct CustomGroupCode {
+    name: String,
+    counter: String,
+    kind: CountKind,
+    parser: ItemParser,
+    encoder: ItemEncoder,
+}
+
+impl CustomGroupCode {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Two chars hard part of counter.
+    pub fn counter(&self) -> &str {
+        &self.counter
+    }
+
+    pub fn count_kind(&self) -> CountKind {
+        self.kind
+    }
+}
+
+impl fmt::Debug for CustomGroupCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomGroupCode")
+            .field("name", &self.name)
+            .field("counter", &self.counter)
+            .field("kind", &self.kind)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Codes are equal if they have the same name and counter. Parsers and
+/// encoders aren't compared.
+impl PartialEq for CustomGroupCode {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.counter == other.counter && self.kind == other.kind
+    }
+}
+
+/// Primitive of registered code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomPrimitive {
+    code: Arc<CustomPrimitiveCode>,
+    soft: String,
+    raw: Vec<u8>,
+}
+
+impl CustomPrimitive {
+    /// Creates primitive, checking soft part and raw value sizes against
+    /// code.
+    pub fn new(code: Arc<CustomPrimitiveCode>, soft: &str, raw: Vec<u8>) -> Result<Self, Error> {
+        if soft.len() != code.soft_size || !is_base64(soft) {
+            return Err(Error::IncorrectLengthError(soft.to_string()));
+        }
+        if raw.len() != code.raw_size {
+            return Err(Error::IncorrectLengthError(format!(
+                "{} bytes of {}",
+                raw.len(),
+                code.name
+            )));
+        }
+        Ok(Self {
+            code,
+            soft: soft.to_string(),
+            raw,
+        })
+    }
+
+    pub fn code(&self) -> &CustomPrimitiveCode {
+        &self.code
+    }
+
+    /// Soft part of code.
+    pub fn soft(&self) -> &str {
+        &self.soft
+    }
+
+    pub fn raw(&self) -> &[u8] {
+        &self.raw
+    }
+}
+
+impl Encode for CustomPrimitive {
+    fn encoded_len(&self) -> usize {
+        self.code.full_size()
+    }
+
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_str(&self.code.hard)?;
+        writer.write_str(&self.soft)?;
+        write_qb64_value(self.code.code_size(), &self.raw, writer)
+    }
+}
+
+/// Group of registered counter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomGroup {
+    code: Arc<CustomGroupCode>,
+    items: Vec<Vec<Value>>,
+}
+
+impl CustomGroup {
+    pub fn new(code: Arc<CustomGroupCode>, items: Vec<Vec<Value>>) -> Self {
+        Self { code, items }
+    }
+
+    pub fn code(&self) -> &CustomGroupCode {
+        &self.code
+    }
+
+    pub fn items(&self) -> &[Vec<Value>] {
+        &self.items
+    }
+
+    pub(crate) fn items_mut(&mut self) -> &mut Vec<Vec<Value>> {
+        &mut self.items
+    }
+
+    /// Count of counter, for content of given size.
+    fn count(&self, content_len: usize) -> usize {
+        match self.code.kind {
+            CountKind::Items => self.items.len(),
+            CountKind::Quadlets => content_len / 4,
+        }
+    }
+
+    /// Text of group items, written by encoder of the code.
+    fn content(&self) -> Result<String, fmt::Error> {
+        let mut content = String::new();
+        for item in &self.items {
+            (self.code.encoder)(item, &mut content)?;
+        }
+        Ok(content)
+    }
+}
+
+impl Encode for CustomGroup {
+    fn encoded_len(&self) -> usize {
+        let content_len = self.content().map_or(0, |content| content.len());
+        counter_len(self.count(content_len)) + content_len
+    }
+
+    /// Fails if count doesn't fit into big counter.
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        let content = self.content()?;
+        let count = self.count(content.len());
+        write_counter_code(&self.code.counter, count as u64, writer)?;
+        writer.write_str(&content)
+    }
+}
+
+/// Codes registered by application.
+#[derive(Debug, Clone, Default)]
+pub struct CodeRegistry {
+    primitives: Vec<Arc<CustomPrimitiveCode>>,
+    groups: Vec<Arc<CustomGroupCode>>,
+}
+
+impl CodeRegistry {
+    /// Registers fixed size primitive code. Hard part starts with selector,
+    /// which sets its size: letter for one char, `0` for two chars and `1` -
+    /// `3` for four chars. Codes of master code table can't be registered,
+    /// except codes which primitives this crate doesn't decode, if sizes
+    /// agree. Code with raw value has to make whole quadlets.
+    pub fn register_primitive(
+        &mut self,
+        name: &str,
+        hard: &str,
+        soft_size: usize,
+        raw_size: usize,
+    ) -> Result<(), Error> {
+        let hard_size = match hard.chars().next() {
+            Some(c) if c.is_ascii_alphabetic() => 1,
+            Some('0') => 2,
+            Some('1'..='3') => 4,
+            _ => return Err(Error::NonBase64TextError(hard.to_string())),
+        };
+        if hard.len() != hard_size || !is_base64(hard) {
+            return Err(Error::IncorrectLengthError(hard.to_string()));
+        }
+        let code_size = hard_size + soft_size;
+        if !(code_size % 4 + raw_size).is_multiple_of(3) {
+            return Err(Error::IncorrectLengthError(format!(
+                "{} bytes of {}",
+                raw_size, name
+            )));
+        }
+        let conflicts = table::lookup(hard).is_some_and(|entry| {
+            entry.kind != CodeKind::Unsupported
+                || entry.soft != soft_size
+                || entry.full != qb64_len(code_size, raw_size)
+        });
+        if conflicts || self.primitive_at(hard).is_some() {
+            return Err(Error::CodeConflictError(hard.to_string()));
+        }
+        self.primitives.push(Arc::new(CustomPrimitiveCode {
+            name: name.to_string(),
+            hard: hard.to_string(),
+            soft_size,
+            raw_size,
+        }));
+        Ok(())
+    }
+
+    /// Registers counter code of group, which items are parsed and encoded
+    /// with given functions. Counter is `-` followed by one Base64 char, that
+    /// isn't used by native counters or universal count codes.
+    pub fn register_group(
+        &mut self,
+        name: &str,
+        counter: &str,
+        kind: CountKind,
+        parser: ItemParser,
+        encoder: ItemEncoder,
+    ) -> Result<(), Error> {
+        check_counter(counter)?;
+        let native = GroupCode::from_str(&[counter, "AA"].concat()).is_ok();
+        // Universal codes, and selectors of genus code and big counters
+        let reserved = is_universal_counter(&counter[1..]) || matches!(&counter[1..], "_" | "-");
+        if native || reserved || self.group_at(counter).is_some() {
+            return Err(Error::CodeConflictError(counter.to_string()));
+        }
+        self.groups.push(Arc::new(CustomGroupCode {
+            name: name.to_string(),
+            counter: counter.to_string(),
+            kind,
+            parser,
+            encoder,
+        }));
+        Ok(())
+    }
+
+    /// Returns primitive code of given name.
+    pub fn primitive(&self, name: &str) -> Option<Arc<CustomPrimitiveCode>> {
+        self.primitives
+            .iter()
+            .find(|code| code.name == name)
+            .cloned()
+    }
+
+    /// Returns group code of given name.
+    pub fn group(&self, name: &str) -> Option<Arc<CustomGroupCode>> {
+        self.groups.iter().find(|code| code.name == name).cloned()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.primitives.is_empty() && self.groups.is_empty()
+    }
+
+    fn primitive_at(&self, s: &str) -> Option<&Arc<CustomPrimitiveCode>> {
+        self.primitives
+            .iter()
+            .find(|code| s.starts_with(code.hard.as_str()))
+    }
+
+    fn group_at(&self, s: &str) -> Option<&Arc<CustomGroupCode>> {
+        self.groups
+            .iter()
+            .find(|code| s.starts_with(code.counter.as_str()))
+    }
+}
+
+/// Parses value of code registered in configuration. Returns `None` if
+/// stream doesn't start with registered code. Counters of code table
+/// selected in configuration take precedence over registered counters.
+pub(crate) fn parse_custom<'a>(
+    stream: &'a str,
+    config: &ParserConfig,
+) -> Option<IResult<&'a str, Value>> {
+    if config.codes.is_empty() {
+        return None;
+    }
+    if let Some(code) = config.codes.primitive_at(stream) {
+        return Some(custom_primitive(stream, code, config.strict));
+    }
+    // Hard part of counter follows `-` of big form
+    let counter = match stream.starts_with("--") {
+        true => stream.get(1..3)?,
+        false => stream.get(..2)?,
+    };
+    let code = config.codes.group_at(counter)?;
+    if config
+        .code_table()
+        .is_some_and(|table| table.knows_counter(counter))
+    {
+        return None;
+    }
+    Some(custom_group(stream, code, config))
+}
+
+fn custom_primitive<'a>(
+    stream: &'a str,
+    code: &Arc<CustomPrimitiveCode>,
+    strict: bool,
+) -> IResult<&'a str, Value> {
+    let (rest, code_text) = take(code.code_size())(stream)?;
+    let (rest, value) = take(code.full_size() - code.code_size())(rest)?;
+    let soft = &code_text[code.hard.len()..];
+    if !is_base64(soft) {
+        return Err(nom::Err::Error(make_error(stream, ErrorKind::IsNot)));
+    }
+    let raw = match decode_raw(code.code_size(), value, strict) {
+        Ok(raw) => raw,
+        Err(Error::NonZeroPadBitsError(_)) => {
+            return Err(StreamError::failure(
+                stream,
+                StreamErrorKind::NonZeroPadBits,
+            ))
+        }
+        Err(_) => return Err(nom::Err::Error(make_error(stream, ErrorKind::IsNot))),
+    };
+    let primitive = CustomPrimitive {
+        code: code.clone(),
+        soft: soft.to_string(),
+        raw,
+    };
+    Ok((rest, Value::CustomPrimitive(primitive)))
+}
+
+fn custom_group<'a>(
+    stream: &'a str,
+    code: &Arc<CustomGroupCode>,
+    config: &ParserConfig,
+) -> IResult<&'a str, Value> {
+    let (rest, counter) = take(counter_size(stream))(stream)?;
+    let Ok((_, count)) = split_counter(counter) else {
+        return Err(nom::Err::Error(make_error(stream, ErrorKind::IsNot)));
+    };
+    let config = config.nested(stream)?;
+    let (rest, items) = framed(rest, count as u32, code.kind, &config, |s| {
+        (code.parser)(s, &config)
+    })?;
+    let group = CustomGroup {
+        code: code.clone(),
+        items,
+    };
+    Ok((rest, Value::CustomGroup(group)))
+}
+
+/// Item parser of `N` values, for groups of tuples of values.
+pub fn parse_tuple<'a, const N: usize>(
+    stream: &'a str,
+    config: &ParserConfig,
+) -> IResult<&'a str, Vec<Value>> {
+    let mut values = Vec::with_capacity(N);
+    let mut rest = stream;
+    for _ in 0..N {
+        let (more, value) = parse_value_with(rest, config)?;
+        values.push(value);
+        rest = more;
+    }
+    Ok((rest, values))
+}
+
+/// Item encoder that writes values one after another, for items parsed with
+/// `parse_tuple`.
+pub fn write_tuple(item: &[Value], writer: &mut dyn fmt::Write) -> fmt::Result {
+    item.iter().try_for_each(|value| value.write_cesr(writer))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::ParserConfig,
+        encode::Encode,
+        error::Error,
+        genus::CountKind,
+        parse_all_ref_with, parse_all_with,
+        registry::{parse_tuple, write_tuple, CodeRegistry, CustomPrimitive},
+        value::Value,
+    };
+
+    const DIGEST: &str = "ELC5L3iBVD77d_MYbYGGCUQgqQBju1o4x1Ud-z2sL-ux";
+
+    fn registry() -> CodeRegistry {
+        let mut registry = CodeRegistry::default();
+        registry.register_primitive("nonce", "1AAZ", 0, 18).unwrap();
+        registry
+            .register_group(
+                "digest couples",
+                "-s",
+                CountKind::Items,
+                parse_tuple::<2>,
+                write_tuple,
+            )
+            .unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_custom_values() {
+        let config = ParserConfig::default().with_codes(registry());
+        let nonce = ["1AAZ", &"A".repeat(24)].concat();
+        let group = ["-sAB", DIGEST, &nonce].concat();
+        let stream = [nonce.as_str(), &group].concat();
+
+        let (rest, values) = parse_all_with(&stream, &config).unwrap();
+        assert!(rest.is_empty());
+        let Value::CustomPrimitive(primitive) = &values[0] else {
+            panic!("Unexpected value: {:?}", values[0]);
+        };
+        assert_eq!(primitive.code().name(), "nonce");
+        assert_eq!(primitive.raw(), &[0; 18]);
+        let Value::CustomGroup(custom) = &values[1] else {
+            panic!("Unexpected value: {:?}", values[1]);
+        };
+        assert_eq!(custom.code().name(), "digest couples");
+        assert_eq!(custom.items().len(), 1);
+        assert_eq!(custom.items()[0][1], values[0]);
+
+        let encoded: String = values.iter().map(Encode::to_cesr_string).collect();
+        assert_eq!(encoded, stream);
+        assert_eq!(values[1].encoded_len(), group.len());
+
+        // Borrowed values are decoded when parsed
+        let (rest, refs) = parse_all_ref_with(&stream, &config).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(refs[1].to_owned().unwrap(), values[1]);
+
+        // Codes aren't known without registry
+        let (rest, _) = parse_all_with(&stream, &ParserConfig::default()).unwrap();
+        assert_eq!(rest, stream);
+    }
+
+    #[test]
+    fn test_register_errors() {
+        let mut registry = registry();
+        let name = "digest";
+        assert_eq!(
+            registry.register_primitive(name, "E", 0, 32),
+            Err(Error::CodeConflictError("E".into()))
+        );
+        assert_eq!(
+            registry.register_primitive(name, "1AAZ", 0, 18),
+            Err(Error::CodeConflictError("1AAZ".into()))
+        );
+        assert!(matches!(
+            registry.register_primitive(name, "1AAY", 0, 32),
+            Err(Error::IncorrectLengthError(_))
+        ));
+        assert!(matches!(
+            registry.register_primitive(name, "-AAY", 0, 33),
+            Err(Error::NonBase64TextError(_))
+        ));
+        assert!(matches!(
+            registry.register_primitive(name, "1AY", 0, 33),
+            Err(Error::IncorrectLengthError(_))
+        ));
+        // Code of known size, that isn't decoded by this crate
+        assert_eq!(
+            registry.register_primitive(name, "1AAH", 0, 33),
+            Err(Error::CodeConflictError("1AAH".into()))
+        );
+        assert!(registry.register_primitive(name, "1AAH", 0, 72).is_ok());
+        for counter in ["-K", "-A", "-E", "-s", "-_", "--"] {
+            assert_eq!(
+                registry.register_group(
+                    name,
+                    counter,
+                    CountKind::Items,
+                    parse_tuple::<1>,
+                    write_tuple
+                ),
+                Err(Error::CodeConflictError(counter.into()))
+            );
+        }
+
+        let code = registry.primitive("nonce").unwrap();
+        assert!(CustomPrimitive::new(code.clone(), "", vec![0; 18]).is_ok());
+        assert!(CustomPrimitive::new(code, "A", vec![0; 18]).is_err());
+    }
+}
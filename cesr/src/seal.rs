@@ -0,0 +1,656 @@
+//! Typed KERI seals. Every seal (de)serializes to JSON object with standard
+//! KERI field labels, as found in event bodies, and encodes to CESR as a
+//! member of counted seal group, as found in attachments.
+
+use std::fmt;
+
+use nom::sequence::tuple;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    derivation_code::DerivationCode,
+    encode::Encode,
+    error::{Error, IResult},
+    group::Group,
+    primitives::{
+        codes::{
+            rand_128::{write_sn, Rand128Code},
+            self_addressing::SelfAddressing,
+            TagCode,
+        },
+        parsers::{identifier_with, primitive_with, serial_number_parser_with, tag_parser},
+        Digest, Identifier,
+    },
+};
+
+/// Seal of arbitrary data digest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DigestSeal {
+    #[serde(rename = "d", with = "qb64_digest")]
+    pub digest: Digest,
+}
+
+/// Seal of Merkle tree root digest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MerkleRootSeal {
+    #[serde(rename = "rd", with = "qb64_digest")]
+    pub root_digest: Digest,
+}
+
+/// Seal of key event: identifier, serial number and event digest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventSeal {
+    #[serde(rename = "i", with = "qb64_identifier")]
+    pub identifier: Identifier,
+    #[serde(rename = "s", with = "hex_sn")]
+    pub sn: u64,
+    #[serde(rename = "d", with = "qb64_digest")]
+    pub digest: Digest,
+}
+
+/// Seal of source event (delegating or issuing) of the same identifier.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SourceSeal {
+    #[serde(rename = "s", with = "hex_sn")]
+    pub sn: u64,
+    #[serde(rename = "d", with = "qb64_digest")]
+    pub digest: Digest,
+}
+
+/// Seal of the last establishment event of identifier.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LastEstablishmentSeal {
+    #[serde(rename = "i", with = "qb64_identifier")]
+    pub identifier: Identifier,
+}
+
+/// Seal of event location in key event log: identifier, serial number,
+/// event type and prior event digest.
+///
+/// Code table has no counter for location seals, so they are encoded in CESR
+/// as bare primitive sequence, without group, and parsed with
+/// `location_seal` or `LocationSeal::from_cesr`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LocationSeal {
+    #[serde(rename = "i", with = "qb64_identifier")]
+    pub identifier: Identifier,
+    #[serde(rename = "s", with = "hex_sn")]
+    pub sn: u64,
+    #[serde(rename = "t", with = "tag_text")]
+    pub ilk: TagCode,
+    #[serde(rename = "p", with = "qb64_digest")]
+    pub prior_digest: Digest,
+}
+
+/// Seal of backer or registrar identifier and its metadata digest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegistrarSeal {
+    #[serde(rename = "bi", with = "qb64_identifier")]
+    pub identifier: Identifier,
+    #[serde(rename = "d", with = "qb64_digest")]
+    pub digest: Digest,
+}
+
+/// Any seal, as found in `a` field of event body. Seal type is recognized
+/// by its JSON field labels.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Seal {
+    Location(LocationSeal),
+    Event(EventSeal),
+    Registrar(RegistrarSeal),
+    Source(SourceSeal),
+    MerkleRoot(MerkleRootSeal),
+    Digest(DigestSeal),
+    LastEstablishment(LastEstablishmentSeal),
+}
+
+impl DigestSeal {
+    pub fn to_cesr(&self) -> String {
+        self.to_cesr_string()
+    }
+}
+
+impl MerkleRootSeal {
+    pub fn to_cesr(&self) -> String {
+        self.to_cesr_string()
+    }
+}
+
+impl EventSeal {
+    pub fn to_cesr(&self) -> String {
+        self.to_cesr_string()
+    }
+}
+
+impl SourceSeal {
+    pub fn to_cesr(&self) -> String {
+        self.to_cesr_string()
+    }
+}
+
+impl LastEstablishmentSeal {
+    pub fn to_cesr(&self) -> String {
+        self.to_cesr_string()
+    }
+}
+
+impl LocationSeal {
+    pub fn to_cesr(&self) -> String {
+        self.to_cesr_string()
+    }
+
+    /// Parses seal from whole CESR text, as encoded by `to_cesr`.
+    pub fn from_cesr(text: &str) -> Result<Self, Error> {
+        from_qb64(location_seal, text)
+    }
+}
+
+impl RegistrarSeal {
+    pub fn to_cesr(&self) -> String {
+        self.to_cesr_string()
+    }
+}
+
+impl Encode for DigestSeal {
+    fn encoded_len(&self) -> usize {
+        self.digest.encoded_len()
+    }
+
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        self.digest.write_cesr(writer)
+    }
+}
+
+impl Encode for MerkleRootSeal {
+    fn encoded_len(&self) -> usize {
+        self.root_digest.encoded_len()
+    }
+
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        self.root_digest.write_cesr(writer)
+    }
+}
+
+impl Encode for EventSeal {
+    fn encoded_len(&self) -> usize {
+        self.identifier.encoded_len() + Rand128Code.full_size() + self.digest.encoded_len()
+    }
+
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        self.identifier.write_cesr(writer)?;
+        write_sn(self.sn, writer)?;
+        self.digest.write_cesr(writer)
+    }
+}
+
+impl Encode for SourceSeal {
+    fn encoded_len(&self) -> usize {
+        Rand128Code.full_size() + self.digest.encoded_len()
+    }
+
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        write_sn(self.sn, writer)?;
+        self.digest.write_cesr(writer)
+    }
+}
+
+impl Encode for LastEstablishmentSeal {
+    fn encoded_len(&self) -> usize {
+        self.identifier.encoded_len()
+    }
+
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        self.identifier.write_cesr(writer)
+    }
+}
+
+impl Encode for LocationSeal {
+    fn encoded_len(&self) -> usize {
+        self.identifier.encoded_len()
+            + Rand128Code.full_size()
+            + self.ilk.full_size()
+            + self.prior_digest.encoded_len()
+    }
+
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        self.identifier.write_cesr(writer)?;
+        write_sn(self.sn, writer)?;
+        self.ilk.write_code(writer)?;
+        self.prior_digest.write_cesr(writer)
+    }
+}
+
+impl Encode for RegistrarSeal {
+    fn encoded_len(&self) -> usize {
+        self.identifier.encoded_len() + self.digest.encoded_len()
+    }
+
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        self.identifier.write_cesr(writer)?;
+        self.digest.write_cesr(writer)
+    }
+}
+
+impl From<(Identifier, u64, Digest)> for EventSeal {
+    fn from((identifier, sn, digest): (Identifier, u64, Digest)) -> Self {
+        Self {
+            identifier,
+            sn,
+            digest,
+        }
+    }
+}
+
+impl From<(u64, Digest)> for SourceSeal {
+    fn from((sn, digest): (u64, Digest)) -> Self {
+        Self { sn, digest }
+    }
+}
+
+impl From<Vec<DigestSeal>> for Group {
+    fn from(seals: Vec<DigestSeal>) -> Self {
+        Group::DigestSeals(seals)
+    }
+}
+
+impl From<Vec<MerkleRootSeal>> for Group {
+    fn from(seals: Vec<MerkleRootSeal>) -> Self {
+        Group::MerkleRootSeals(seals)
+    }
+}
+
+impl From<Vec<EventSeal>> for Group {
+    fn from(seals: Vec<EventSeal>) -> Self {
+        Group::AnchoringSeals(seals)
+    }
+}
+
+impl From<Vec<SourceSeal>> for Group {
+    fn from(seals: Vec<SourceSeal>) -> Self {
+        Group::SourceSealCouples(seals)
+    }
+}
+
+impl From<Vec<LastEstablishmentSeal>> for Group {
+    fn from(seals: Vec<LastEstablishmentSeal>) -> Self {
+        Group::LastEstablishmentSeals(seals)
+    }
+}
+
+impl From<Vec<RegistrarSeal>> for Group {
+    fn from(seals: Vec<RegistrarSeal>) -> Self {
+        Group::RegistrarSeals(seals)
+    }
+}
+
+impl TryFrom<Group> for Vec<Seal> {
+    type Error = Error;
+
+    fn try_from(group: Group) -> Result<Self, Self::Error> {
+        Ok(match group {
+            Group::DigestSeals(seals) => seals.into_iter().map(Seal::Digest).collect(),
+            Group::MerkleRootSeals(seals) => seals.into_iter().map(Seal::MerkleRoot).collect(),
+            Group::AnchoringSeals(seals) => seals.into_iter().map(Seal::Event).collect(),
+            Group::SourceSealCouples(seals) => seals.into_iter().map(Seal::Source).collect(),
+            Group::LastEstablishmentSeals(seals) => {
+                seals.into_iter().map(Seal::LastEstablishment).collect()
+            }
+            Group::RegistrarSeals(seals) => seals.into_iter().map(Seal::Registrar).collect(),
+            other => {
+                return Err(Error::UnexpectedValueError {
+                    expected: "seal group".into(),
+                    found: other.name().into(),
+                })
+            }
+        })
+    }
+}
+
+/// Collects seals into groups in their order, consecutive seals of the same
+/// type into one group, as `Vec<Seal>::try_from` reads them back. Location
+/// seals have no group, so they fail conversion.
+pub fn seal_groups(seals: Vec<Seal>) -> Result<Vec<Group>, Error> {
+    let mut groups: Vec<Group> = vec![];
+    for seal in seals {
+        match (groups.last_mut(), seal) {
+            (Some(Group::DigestSeals(seals)), Seal::Digest(seal)) => seals.push(seal),
+            (Some(Group::MerkleRootSeals(seals)), Seal::MerkleRoot(seal)) => seals.push(seal),
+            (Some(Group::AnchoringSeals(seals)), Seal::Event(seal)) => seals.push(seal),
+            (Some(Group::SourceSealCouples(seals)), Seal::Source(seal)) => seals.push(seal),
+            (Some(Group::LastEstablishmentSeals(seals)), Seal::LastEstablishment(seal)) => {
+                seals.push(seal)
+            }
+            (Some(Group::RegistrarSeals(seals)), Seal::Registrar(seal)) => seals.push(seal),
+            (_, Seal::Digest(seal)) => groups.push(vec![seal].into()),
+            (_, Seal::MerkleRoot(seal)) => groups.push(vec![seal].into()),
+            (_, Seal::Event(seal)) => groups.push(vec![seal].into()),
+            (_, Seal::Source(seal)) => groups.push(vec![seal].into()),
+            (_, Seal::LastEstablishment(seal)) => groups.push(vec![seal].into()),
+            (_, Seal::Registrar(seal)) => groups.push(vec![seal].into()),
+            (_, Seal::Location(_)) => {
+                return Err(Error::UnexpectedValueError {
+                    expected: "seal with group".into(),
+                    found: "location seal".into(),
+                })
+            }
+        }
+    }
+    Ok(groups)
+}
+
+pub fn digest_seal(s: &str) -> IResult<&str, DigestSeal> {
+    digest_seal_with(true)(s)
+}
+
+pub fn merkle_root_seal(s: &str) -> IResult<&str, MerkleRootSeal> {
+    merkle_root_seal_with(true)(s)
+}
+
+pub fn event_seal(s: &str) -> IResult<&str, EventSeal> {
+    event_seal_with(true)(s)
+}
+
+pub fn source_seal(s: &str) -> IResult<&str, SourceSeal> {
+    source_seal_with(true)(s)
+}
+
+pub fn last_establishment_seal(s: &str) -> IResult<&str, LastEstablishmentSeal> {
+    last_establishment_seal_with(true)(s)
+}
+
+pub fn location_seal(s: &str) -> IResult<&str, LocationSeal> {
+    location_seal_with(true)(s)
+}
+
+pub fn registrar_seal(s: &str) -> IResult<&str, RegistrarSeal> {
+    registrar_seal_with(true)(s)
+}
+
+// Seal parsers that accept primitive text with non-zero lead bits unless
+// `strict`, as configured in group parser.
+pub(crate) fn digest_seal_with(strict: bool) -> impl Fn(&str) -> IResult<&str, DigestSeal> {
+    move |s| {
+        let (rest, digest) = primitive_with::<SelfAddressing>(strict)(s)?;
+        Ok((rest, DigestSeal { digest }))
+    }
+}
+
+pub(crate) fn merkle_root_seal_with(
+    strict: bool,
+) -> impl Fn(&str) -> IResult<&str, MerkleRootSeal> {
+    move |s| {
+        let (rest, root_digest) = primitive_with::<SelfAddressing>(strict)(s)?;
+        Ok((rest, MerkleRootSeal { root_digest }))
+    }
+}
+
+pub(crate) fn event_seal_with(strict: bool) -> impl Fn(&str) -> IResult<&str, EventSeal> {
+    move |s| {
+        let (rest, seal) = tuple((
+            identifier_with(strict),
+            serial_number_parser_with(strict),
+            primitive_with::<SelfAddressing>(strict),
+        ))(s)?;
+        Ok((rest, seal.into()))
+    }
+}
+
+pub(crate) fn source_seal_with(strict: bool) -> impl Fn(&str) -> IResult<&str, SourceSeal> {
+    move |s| {
+        let (rest, seal) = tuple((
+            serial_number_parser_with(strict),
+            primitive_with::<SelfAddressing>(strict),
+        ))(s)?;
+        Ok((rest, seal.into()))
+    }
+}
+
+pub(crate) fn last_establishment_seal_with(
+    strict: bool,
+) -> impl Fn(&str) -> IResult<&str, LastEstablishmentSeal> {
+    move |s| {
+        let (rest, identifier) = identifier_with(strict)(s)?;
+        Ok((rest, LastEstablishmentSeal { identifier }))
+    }
+}
+
+pub(crate) fn location_seal_with(strict: bool) -> impl Fn(&str) -> IResult<&str, LocationSeal> {
+    move |s| {
+        let (rest, (identifier, sn, ilk, prior_digest)) = tuple((
+            identifier_with(strict),
+            serial_number_parser_with(strict),
+            tag_parser,
+            primitive_with::<SelfAddressing>(strict),
+        ))(s)?;
+        Ok((
+            rest,
+            LocationSeal {
+                identifier,
+                sn,
+                ilk,
+                prior_digest,
+            },
+        ))
+    }
+}
+
+pub(crate) fn registrar_seal_with(strict: bool) -> impl Fn(&str) -> IResult<&str, RegistrarSeal> {
+    move |s| {
+        let (rest, (identifier, digest)) = tuple((
+            identifier_with(strict),
+            primitive_with::<SelfAddressing>(strict),
+        ))(s)?;
+        Ok((rest, RegistrarSeal { identifier, digest }))
+    }
+}
+
+/// Parses whole text with provided parser, as JSON field holds exactly one
+/// primitive.
+fn from_qb64<T>(parser: fn(&str) -> IResult<&str, T>, text: &str) -> Result<T, Error> {
+    match parser(text) {
+        Ok(("", parsed)) => Ok(parsed),
+        _ => Err(Error::IncorrectLengthError(text.to_string())),
+    }
+}
+
+mod qb64_digest {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use crate::primitives::{CesrPrimitive, Digest};
+
+    pub fn serialize<S: Serializer>(digest: &Digest, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&digest.to_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Digest, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(de::Error::custom)
+    }
+}
+
+mod qb64_identifier {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use crate::primitives::{parsers::identifier, CesrPrimitive, Identifier};
+
+    pub fn serialize<S: Serializer>(id: &Identifier, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&id.to_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Identifier, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        super::from_qb64(identifier, &text).map_err(de::Error::custom)
+    }
+}
+
+/// Serial numbers are lowercase hex strings in KERI event bodies.
+mod hex_sn {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(sn: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:x}", sn))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        u64::from_str_radix(&text, 16).map_err(de::Error::custom)
+    }
+}
+
+mod tag_text {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use crate::primitives::codes::TagCode;
+
+    pub fn serialize<S: Serializer>(tag: &TagCode, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&tag.tag())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<TagCode, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        TagCode::new(&text).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        group::{parsers::parse_group, Group},
+        primitives::codes::TagCode,
+    };
+
+    use super::*;
+
+    const ID: &str = "EKC8085pwSwzLwUGzh-HrEoFDwZnCJq27bVp5atdMT9o";
+    const DIGEST: &str = "EJtQndkvwnMpVGE5oVVbLWSCm-jLviGw1AOOkzBvNwsS";
+
+    #[test]
+    fn test_seals_json() -> Result<(), Error> {
+        let json = format!(
+            r#"[{{"i":"{ID}","s":"a","t":"ixn","p":"{DIGEST}"}},{{"i":"{ID}","s":"a","d":"{DIGEST}"}},{{"bi":"{ID}","d":"{DIGEST}"}},{{"s":"1","d":"{DIGEST}"}},{{"rd":"{DIGEST}"}},{{"d":"{DIGEST}"}},{{"i":"{ID}"}}]"#
+        );
+        let seals: Vec<Seal> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            seals.as_slice(),
+            [
+                Seal::Location(_),
+                Seal::Event(_),
+                Seal::Registrar(_),
+                Seal::Source(_),
+                Seal::MerkleRoot(_),
+                Seal::Digest(_),
+                Seal::LastEstablishment(_),
+            ]
+        ));
+        let Seal::Location(location) = &seals[0] else {
+            unreachable!()
+        };
+        assert_eq!(location.sn, 10);
+        assert_eq!(location.ilk, TagCode::new("ixn")?);
+        assert_eq!(serde_json::to_string(&seals).unwrap(), json);
+
+        // Identifier field must hold exactly one primitive
+        let json = format!(r#"{{"i":"{ID}{ID}"}}"#);
+        assert!(serde_json::from_str::<LastEstablishmentSeal>(&json).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_seals_cesr() {
+        let json = format!(r#"[{{"i":"{ID}","s":"0","d":"{DIGEST}"}}]"#);
+        let seals: Vec<EventSeal> = serde_json::from_str(&json).unwrap();
+        let group = Group::from(seals.clone());
+        let cesr = group.to_cesr_str();
+        assert_eq!(cesr, format!("-SAB{ID}0AAAAAAAAAAAAAAAAAAAAAAA{DIGEST}"));
+        let (rest, parsed) = parse_group(&cesr).unwrap();
+        assert!(rest.is_empty());
+        let parsed: Vec<Seal> = parsed.try_into().unwrap();
+        assert_eq!(parsed, vec![Seal::Event(seals[0].clone())]);
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+
+        let json = format!(
+            r#"[{{"d":"{DIGEST}"}},{{"rd":"{DIGEST}"}},{{"i":"{ID}"}},{{"bi":"{ID}","d":"{DIGEST}"}},{{"s":"1","d":"{DIGEST}"}}]"#
+        );
+        let seals: Vec<Seal> = serde_json::from_str(&json).unwrap();
+        for (seal, expected_code) in seals.into_iter().zip(["-Q", "-R", "-U", "-V", "-T"]) {
+            let group = match seal.clone() {
+                Seal::Digest(seal) => Group::from(vec![seal]),
+                Seal::MerkleRoot(seal) => Group::from(vec![seal]),
+                Seal::LastEstablishment(seal) => Group::from(vec![seal]),
+                Seal::Registrar(seal) => Group::from(vec![seal]),
+                Seal::Source(seal) => Group::from(vec![seal]),
+                _ => unreachable!(),
+            };
+            let cesr = group.to_cesr_str();
+            assert!(cesr.starts_with(expected_code));
+            let (rest, parsed) = parse_group(&cesr).unwrap();
+            assert!(rest.is_empty());
+            assert_eq!(Vec::<Seal>::try_from(parsed).unwrap(), vec![seal]);
+        }
+    }
+
+    #[test]
+    fn test_location_seal_cesr() {
+        let json = format!(r#"{{"i":"{ID}","s":"1","t":"rot","p":"{DIGEST}"}}"#);
+        let seal: LocationSeal = serde_json::from_str(&json).unwrap();
+        let cesr = seal.to_cesr();
+        assert_eq!(
+            cesr,
+            format!("{ID}0AAAAAAAAAAAAAAAAAAAAAAB{}{DIGEST}", "Xrot")
+        );
+        let (rest, parsed) = location_seal(&cesr).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parsed, seal);
+        assert_eq!(LocationSeal::from_cesr(&cesr), Ok(seal.clone()));
+        assert!(LocationSeal::from_cesr(&cesr[..cesr.len() - 1]).is_err());
+
+        // Location seal has no group
+        assert!(matches!(
+            seal_groups(vec![Seal::Location(seal)]),
+            Err(Error::UnexpectedValueError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_seal_groups() {
+        let json = format!(
+            r#"[{{"d":"{DIGEST}"}},{{"d":"{DIGEST}"}},{{"i":"{ID}","s":"1","d":"{DIGEST}"}},{{"d":"{DIGEST}"}}]"#
+        );
+        let seals: Vec<Seal> = serde_json::from_str(&json).unwrap();
+        let groups = seal_groups(seals.clone()).unwrap();
+        assert!(matches!(
+            groups.as_slice(),
+            [
+                Group::DigestSeals(digests),
+                Group::AnchoringSeals(_),
+                Group::DigestSeals(_),
+            ] if digests.len() == 2
+        ));
+        let cesr: String = groups.iter().map(Group::to_cesr_str).collect();
+        let mut rest = cesr.as_str();
+        let mut parsed = vec![];
+        while !rest.is_empty() {
+            let (more, group) = parse_group(rest).unwrap();
+            parsed.extend(Vec::<Seal>::try_from(group).unwrap());
+            rest = more;
+        }
+        assert_eq!(parsed, seals);
+
+        // Group of other items isn't a seal group
+        assert_eq!(
+            Vec::<Seal>::try_from(Group::IndexedControllerSignatures(vec![])),
+            Err(Error::UnexpectedValueError {
+                expected: "seal group".into(),
+                found: "IndexedControllerSignatures".into(),
+            })
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    config::ParserConfig,
+    error::{Error, TranscodingError},
+    genus::CodeTable,
+    parse_all_with,
+    universal_codes::{GenusCountCode, KERI_GENUS},
+    value::Value,
+};
+
+/// Version of KERI code tables of CESR stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CesrVersion {
+    /// CESR 1.0: counts of items and `-V` attachment groups
+    V1,
+    /// CESR 2.0: counts of quadlets and universal `-C` attachment groups
+    V2,
+}
+
+impl CesrVersion {
+    pub fn genus(&self) -> GenusCountCode {
+        let major = match self {
+            CesrVersion::V1 => 1,
+            CesrVersion::V2 => 2,
+        };
+        GenusCountCode::Keri { minor: 0, major }
+    }
+
+    fn code_table(&self) -> CodeTable {
+        match self {
+            CesrVersion::V1 => CodeTable::keri_v1(),
+            CesrVersion::V2 => CodeTable::keri_v2(),
+        }
+    }
+}
+
+impl FromStr for CesrVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" | "1.0" => Ok(CesrVersion::V1),
+            "2" | "2.0" => Ok(CesrVersion::V2),
+            _ => Err(Error::UnknownCodeError),
+        }
+    }
+}
+
+impl Display for CesrVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CesrVersion::V1 => write!(f, "1.0"),
+            CesrVersion::V2 => write!(f, "2.0"),
+        }
+    }
+}
+
+/// Parses stream with code tables of `from` version and encodes it again
+/// with code tables of `to` version. CESR 2.0 output starts with KERI genus
+/// version code, KERI genus version codes of input are dropped. Fails with
+/// all groups that have no equivalent in target version.
+pub fn transcode(
+    stream: &str,
+    from: CesrVersion,
+    to: CesrVersion,
+) -> Result<String, TranscodingError> {
+    let config = ParserConfig::default().with_genus(from.genus());
+    let (rest, values) = parse_all_with(stream, &config)?;
+    if !rest.is_empty() {
+        return Err(TranscodingError::UnparsedData(rest.to_string()));
+    }
+
+    let table = to.code_table();
+    let mut output = match to {
+        CesrVersion::V1 => String::new(),
+        CesrVersion::V2 => Value::VersionGenus(to.genus()).to_string(),
+    };
+    let mut missing = vec![];
+    for value in values {
+        match &value {
+            Value::VersionGenus(genus) if genus.genus() == KERI_GENUS => continue,
+            Value::VersionGenus(genus) => missing.push(format!("-{}", genus)),
+            _ => match value.to_cesr_str_with(&table) {
+                Ok(encoded) => output.push_str(&encoded),
+                Err(Error::NoEquivalentCodeError { code, .. }) => missing.push(code),
+                Err(e) => return Err(e.into()),
+            },
+        }
+    }
+    if !missing.is_empty() {
+        return Err(TranscodingError::NoEquivalent {
+            version: to.to_string(),
+            codes: missing,
+        });
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::TranscodingError;
+
+    use super::{transcode, CesrVersion};
+
+    const PREFIX: &str = "EKC8085pwSwzLwUGzh-HrEoFDwZnCJq27bVp5atdMT9o";
+    const SN: &str = "0AAAAAAAAAAAAAAAAAAAAAAA";
+    const DIGEST: &str = "EJtQndkvwnMpVGE5oVVbLWSCm-jLviGw1AOOkzBvNwsS";
+    const SIGNATURE: &str =
+        "AABB5IVZOhEfcH4TBQgOCyMgyQrJujtBBjT8K_zTPk0-FLMtTZuBgXV7jnLw6fDe6FWtzshh2HGCL_H_j4i1b9kF";
+
+    #[test]
+    fn test_transcode_signatures() {
+        // Two signatures are 44 quadlets
+        let v1 = ["-AAC", SIGNATURE, SIGNATURE].concat();
+        let v2 = ["-_AAACAA", "-KAs", SIGNATURE, SIGNATURE].concat();
+        assert_eq!(
+            transcode(&v1, CesrVersion::V1, CesrVersion::V2).unwrap(),
+            v2
+        );
+        assert_eq!(
+            transcode(&v2, CesrVersion::V2, CesrVersion::V1).unwrap(),
+            v1
+        );
+
+        // Nested signatures group is remapped as well
+        let v1 = ["-FAB", PREFIX, SN, DIGEST, "-AAB", SIGNATURE].concat();
+        let v2 = ["-_AAACAA", "-XAz", PREFIX, SN, DIGEST, "-KAW", SIGNATURE].concat();
+        assert_eq!(
+            transcode(&v1, CesrVersion::V1, CesrVersion::V2).unwrap(),
+            v2
+        );
+        assert_eq!(
+            transcode(&v2, CesrVersion::V2, CesrVersion::V1).unwrap(),
+            v1
+        );
+    }
+
+    #[test]
+    fn test_transcode_attachments_group() {
+        let v1 = ["-VAX", "-AAB", SIGNATURE].concat();
+        let v2 = ["-_AAACAA", "-CAX", "-KAW", SIGNATURE].concat();
+        assert_eq!(
+            transcode(&v1, CesrVersion::V1, CesrVersion::V2).unwrap(),
+            v2
+        );
+        assert_eq!(
+            transcode(&v2, CesrVersion::V2, CesrVersion::V1).unwrap(),
+            v1
+        );
+        assert_eq!(
+            transcode(&v1, CesrVersion::V1, CesrVersion::V1).unwrap(),
+            v1
+        );
+    }
+
+    #[test]
+    fn test_transcode_no_equivalent() {
+        let v2 = ["-QAL", DIGEST, "-KAW", SIGNATURE, "-RAL", DIGEST].concat();
+        assert_eq!(
+            transcode(&v2, CesrVersion::V2, CesrVersion::V1),
+            Err(TranscodingError::NoEquivalent {
+                version: "1.0".into(),
+                codes: vec!["-QAB".into(), "-RAB".into()],
+            })
+        );
+
+        // Generic pipeline group can't be framed in CESR 1.0
+        let v2 = ["-AAX", "-KAW", SIGNATURE].concat();
+        assert!(matches!(
+            transcode(&v2, CesrVersion::V2, CesrVersion::V1),
+            Err(TranscodingError::NoEquivalent { .. })
+        ));
+
+        // Wrong counts leave data unparsed
+        let v1 = ["-AAC", SIGNATURE].concat();
+        assert!(transcode(&v1, CesrVersion::V1, CesrVersion::V2).is_err());
+    }
+}
@@ -1,4 +1,7 @@
-use std::{fmt::Display, str::FromStr};
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
 
 use nom::{
     bytes::complete::take,
@@ -6,9 +9,11 @@ use nom::{
 };
 
 use crate::{
-    conversion::{adjust_with_num, b64_to_num, num_to_b64},
+    conversion::{adjust_with_num, b64_to_num, b64_to_u64, is_base64, num_to_b64, write_b64_num},
     derivation_code::DerivationCode,
-    error::Error,
+    encode::Encode,
+    error::{Error, IResult},
+    group::codes::counter_len,
     value::Value,
 };
 
@@ -19,22 +24,24 @@ pub enum UniversalGroupCode {
     /// Universal Count Codes that allow genus/version override
     OverrideAllowed {
         code: CustomizableCode,
-        quadlets: u16,
+        quadlets: u32,
     },
     /// Universal Count Codes that do not allow genus/version override
-    OverrideNotAllowed { code: FixedCode, quadlets: u16 },
+    OverrideNotAllowed { code: FixedCode, quadlets: u32 },
 }
 
 pub fn generic_pipeline(values: Vec<Value>) -> Value {
-    let data_len: usize = values.iter().map(|v| v.to_string().len()).sum();
+    let data_len: usize = values.iter().map(Encode::encoded_len).sum();
     let universal_group_code = UniversalGroupCode::OverrideAllowed {
         code: CustomizableCode::GenericPipeline,
-        quadlets: (data_len / 4) as u16,
+        quadlets: (data_len / 4) as u32,
     };
 
     Value::UniversalGroup(universal_group_code, values)
 }
 
+/// Parses code text without `-` selector: one char code and two chars count,
+/// or `-`, one char code and five chars count of big form.
 impl FromStr for UniversalGroupCode {
     type Err = Error;
 
@@ -46,81 +53,151 @@ impl FromStr for UniversalGroupCode {
                 let group_code = GenusCountCode::from_str(genus_code)?;
                 Ok(Self::Genus(group_code))
             }
-            'A' | 'B' | 'C' => {
-                let length = s.get(1..3).ok_or(Error::EmptyCodeError)?;
-                let quadlets = b64_to_num(length)?;
-                let special_code = CustomizableCode::from_str(&code.to_string())?;
-                Ok(Self::OverrideAllowed {
-                    code: special_code,
-                    quadlets,
-                })
+            '-' => {
+                let code = s.get(1..2).ok_or(Error::EmptyCodeError)?;
+                let length = s.get(2..7).ok_or(Error::EmptyCodeError)?;
+                Self::from_parts(code, b64_to_u64(length)? as u32)
             }
-            x if x.is_alphabetic() => {
+            _ => {
                 let length = s.get(1..3).ok_or(Error::EmptyCodeError)?;
-                let quadlets = b64_to_num(length)?;
-                let special_code = FixedCode::from_str(&code.to_string())?;
-                Ok(Self::OverrideNotAllowed {
-                    code: special_code,
-                    quadlets,
-                })
+                Self::from_parts(&s[..1], b64_to_u64(length)? as u32)
             }
+        }
+    }
+}
+
+impl UniversalGroupCode {
+    fn from_parts(code: &str, quadlets: u32) -> Result<Self, Error> {
+        match code {
+            "A" | "B" | "C" => Ok(Self::OverrideAllowed {
+                code: CustomizableCode::from_str(code)?,
+                quadlets,
+            }),
+            x if x.chars().all(char::is_alphabetic) => Ok(Self::OverrideNotAllowed {
+                code: FixedCode::from_str(code)?,
+                quadlets,
+            }),
             _ => Err(Error::UnknownCodeError),
         }
     }
+
+    fn quadlets(&self) -> Option<u32> {
+        match self {
+            UniversalGroupCode::Genus(_) => None,
+            UniversalGroupCode::OverrideAllowed { quadlets, .. }
+            | UniversalGroupCode::OverrideNotAllowed { quadlets, .. } => Some(*quadlets),
+        }
+    }
 }
 
 impl Display for UniversalGroupCode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            UniversalGroupCode::Genus(genus_count_code) => write!(f, "{}", genus_count_code),
-            UniversalGroupCode::OverrideAllowed { code, quadlets } => {
-                write!(f, "{}{}", code, adjust_with_num(*quadlets, 2))
-            }
-            UniversalGroupCode::OverrideNotAllowed { code, quadlets } => {
-                write!(f, "{}{}", code, adjust_with_num(*quadlets, 2))
-            }
-        }
+        self.write_code(f)
     }
 }
 
+/// Genus code of KERI and ACDC code tables
+pub const KERI_GENUS: &str = "AAA";
+/// Genus code of TSP code tables
+pub const TSP_GENUS: &str = "AAB";
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum GenusCountCode {
+    /// KERI and ACDC genus
     Keri { minor: u16, major: u16 },
+    /// Trust Spanning Protocol genus
+    Tsp { minor: u16, major: u16 },
+    /// Application defined genus
+    Other {
+        genus: String,
+        minor: u16,
+        major: u16,
+    },
+}
+
+impl GenusCountCode {
+    pub fn new(genus: &str, major: u16, minor: u16) -> Self {
+        match genus {
+            KERI_GENUS => Self::Keri { minor, major },
+            TSP_GENUS => Self::Tsp { minor, major },
+            _ => Self::Other {
+                genus: genus.to_string(),
+                minor,
+                major,
+            },
+        }
+    }
+
+    /// Returns three chars genus code, without selector.
+    pub fn genus(&self) -> &str {
+        match self {
+            GenusCountCode::Keri { .. } => KERI_GENUS,
+            GenusCountCode::Tsp { .. } => TSP_GENUS,
+            GenusCountCode::Other { genus, .. } => genus,
+        }
+    }
+
+    pub fn major(&self) -> u16 {
+        match self {
+            GenusCountCode::Keri { major, .. }
+            | GenusCountCode::Tsp { major, .. }
+            | GenusCountCode::Other { major, .. } => *major,
+        }
+    }
+
+    pub fn minor(&self) -> u16 {
+        match self {
+            GenusCountCode::Keri { minor, .. }
+            | GenusCountCode::Tsp { minor, .. }
+            | GenusCountCode::Other { minor, .. } => *minor,
+        }
+    }
+
+    /// Size in chars of code text, without `-` selector.
+    pub(crate) fn code_size(&self) -> usize {
+        self.genus().len() + 4
+    }
+
+    /// Writes code text, without `-` selector. Fails if version doesn't fit
+    /// into code.
+    pub(crate) fn write_code<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        writer.write_char('_')?;
+        writer.write_str(self.genus())?;
+        write_b64_num(self.major() as u64, 1, writer)?;
+        write_b64_num(self.minor() as u64, 2, writer)
+    }
 }
 
 impl FromStr for GenusCountCode {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let code = s.get(..4).ok_or(Error::EmptyCodeError)?;
-        let minor_version = s.get(5..).ok_or(Error::EmptyCodeError)?;
+        let selector = s.get(..1).ok_or(Error::EmptyCodeError)?;
+        let genus = s.get(1..4).ok_or(Error::EmptyCodeError)?;
         let major_version = s.get(4..5).ok_or(Error::EmptyCodeError)?;
-        match code {
-            "_AAA" => {
-                let major = b64_to_num(major_version)?;
-                let minor = b64_to_num(minor_version)?;
-                Ok(Self::Keri { minor, major })
-            }
-            _ => Err(Error::UnknownCodeError),
+        let minor_version = s.get(5..7).ok_or(Error::EmptyCodeError)?;
+        if selector != "_" || !is_base64(genus) {
+            return Err(Error::UnknownCodeError);
         }
+        let major = b64_to_num(major_version)?;
+        let minor = b64_to_num(minor_version)?;
+        Ok(Self::new(genus, major, minor))
     }
 }
+
 impl Display for GenusCountCode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            GenusCountCode::Keri { minor, major } => {
-                write!(
-                    f,
-                    "_AAA{}{}",
-                    num_to_b64(*major),
-                    adjust_with_num(*minor, 2)
-                )
-            }
-        }
+        write!(
+            f,
+            "_{}{}{}",
+            self.genus(),
+            num_to_b64(self.major()),
+            adjust_with_num(self.minor(), 2)
+        )
     }
 }
 
-pub fn genus_code(s: &str) -> nom::IResult<&str, GenusCountCode> {
+pub fn genus_code(s: &str) -> IResult<&str, GenusCountCode> {
     let (rest, version_genus) = take(7u8)(s)?;
     let Ok(group_code) = GenusCountCode::from_str(version_genus) else {
         return Err(nom::Err::Error(make_error(s, ErrorKind::IsNot)));
@@ -128,7 +205,7 @@ pub fn genus_code(s: &str) -> nom::IResult<&str, GenusCountCode> {
     Ok((rest, group_code))
 }
 
-pub fn short_universal_group_code(s: &str) -> nom::IResult<&str, UniversalGroupCode> {
+pub fn short_universal_group_code(s: &str) -> IResult<&str, UniversalGroupCode> {
     let (rest, payload_type) = take(3u8)(s)?;
     let Ok(group_code) = UniversalGroupCode::from_str(payload_type) else {
         return Err(nom::Err::Error(make_error(s, ErrorKind::IsNot)));
@@ -136,6 +213,26 @@ pub fn short_universal_group_code(s: &str) -> nom::IResult<&str, UniversalGroupC
     Ok((rest, group_code))
 }
 
+/// Checks if text that follows `-` selector starts with universal count code,
+/// in small or big form.
+pub(crate) fn is_universal_counter(s: &str) -> bool {
+    let code = s.strip_prefix('-').unwrap_or(s);
+    matches!(code.chars().next(), Some('A' | 'B' | 'C' | 'E'))
+}
+
+/// Parses universal group code, in small or big form, that follows `-`
+/// selector.
+pub fn universal_group_code(s: &str) -> IResult<&str, UniversalGroupCode> {
+    if !s.starts_with('-') {
+        return short_universal_group_code(s);
+    }
+    let (rest, payload_type) = take(7u8)(s)?;
+    let Ok(group_code) = UniversalGroupCode::from_str(payload_type) else {
+        return Err(nom::Err::Error(make_error(s, ErrorKind::IsNot)));
+    };
+    Ok((rest, group_code))
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum CustomizableCode {
     /// Generic pipeline group up to 4,095 quadlets/triplets
@@ -193,44 +290,58 @@ impl Display for FixedCode {
 }
 
 impl DerivationCode for UniversalGroupCode {
+    /// Size of hard part, including `-` selector. Hard part of big counter
+    /// starts with `--`.
     fn hard_size(&self) -> usize {
-        match self {
-            UniversalGroupCode::Genus(_genus_count_code) => 8,
-            UniversalGroupCode::OverrideAllowed { code, quadlets: _ } => match code {
-                CustomizableCode::GenericPipeline => 2,
-                CustomizableCode::Attachments => 2,
-            },
-            UniversalGroupCode::OverrideNotAllowed { .. } => 2,
+        match self.quadlets() {
+            None => 8,
+            Some(quadlets) => counter_len(quadlets as usize) - self.soft_size(),
         }
     }
 
     fn soft_size(&self) -> usize {
-        match self {
-            UniversalGroupCode::Genus(_genus_count_code) => 0,
-            UniversalGroupCode::OverrideAllowed { .. } => 2,
-            UniversalGroupCode::OverrideNotAllowed { .. } => 2,
+        match self.quadlets() {
+            None => 0,
+            Some(quadlets) if counter_len(quadlets as usize) == 4 => 2,
+            Some(_) => 5,
         }
     }
 
     fn value_size(&self) -> usize {
-        match self {
-            UniversalGroupCode::Genus(_) => 0,
-            UniversalGroupCode::OverrideAllowed { quadlets, .. } => *quadlets as usize,
-            UniversalGroupCode::OverrideNotAllowed { quadlets, .. } => *quadlets as usize,
-        }
+        self.quadlets().unwrap_or_default() as usize
     }
 
-    fn to_str(&self) -> String {
+    /// Writes code text without `-` selector. Fails if count doesn't fit into
+    /// big counter.
+    fn write_code<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
         match self {
-            UniversalGroupCode::Genus(genus_count_code) => genus_count_code.to_string(),
+            UniversalGroupCode::Genus(genus_count_code) => genus_count_code.write_code(writer),
             UniversalGroupCode::OverrideAllowed { code, quadlets } => {
-                format!("{}{}", code, adjust_with_num(*quadlets, 2))
+                write_universal_code(code, *quadlets, writer)
             }
             UniversalGroupCode::OverrideNotAllowed { code, quadlets } => {
-                format!("{}{}", code, adjust_with_num(*quadlets, 2))
+                write_universal_code(code, *quadlets, writer)
             }
         }
     }
+
+    fn to_str(&self) -> String {
+        self.to_string()
+    }
+}
+
+fn write_universal_code<W: fmt::Write + ?Sized>(
+    code: &impl Display,
+    quadlets: u32,
+    writer: &mut W,
+) -> fmt::Result {
+    if counter_len(quadlets as usize) == 4 {
+        write!(writer, "{}", code)?;
+        write_b64_num(quadlets as u64, 2, writer)
+    } else {
+        write!(writer, "-{}", code)?;
+        write_b64_num(quadlets as u64, 5, writer)
+    }
 }
 
 // pub fn parse_primitive(stream: &str) ->  IResult<&str, PrimitiveCode>{
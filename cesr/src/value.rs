@@ -1,22 +1,43 @@
-use std::fmt::Display;
+use std::fmt::{self, Display};
 
-use nom::{character::complete::anychar, combinator::peek, multi::many1, IResult};
+use nom::{
+    bytes::complete::take,
+    character::complete::anychar,
+    combinator::peek,
+    error::{make_error, ErrorKind},
+};
 
 use crate::{
-    conversion::from_bytes_to_text,
+    config::ParserConfig,
+    conversion::{b64_to_num, from_text_to_bytes, write_b64_num},
     derivation_code::DerivationCode,
-    group::parsers::parse_group,
-    payload::{parse_payload, Payload},
+    encode::{qb64_len, write_qb64_value, Encode},
+    error::{Error, IResult, StreamError, StreamErrorKind},
+    genus::CodeTable,
+    group::{
+        codes::{unknown_counter_size, BIG_COUNT_MAX, SMALL_COUNT_MAX},
+        parsers::parse_group_with,
+    },
+    payload::{payload_with, Payload},
     primitives::{
-        codes::{PrimitiveCode, TagCode},
-        parsers::parse_primitive,
+        codes::{special::SpecialCode, unknown_fixed_size, PrimitiveCode, TagCode},
+        parsers::parse_primitive_with,
+    },
+    registry::{parse_custom, CustomGroup, CustomPrimitive},
+    universal_codes::{
+        genus_code, is_universal_counter, universal_group_code, CustomizableCode, GenusCountCode,
+        UniversalGroupCode,
+    },
+    variable_length::{
+        unknown_variable_length_size, variable_length_value_with, VariableLengthPrimitive,
     },
-    universal_codes::{genus_code, short_universal_group_code, GenusCountCode, UniversalGroupCode},
-    variable_length::{variable_length_value, VariableLengthPrimitive},
 };
 
 use super::group::Group;
 
+/// Start of genus version code
+pub(crate) const GENUS_SELECTOR: &str = "-_";
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum Value {
     Payload(Payload),
@@ -26,19 +47,172 @@ pub enum Value {
     UniversalGroup(UniversalGroupCode, Vec<Value>),
     SpecificGroup(Group),
     VariableLengthRaw(VariableLengthPrimitive),
+    Null,
+    Empty,
+    Bool(bool),
+    Escape,
+    /// Code unknown to this crate, kept only if parser is configured to pass
+    /// unknown codes through. `code` contains hard and soft part, `raw`
+    /// contains decoded value with lead bytes.
+    Unknown {
+        code: String,
+        raw: Vec<u8>,
+    },
+    /// Primitive of code registered in parser configuration.
+    CustomPrimitive(CustomPrimitive),
+    /// Group of counter registered in parser configuration.
+    CustomGroup(CustomGroup),
+}
+
+impl Value {
+    /// Short description of value kind, used in error messages.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::Payload(_) => "payload",
+            Value::Primitive(_, _) => "primitive",
+            Value::Tag(_) => "tag",
+            Value::VersionGenus(_) => "version genus",
+            Value::UniversalGroup(_, _) => "universal group",
+            Value::SpecificGroup(_) => "specific group",
+            Value::VariableLengthRaw(_) => "variable length primitive",
+            Value::Null => "null",
+            Value::Empty => "empty",
+            Value::Bool(_) => "bool",
+            Value::Escape => "escape",
+            Value::Unknown { .. } => "unknown",
+            Value::CustomPrimitive(_) => "custom primitive",
+            Value::CustomGroup(_) => "custom group",
+        }
+    }
+
+    /// Error of this value found in place of expected one. Specific groups
+    /// are described by their names.
+    pub(crate) fn unexpected(&self, expected: &str) -> Error {
+        let found = match self {
+            Value::SpecificGroup(group) => group.name(),
+            other => other.kind(),
+        };
+        Error::UnexpectedValueError {
+            expected: expected.into(),
+            found: found.into(),
+        }
+    }
+
+    /// Encodes value with counters of given code table. Fails if value
+    /// contains group that the table can't frame.
+    pub fn to_cesr_str_with(&self, table: &CodeTable) -> Result<String, Error> {
+        self.encode(Some(table))
+    }
+
+    pub(crate) fn encode(&self, table: Option<&CodeTable>) -> Result<String, Error> {
+        let mut text = String::with_capacity(self.encoded_len());
+        self.write_with(table, &mut text)?;
+        Ok(text)
+    }
+
+    /// Writes value with counters of code table, or with native counters if
+    /// there's none.
+    pub(crate) fn write_with<W: fmt::Write + ?Sized>(
+        &self,
+        table: Option<&CodeTable>,
+        writer: &mut W,
+    ) -> Result<(), Error> {
+        match self {
+            Value::SpecificGroup(group) => group.write_with(table, writer),
+            // Content of overriding genus is kept in its own encoding
+            Value::UniversalGroup(_, values)
+                if matches!(values.first(), Some(Value::VersionGenus(_))) =>
+            {
+                Ok(self.write_cesr(writer)?)
+            }
+            Value::UniversalGroup(code, values) => {
+                // Counters of code table may be of other size than native
+                // ones, so content is written first
+                let mut content = String::new();
+                for value in values {
+                    value.write_with(table, &mut content)?;
+                }
+                let quadlets = u32::try_from(content.len() / 4)
+                    .ok()
+                    .filter(|quadlets| *quadlets as usize <= BIG_COUNT_MAX)
+                    .ok_or_else(|| Error::NumberOverflowError(format!("-{} count", code)))?;
+                let code = match code {
+                    UniversalGroupCode::OverrideAllowed { code, .. } => {
+                        UniversalGroupCode::OverrideAllowed {
+                            code: code.clone(),
+                            quadlets,
+                        }
+                    }
+                    UniversalGroupCode::OverrideNotAllowed { code, .. } => {
+                        UniversalGroupCode::OverrideNotAllowed {
+                            code: code.clone(),
+                            quadlets,
+                        }
+                    }
+                    genus => genus.clone(),
+                };
+                match table {
+                    Some(table) if !table.has_universal_codes() => {
+                        match (&code, table.attachment_counter()) {
+                            (
+                                UniversalGroupCode::OverrideAllowed {
+                                    code: CustomizableCode::Attachments,
+                                    ..
+                                },
+                                Some(counter),
+                            ) => {
+                                // Tables without universal codes have no big
+                                // counters
+                                if quadlets as usize > SMALL_COUNT_MAX {
+                                    return Err(Error::NumberOverflowError(format!(
+                                        "{} count",
+                                        counter
+                                    )));
+                                }
+                                writer.write_str(counter)?;
+                                write_b64_num(quadlets as u64, 2, writer)?;
+                            }
+                            _ => {
+                                return Err(Error::NoEquivalentCodeError {
+                                    code: format!("-{}", code),
+                                    table: table.name().to_string(),
+                                })
+                            }
+                        }
+                    }
+                    _ => {
+                        writer.write_char('-')?;
+                        code.write_code(writer)?;
+                    }
+                };
+                Ok(writer.write_str(&content)?)
+            }
+            _ => Ok(self.write_cesr(writer)?),
+        }
+    }
 }
 
 pub fn parse_value(stream: &str) -> IResult<&str, Value> {
-    let (rest, selector) = anychar::<_, nom::error::Error<&str>>(stream)?;
+    parse_value_with(stream, &ParserConfig::default())
+}
+
+pub fn parse_value_with<'a>(stream: &'a str, config: &ParserConfig) -> IResult<&'a str, Value> {
+    if let Some(custom) = parse_custom(stream, config) {
+        return custom;
+    }
+    if config.passthrough_unknown {
+        let unknown_size = unknown_counter_size(stream, config)
+            .or_else(|| unknown_variable_length_size(stream))
+            .or_else(|| unknown_fixed_size(stream));
+        if let Some((code_size, value_size)) = unknown_size {
+            return unknown_value(stream, code_size, value_size);
+        }
+    }
+    let (rest, selector) = anychar::<_, StreamError<&str>>(stream)?;
     match selector {
         '{' => {
-            let (rest, payload) = parse_payload(stream.as_bytes()).map_err(|e| {
-                e.map(|e| {
-                    let rest = str::from_utf8(e.input).unwrap();
-                    nom::error::make_error(rest, e.code)
-                })
-            })?;
-            Ok((str::from_utf8(rest).unwrap(), Value::Payload(payload)))
+            let (rest, payload) = payload_with(stream, config)?;
+            Ok((rest, Value::Payload(payload.to_owned())))
         }
         '-' => {
             // It's group
@@ -47,69 +221,252 @@ pub fn parse_value(stream: &str) -> IResult<&str, Value> {
                 '_' => {
                     // Protocol Version Genus
                     let (rest, genus) = genus_code(rest)?;
+                    if config.genera.table_for(&genus).is_none() && !config.passthrough_unknown {
+                        return Err(nom::Err::Error(make_error(stream, ErrorKind::Verify)));
+                    }
                     Ok((rest, Value::VersionGenus(genus)))
                 }
-                'A' | 'B' | 'C' | 'E' => {
+                _ if config.universal_codes() && is_universal_counter(rest) => {
                     // Universal group code
-                    let (rest, group_code) = short_universal_group_code(rest)?;
+                    let (rest, group_code) = universal_group_code(rest)?;
                     let length = group_code.value_size();
+                    config.check_group_quadlets(stream, length)?;
                     let (rest, inner_value) = nom::bytes::complete::take(length * 4)(rest)?;
-                    let (empty_expected, inner_value) = many1(parse_value)(inner_value)?;
-                    if !empty_expected.is_empty() {
-                        return Err(nom::Err::Error(nom::error::make_error(
-                            stream,
-                            nom::error::ErrorKind::Many0,
-                        )));
-                    }
+                    let override_allowed =
+                        matches!(group_code, UniversalGroupCode::OverrideAllowed { .. });
+                    let inner_value = universal_group_values(
+                        inner_value,
+                        &config.nested(stream)?,
+                        override_allowed,
+                    )?;
+                    Ok((rest, Value::UniversalGroup(group_code, inner_value)))
+                }
+                _ if config
+                    .code_table()
+                    .and_then(CodeTable::attachment_counter)
+                    .is_some_and(|counter| stream.starts_with(counter)) =>
+                {
+                    // Attachments group of code table without universal codes
+                    let (rest, code) = take(4u8)(stream)?;
+                    let Ok(quadlets) = b64_to_num(&code[2..]) else {
+                        return Err(nom::Err::Error(make_error(stream, ErrorKind::IsNot)));
+                    };
+                    config.check_group_quadlets(stream, quadlets as usize)?;
+                    let (rest, inner_value) = take(quadlets as usize * 4)(rest)?;
+                    let inner_value =
+                        universal_group_values(inner_value, &config.nested(stream)?, true)?;
+                    let group_code = UniversalGroupCode::OverrideAllowed {
+                        code: CustomizableCode::Attachments,
+                        quadlets: quadlets as u32,
+                    };
                     Ok((rest, Value::UniversalGroup(group_code, inner_value)))
                 }
 
                 _ => {
                     // Specific group code
-                    let (rest, group) = parse_group(stream)?;
+                    let (rest, group) = parse_group_with(stream, config)?;
                     Ok((rest, Value::SpecificGroup(group)))
                 }
             }
         }
-        '4' | '5' | '6' | '7' | '8' => {
-            let (rest, value) = variable_length_value(stream)?;
+        _ => primitive_value(stream, config.strict),
+    }
+}
+
+/// Parses fixed size or variable length primitive, accepting text with
+/// non-zero lead bits unless `strict`.
+pub(crate) fn primitive_value(stream: &str, strict: bool) -> IResult<&str, Value> {
+    let (_, selector) = anychar::<_, StreamError<&str>>(stream)?;
+    match selector {
+        '4' | '5' | '6' | '7' | '8' | '9' => {
+            let (rest, value) = variable_length_value_with(stream, strict)?;
             Ok((rest, Value::VariableLengthRaw(value)))
         }
         x if x.is_alphanumeric() => {
             // It's primitive
-            let (rest, value) = parse_primitive::<PrimitiveCode>(stream)?;
+            let (rest, value) = parse_primitive_with::<PrimitiveCode>(strict)(stream)?;
             match &value.0 {
                 PrimitiveCode::Tag(tag_code) => Ok((rest, Value::Tag(tag_code.clone()))),
+                PrimitiveCode::Special(code) => Ok((rest, (*code).into())),
                 _ => Ok((rest, Value::Primitive(value.0, value.1))),
             }
         }
-        _ => todo!(),
+        _ => Err(nom::Err::Error(make_error(stream, ErrorKind::IsNot))),
+    }
+}
+
+/// Parses all values of universal group, with configuration for group
+/// content. Genus version code at the start of group that allows override
+/// switches code table for the rest of the group only. Genus version code
+/// anywhere else fails with `StreamErrorKind::GenusOverrideNotAllowed`.
+pub(crate) fn universal_group_values<'a>(
+    inner: &'a str,
+    config: &ParserConfig,
+    override_allowed: bool,
+) -> Result<Vec<Value>, nom::Err<StreamError<&'a str>>> {
+    if inner.is_empty() {
+        return Err(nom::Err::Error(make_error(inner, ErrorKind::Many1)));
+    }
+    let mut values = vec![];
+    let mut rest = inner;
+    let scoped;
+    let mut config = config;
+    if override_allowed && rest.starts_with(GENUS_SELECTOR) {
+        let (more, genus) = parse_value_with(rest, config)?;
+        if let Value::VersionGenus(genus) = &genus {
+            scoped = config.clone().with_genus(genus.clone());
+            config = &scoped;
+        }
+        values.push(genus);
+        rest = more;
     }
+    while !rest.is_empty() {
+        if rest.starts_with(GENUS_SELECTOR) {
+            return Err(StreamError::failure(
+                rest,
+                StreamErrorKind::GenusOverrideNotAllowed,
+            ));
+        }
+        config.check_group_items(inner, values.len() + 1)?;
+        let (more, value) = parse_value_with(rest, config)?;
+        values.push(value);
+        rest = more;
+    }
+    Ok(values)
+}
+
+fn unknown_value(stream: &str, code_size: usize, value_size: usize) -> IResult<&str, Value> {
+    let (rest, code) = take(code_size)(stream)?;
+    let (rest, value) = take(value_size)(rest)?;
+    let Ok(raw) = from_text_to_bytes(value) else {
+        return Err(nom::Err::Error(make_error(stream, ErrorKind::IsNot)));
+    };
+    Ok((
+        rest,
+        Value::Unknown {
+            code: code.to_string(),
+            raw,
+        },
+    ))
 }
 
 impl Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let text = match self {
-            Value::Payload(Payload::JSON(json)) => String::from_utf8(json.clone()).unwrap(),
+        self.write_cesr(f)
+    }
+}
+
+impl Encode for Value {
+    fn encoded_len(&self) -> usize {
+        match self {
+            Value::Payload(payload) => payload.as_bytes().len(),
             Value::Primitive(primitive_code, value) => {
-                let dc = primitive_code.to_str();
-                let lead_bytes = if dc.len() % 4 != 0 { dc.len() % 4 } else { 0 };
-                // replace lead bytes with code
-                let derivative_text = from_bytes_to_text(value)[lead_bytes..].to_string();
-                [dc, derivative_text].join("")
+                qb64_len(primitive_code.code_size(), value.len())
             }
-            Value::VersionGenus(genus_count_code) => format!("-{}", genus_count_code),
-            Value::UniversalGroup(universal_group_code, values) => format!(
-                "-{}{}",
-                universal_group_code,
-                values.iter().map(|v| v.to_string()).collect::<String>()
-            ),
-            Value::SpecificGroup(group) => group.to_cesr_str(),
-            Value::Tag(tag_code) => tag_code.to_str(),
-            Value::VariableLengthRaw(prim) => prim.to_cesr(),
-            _ => todo!(),
-        };
-        write!(f, "{}", text)
+            Value::Tag(tag_code) => tag_code.full_size(),
+            Value::VersionGenus(genus_count_code) => 1 + genus_count_code.code_size(),
+            Value::UniversalGroup(universal_group_code, values) => {
+                universal_group_code.code_size()
+                    + values.iter().map(Encode::encoded_len).sum::<usize>()
+            }
+            Value::SpecificGroup(group) => group.encoded_len(),
+            Value::VariableLengthRaw(prim) => prim.encoded_len(),
+            Value::Null | Value::Empty | Value::Bool(_) | Value::Escape => {
+                SpecialCode::Null.full_size()
+            }
+            Value::Unknown { code, raw } => qb64_len(code.len(), raw.len()),
+            Value::CustomPrimitive(primitive) => primitive.encoded_len(),
+            Value::CustomGroup(group) => group.encoded_len(),
+        }
+    }
+
+    /// Payload is written as text, so it fails if payload isn't valid UTF-8.
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        match self {
+            Value::Payload(payload) => {
+                writer.write_str(str::from_utf8(payload.as_bytes()).map_err(|_| fmt::Error)?)
+            }
+            Value::Primitive(primitive_code, value) => {
+                primitive_code.write_code(writer)?;
+                write_qb64_value(primitive_code.code_size(), value, writer)
+            }
+            Value::VersionGenus(genus_count_code) => {
+                writer.write_char('-')?;
+                genus_count_code.write_code(writer)
+            }
+            Value::UniversalGroup(universal_group_code, values) => {
+                writer.write_char('-')?;
+                universal_group_code.write_code(writer)?;
+                values.iter().try_for_each(|value| value.write_cesr(writer))
+            }
+            Value::SpecificGroup(group) => group.write_cesr(writer),
+            Value::Tag(tag_code) => tag_code.write_code(writer),
+            Value::VariableLengthRaw(prim) => prim.write_cesr(writer),
+            Value::Null => SpecialCode::Null.write_code(writer),
+            Value::Empty => SpecialCode::Empty.write_code(writer),
+            Value::Bool(true) => SpecialCode::Yes.write_code(writer),
+            Value::Bool(false) => SpecialCode::No.write_code(writer),
+            Value::Escape => SpecialCode::Escape.write_code(writer),
+            Value::Unknown { code, raw } => {
+                writer.write_str(code)?;
+                write_qb64_value(code.len(), raw, writer)
+            }
+            Value::CustomPrimitive(primitive) => primitive.write_cesr(writer),
+            Value::CustomGroup(group) => group.write_cesr(writer),
+        }
+    }
+}
+
+impl From<SpecialCode> for Value {
+    fn from(code: SpecialCode) -> Self {
+        match code {
+            SpecialCode::Null => Value::Null,
+            SpecialCode::No => Value::Bool(false),
+            SpecialCode::Yes => Value::Bool(true),
+            SpecialCode::Escape => Value::Escape,
+            SpecialCode::Empty => Value::Empty,
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl TryFrom<serde_json::Value> for Value {
+    type Error = Error;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        match value {
+            serde_json::Value::Null => Ok(Value::Null),
+            serde_json::Value::Bool(b) => Ok(Value::Bool(b)),
+            other => Err(Error::UnexpectedValueError {
+                expected: "null or bool".into(),
+                found: other.to_string(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<&Value> for serde_json::Value {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Null => Ok(serde_json::Value::Null),
+            Value::Bool(b) => Ok(serde_json::Value::Bool(*b)),
+            other => Err(other.unexpected("null or bool")),
+        }
+    }
+}
+
+impl TryFrom<Value> for serde_json::Value {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        serde_json::Value::try_from(&value)
     }
 }
 
@@ -125,10 +482,10 @@ mod tests {
                 self_signing::SelfSigning,
                 PrimitiveCode,
             },
-            IdentifierCode,
+            Digest, IdentifierCode, IndexedSignature, PublicKey, Signature,
         },
         universal_codes::{CustomizableCode, GenusCountCode, UniversalGroupCode},
-        value::{parse_value, Value},
+        value::{parse_value, parse_value_with, Value},
         variable_length::VariableLengthCode,
     };
 
@@ -136,33 +493,38 @@ mod tests {
     fn test_parse_controller_signatures() {
         let stream = "-KABAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
         let (_, val) = parse_value(stream).unwrap();
-        let expected_val = Value::SpecificGroup(Group::IndexedControllerSignatures(vec![(
-            AttachedSignatureCode {
-                index: Index::BothSame(0),
-                code: SelfSigning::Ed25519Sha512,
-            },
-            vec![0u8; 64],
-        )]));
+        let expected_val = Value::SpecificGroup(Group::IndexedControllerSignatures(vec![
+            IndexedSignature::new(
+                AttachedSignatureCode {
+                    index: Index::BothSame(0),
+                    code: SelfSigning::Ed25519Sha512,
+                },
+                vec![0u8; 64],
+            )
+            .unwrap(),
+        ]));
         assert_eq!(val, expected_val);
         assert_eq!(val.to_string(), stream);
 
         let stream = "-KACAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA0AACAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
         let (_rest, val) = parse_value(stream).unwrap();
         let expected_val = Value::SpecificGroup(Group::IndexedControllerSignatures(vec![
-            (
+            IndexedSignature::new(
                 AttachedSignatureCode {
                     index: Index::BothSame(0),
                     code: SelfSigning::Ed25519Sha512,
                 },
                 vec![0u8; 64],
-            ),
-            (
+            )
+            .unwrap(),
+            IndexedSignature::new(
                 AttachedSignatureCode {
                     index: Index::Dual(0, 2),
                     code: SelfSigning::Ed448,
                 },
                 vec![0u8; 114],
-            ),
+            )
+            .unwrap(),
         ]));
         assert_eq!(val, expected_val);
         assert_eq!(val.to_string(), stream);
@@ -170,20 +532,22 @@ mod tests {
         let stream_with_extra_data = "-KACAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA0AACAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAextra data";
         let (rest, val) = parse_value(stream_with_extra_data).unwrap();
         let expected_val = Value::SpecificGroup(Group::IndexedControllerSignatures(vec![
-            (
+            IndexedSignature::new(
                 AttachedSignatureCode {
                     index: Index::BothSame(0),
                     code: SelfSigning::Ed25519Sha512,
                 },
                 vec![0u8; 64],
-            ),
-            (
+            )
+            .unwrap(),
+            IndexedSignature::new(
                 AttachedSignatureCode {
                     index: Index::Dual(0, 2),
                     code: SelfSigning::Ed448,
                 },
                 vec![0u8; 114],
-            ),
+            )
+            .unwrap(),
         ]));
         assert_eq!(rest, "extra data");
         assert_eq!(val, expected_val);
@@ -192,7 +556,7 @@ mod tests {
             stream_with_extra_data[..stream_with_extra_data.len() - 10]
         );
 
-        assert!(parse_value("-KABAA0Q7bqPvenjWXo_YIikMBKOg-pghLKwBi1Plm0PEqdv67L1_c6dq9bll7OFnoLp0a74Nw1cBGdjIPcu-yAllHAw").is_ok());
+        assert!(parse_value("-KABAAAQ7bqPvenjWXo_YIikMBKOg-pghLKwBi1Plm0PEqdv67L1_c6dq9bll7OFnoLp0a74Nw1cBGdjIPcu-yAllHAw").is_ok());
     }
 
     #[test]
@@ -202,24 +566,28 @@ mod tests {
         let expected_value = Value::SpecificGroup(Group::SourceSealCouples(vec![
             (
                 1,
-                (
+                Digest::new(
                     SelfAddressing::Blake3_256,
                     vec![
                         155, 80, 157, 217, 47, 194, 115, 41, 84, 97, 57, 161, 85, 91, 45, 100, 130,
                         155, 232, 203, 190, 33, 176, 212, 3, 142, 147, 48, 111, 55, 11, 18,
                     ],
-                ),
-            ),
+                )
+                .unwrap(),
+            )
+                .into(),
             (
                 1,
-                (
+                Digest::new(
                     SelfAddressing::Blake3_256,
                     vec![
                         155, 80, 157, 217, 47, 194, 115, 41, 84, 97, 57, 161, 85, 91, 45, 100, 130,
                         155, 232, 203, 190, 33, 176, 212, 3, 142, 147, 48, 111, 55, 11, 18,
                     ],
-                ),
-            ),
+                )
+                .unwrap(),
+            )
+                .into(),
         ]));
         assert_eq!(attached_sn_dig, expected_value);
         assert_eq!(attached_sn_dig.to_string(), attached_str);
@@ -235,31 +603,36 @@ mod tests {
                 ],
             ),
             0,
-            (
+            Digest::new(
                 SelfAddressing::Blake3_256,
                 vec![
                     160, 188, 211, 206, 105, 193, 44, 51, 47, 5, 6, 206, 31, 135, 172, 74, 5, 15,
                     6, 103, 8, 154, 182, 237, 181, 105, 229, 171, 93, 49, 63, 104,
                 ],
-            ),
-        )]));
+            )
+            .unwrap(),
+        )
+            .into()]));
         assert_eq!(value, expected_value_1);
         assert_eq!(value.to_string(), attached_str[0..116]);
 
         let (_rest, value) = parse_value(rest).unwrap();
 
-        let expected_value_2 = Value::SpecificGroup(Group::IndexedControllerSignatures(vec![(
-            AttachedSignatureCode {
-                code: SelfSigning::Ed25519Sha512,
-                index: Index::BothSame(0),
-            },
-            vec![
-                65, 228, 133, 89, 58, 17, 31, 112, 126, 19, 5, 8, 14, 11, 35, 32, 201, 10, 201,
-                186, 59, 65, 6, 52, 252, 43, 252, 211, 62, 77, 62, 20, 179, 45, 77, 155, 129, 129,
-                117, 123, 142, 114, 240, 233, 240, 222, 232, 85, 173, 206, 200, 97, 216, 113, 130,
-                47, 241, 255, 143, 136, 181, 111, 217, 5,
-            ],
-        )]));
+        let expected_value_2 = Value::SpecificGroup(Group::IndexedControllerSignatures(vec![
+            IndexedSignature::new(
+                AttachedSignatureCode {
+                    code: SelfSigning::Ed25519Sha512,
+                    index: Index::BothSame(0),
+                },
+                vec![
+                    65, 228, 133, 89, 58, 17, 31, 112, 126, 19, 5, 8, 14, 11, 35, 32, 201, 10, 201,
+                    186, 59, 65, 6, 52, 252, 43, 252, 211, 62, 77, 62, 20, 179, 45, 77, 155, 129,
+                    129, 117, 123, 142, 114, 240, 233, 240, 222, 232, 85, 173, 206, 200, 97, 216,
+                    113, 130, 47, 241, 255, 143, 136, 181, 111, 217, 5,
+                ],
+            )
+            .unwrap(),
+        ]));
 
         assert_eq!(value, expected_value_2);
         assert_eq!(value.to_string(), attached_str[116..]);
@@ -267,14 +640,15 @@ mod tests {
         let attached_str = "-MABBMrwi0a-Zblpqe5Hg7w7iz9JCKnMgWKu_W9w4aNUL64y0BB6cL0DtDVDW26lgjbQu0_D_Pd_6ovBZj6fU-Qjmm7epVs51jEOOwXKbmG4yUvCSN-DQSYSc7HXZRp8CfAw9DQL";
         let (_rest, value) = parse_value(attached_str).unwrap();
         let expected_value = Value::SpecificGroup(Group::NontransReceiptCouples(vec![(
-            (
+            PublicKey::new(
                 Basic::Ed25519Nontrans,
                 vec![
                     202, 240, 139, 70, 190, 101, 185, 105, 169, 238, 71, 131, 188, 59, 139, 63, 73,
                     8, 169, 204, 129, 98, 174, 253, 111, 112, 225, 163, 84, 47, 174, 50,
                 ],
-            ),
-            (
+            )
+            .unwrap(),
+            Signature::new(
                 SelfSigning::Ed25519Sha512,
                 vec![
                     122, 112, 189, 3, 180, 53, 67, 91, 110, 165, 130, 54, 208, 187, 79, 195, 252,
@@ -282,7 +656,8 @@ mod tests {
                     214, 49, 14, 59, 5, 202, 110, 97, 184, 201, 75, 194, 72, 223, 131, 65, 38, 18,
                     115, 177, 215, 101, 26, 124, 9, 240, 48, 244, 52, 11,
                 ],
-            ),
+            )
+            .unwrap(),
         )]));
 
         assert_eq!(value, expected_value);
@@ -290,18 +665,21 @@ mod tests {
 
         let cesr_attachment = "-KABAAB6P97kZ3al3V3z3VstRtHRPeOrotuqZZUgBl2yHzgpGyOjAXYGinVqWLAMhdmQ089FTSAzqSTBmJzI8RvIezsJ";
         let (_rest, value) = parse_value(cesr_attachment).unwrap();
-        let expected_value = Value::SpecificGroup(Group::IndexedControllerSignatures(vec![(
-            AttachedSignatureCode {
-                code: SelfSigning::Ed25519Sha512,
-                index: Index::BothSame(0),
-            },
-            vec![
-                122, 63, 222, 228, 103, 118, 165, 221, 93, 243, 221, 91, 45, 70, 209, 209, 61, 227,
-                171, 162, 219, 170, 101, 149, 32, 6, 93, 178, 31, 56, 41, 27, 35, 163, 1, 118, 6,
-                138, 117, 106, 88, 176, 12, 133, 217, 144, 211, 207, 69, 77, 32, 51, 169, 36, 193,
-                152, 156, 200, 241, 27, 200, 123, 59, 9,
-            ],
-        )]));
+        let expected_value = Value::SpecificGroup(Group::IndexedControllerSignatures(vec![
+            IndexedSignature::new(
+                AttachedSignatureCode {
+                    code: SelfSigning::Ed25519Sha512,
+                    index: Index::BothSame(0),
+                },
+                vec![
+                    122, 63, 222, 228, 103, 118, 165, 221, 93, 243, 221, 91, 45, 70, 209, 209, 61,
+                    227, 171, 162, 219, 170, 101, 149, 32, 6, 93, 178, 31, 56, 41, 27, 35, 163, 1,
+                    118, 6, 138, 117, 106, 88, 176, 12, 133, 217, 144, 211, 207, 69, 77, 32, 51,
+                    169, 36, 193, 152, 156, 200, 241, 27, 200, 123, 59, 9,
+                ],
+            )
+            .unwrap(),
+        ]));
 
         assert_eq!(value, expected_value);
         assert_eq!(value.to_string(), cesr_attachment);
@@ -323,6 +701,83 @@ mod tests {
         assert_eq!(value.to_string(), input[0..8]);
     }
 
+    #[test]
+    fn test_genus_parse() {
+        use crate::{
+            config::ParserConfig,
+            genus::{CodeTable, GenusRegistry},
+        };
+
+        let (_rest, value) = parse_value("-_AABBAA").unwrap();
+        assert_eq!(
+            value,
+            Value::VersionGenus(GenusCountCode::Tsp { minor: 0, major: 1 })
+        );
+
+        // Application genus is accepted only once registered
+        let input = "-_XYZBAC";
+        assert!(parse_value(input).is_err());
+        let mut genera = GenusRegistry::default();
+        genera
+            .register("XYZ", 1, CodeTable::new("XYZ 1.0"))
+            .unwrap();
+        let config = ParserConfig::default().with_genera(genera);
+        let (_rest, value) = parse_value_with(input, &config).unwrap();
+        let Value::VersionGenus(genus) = &value else {
+            panic!("Unexpected element type")
+        };
+        assert_eq!((genus.genus(), genus.major(), genus.minor()), ("XYZ", 1, 2));
+        assert_eq!(value.to_string(), input);
+    }
+
+    #[test]
+    fn test_genus_override() {
+        use crate::{error::ParsingError, parse_all, parse_one};
+
+        let signature = ["AA", &"A".repeat(86)].concat();
+        // CESR 1.0 counter of controller signatures in generic group, that
+        // switches to CESR 1.0 table
+        let input = ["-AAZ-_AAABAA-AAB", &signature, "-KAB", &signature].concat();
+        let (rest, values) = parse_all(&input).unwrap();
+        assert!(rest.is_empty());
+        let [Value::UniversalGroup(_, inner), Value::SpecificGroup(Group::IndexedControllerSignatures(_))] =
+            values.as_slice()
+        else {
+            panic!("Unexpected values: {:?}", values)
+        };
+        assert!(matches!(
+            inner.as_slice(),
+            [
+                Value::VersionGenus(GenusCountCode::Keri { minor: 0, major: 1 }),
+                Value::SpecificGroup(Group::IndexedControllerSignatures(_))
+            ]
+        ));
+
+        // Table is switched for the rest of stream by top level genus code
+        let input = ["-_AAABAA-AAB", &signature].concat();
+        let (rest, values) = parse_all(&input).unwrap();
+        assert!(rest.is_empty());
+        assert!(matches!(
+            values.as_slice(),
+            [
+                Value::VersionGenus(_),
+                Value::SpecificGroup(Group::IndexedControllerSignatures(_))
+            ]
+        ));
+
+        // ESSR group doesn't allow override
+        assert_eq!(
+            parse_one("-EAC-_AAACAA"),
+            Err(ParsingError::GenusOverrideNotAllowed("-_AAACAA".into()))
+        );
+        // Override is allowed only at the start of group
+        let input = ["-AAZ-KAB", &signature, "-_AAABAA"].concat();
+        assert!(matches!(
+            parse_one(&input),
+            Err(ParsingError::GenusOverrideNotAllowed(_))
+        ));
+    }
+
     #[test]
     fn test_parse_nested() {
         let input = "-AAX-KABAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
@@ -340,13 +795,16 @@ mod tests {
                 assert_eq!(values.len(), 1);
                 assert_eq!(
                     values[0],
-                    Value::SpecificGroup(Group::IndexedControllerSignatures(vec![(
-                        AttachedSignatureCode {
-                            index: Index::BothSame(0),
-                            code: SelfSigning::Ed25519Sha512,
-                        },
-                        vec![0u8; 64],
-                    )]),)
+                    Value::SpecificGroup(Group::IndexedControllerSignatures(vec![
+                        IndexedSignature::new(
+                            AttachedSignatureCode {
+                                index: Index::BothSame(0),
+                                code: SelfSigning::Ed25519Sha512,
+                            },
+                            vec![0u8; 64],
+                        )
+                        .unwrap()
+                    ]),)
                 );
             }
             _ => panic!("Unexpected element type"),
@@ -408,6 +866,57 @@ mod tests {
         assert_eq!(value.to_string(), sai_str);
     }
 
+    #[test]
+    fn test_unknown_selector() {
+        assert!(matches!(
+            parse_value("+AAA"),
+            Err(nom::Err::Error(e)) if e.input == "+AAA"
+        ));
+        assert!(crate::parse_one("+AAA").is_err());
+
+        // Parsing stops at text that isn't CESR
+        let (rest, values) = crate::parse_all("MAAB MAAB").unwrap();
+        assert_eq!((rest, values.len()), (" MAAB", 1));
+        let (rest, values) = crate::parse_all("MAAB\n").unwrap();
+        assert_eq!((rest, values.len()), ("\n", 1));
+    }
+
+    #[test]
+    fn test_special_values() {
+        let input = "1AAK1AAP1AAM1AAL1AAO";
+        let (rest, values) = nom::multi::many0(parse_value)(input).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(
+            values,
+            vec![
+                Value::Null,
+                Value::Empty,
+                Value::Bool(true),
+                Value::Bool(false),
+                Value::Escape
+            ]
+        );
+        assert_eq!(
+            values.iter().map(|v| v.to_string()).collect::<String>(),
+            input
+        );
+
+        assert_eq!(
+            Value::try_from(serde_json::Value::Null).unwrap(),
+            Value::Null
+        );
+        assert_eq!(
+            Value::try_from(serde_json::json!(true)).unwrap(),
+            true.into()
+        );
+        assert!(Value::try_from(serde_json::json!("text")).is_err());
+        assert_eq!(
+            serde_json::Value::try_from(Value::Bool(false)).unwrap(),
+            serde_json::json!(false)
+        );
+        assert!(serde_json::Value::try_from(Value::Empty).is_err());
+    }
+
     #[test]
     fn test_variable_len() {
         let value_cesr = "5GANAFcHrKyL33QTg0kqmCEp7p_n6ZTtIFzaqKbtxckT1gDSqOFH4uXK";
@@ -428,4 +937,49 @@ mod tests {
             _ => panic!("Unexpected value type"),
         }
     }
+
+    #[test]
+    fn test_passthrough_unknown() {
+        use crate::{config::ParserConfig, parse_all_with};
+
+        let config = ParserConfig::default().with_passthrough_unknown(true);
+        let fixed = ["O", &"A".repeat(43)].concat();
+        let fixed_four = ["1AAH", &"B".repeat(96)].concat();
+        for stream in [
+            "-WACabcdefgh",
+            "--WAAAABabcd",
+            "-DABabcd",
+            "4ZABabcd",
+            "5ZAB_abc",
+            "7AAZAAABabcd",
+            "-AAB-WAA",
+            &fixed,
+            &fixed_four,
+        ] {
+            assert!(parse_value(stream).is_err());
+            let (rest, value) = parse_value_with(stream, &config).unwrap();
+            assert!(rest.is_empty());
+            assert_eq!(value.to_string(), stream);
+        }
+
+        let (_, value) = parse_value_with("-WACabcdefgh", &config).unwrap();
+        assert!(matches!(value, Value::Unknown { ref code, .. } if code == "-WAC"));
+
+        // Known codes are still parsed as usual
+        let stream = "-KABAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA-WABabcdMAAB";
+        let (rest, values) = parse_all_with(stream, &config).unwrap();
+        assert!(rest.is_empty());
+        assert!(matches!(
+            values.as_slice(),
+            [
+                Value::SpecificGroup(_),
+                Value::Unknown { .. },
+                Value::Primitive(_, _)
+            ]
+        ));
+        assert_eq!(
+            values.iter().map(Value::to_string).collect::<String>(),
+            stream
+        );
+    }
 }
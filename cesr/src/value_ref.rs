@@ -0,0 +1,605 @@
+use std::{borrow::Cow, str::FromStr, sync::LazyLock};
+
+use nom::{
+    bytes::complete::take,
+    character::complete::anychar,
+    combinator::peek,
+    error::{make_error, ErrorKind},
+};
+
+use crate::{
+    config::ParserConfig,
+    conversion::{b64_to_num, decode_lead, from_text_to_bytes, is_base64},
+    derivation_code::DerivationCode,
+    error::{Error, IResult, ParsingError, StreamError, StreamErrorKind},
+    genus::CodeTable,
+    group::{
+        codes::{unknown_counter_size, GroupCode},
+        parsers::{group_extent, parse_group_with},
+        Group,
+    },
+    payload::{payload_with, PayloadRef},
+    primitives::{
+        codes::{special::SpecialCode, unknown_fixed_size, PrimitiveCode, TagCode},
+        matter::decode_raw,
+    },
+    registry::parse_custom,
+    universal_codes::{
+        genus_code, is_universal_counter, universal_group_code, CustomizableCode, GenusCountCode,
+        UniversalGroupCode,
+    },
+    value::{universal_group_values, Value, GENUS_SELECTOR},
+    variable_length::{
+        unknown_variable_length_size, variable_length_code, VariableLengthCode,
+        VariableLengthPrimitive,
+    },
+};
+
+static DEFAULT_CONFIG: LazyLock<ParserConfig> = LazyLock::new(ParserConfig::default);
+
+/// Value borrowed from parsed stream. Primitives keep their qb64 text and
+/// groups their whole text, so nothing is decoded or copied until asked for.
+/// Group items are checked only for their sizes, their content is checked
+/// when group is converted with `to_owned`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueRef<'a> {
+    Payload(PayloadRef<'a>),
+    Primitive(PrimitiveRef<'a>),
+    Tag(TagCode),
+    VersionGenus(GenusCountCode),
+    UniversalGroup(UniversalGroupRef<'a>),
+    SpecificGroup(GroupRef<'a>),
+    VariableLengthRaw(VariableLengthRef<'a>),
+    Null,
+    Empty,
+    Bool(bool),
+    Escape,
+    /// Code unknown to this crate, kept only if parser is configured to pass
+    /// unknown codes through.
+    Unknown {
+        code: &'a str,
+        value: &'a str,
+    },
+    /// Value of code registered in parser configuration. Registered codes
+    /// are parsed by application parsers, so value is decoded when parsed.
+    Custom(Value),
+}
+
+impl ValueRef<'_> {
+    /// Decodes value into its owned form.
+    pub fn to_owned(&self) -> Result<Value, ParsingError> {
+        Ok(match self {
+            ValueRef::Payload(payload) => Value::Payload(payload.to_owned()),
+            ValueRef::Primitive(primitive) => Value::Primitive(
+                primitive.code.clone(),
+                primitive
+                    .raw()
+                    .map_err(|e| ParsingError::undecodable(e, primitive.qb64))?,
+            ),
+            ValueRef::Tag(tag) => Value::Tag(tag.clone()),
+            ValueRef::VersionGenus(genus) => Value::VersionGenus(genus.clone()),
+            ValueRef::UniversalGroup(group) => group.to_owned()?,
+            ValueRef::SpecificGroup(group) => Value::SpecificGroup(group.to_group()?),
+            ValueRef::VariableLengthRaw(primitive) => Value::VariableLengthRaw(
+                primitive
+                    .to_owned()
+                    .map_err(|e| ParsingError::undecodable(e, primitive.text))?,
+            ),
+            ValueRef::Null => Value::Null,
+            ValueRef::Empty => Value::Empty,
+            ValueRef::Bool(value) => Value::Bool(*value),
+            ValueRef::Escape => Value::Escape,
+            ValueRef::Unknown { code, value } => Value::Unknown {
+                code: code.to_string(),
+                raw: from_text_to_bytes(value)
+                    .map_err(|_| ParsingError::Error([*code, value].concat()))?,
+            },
+            ValueRef::Custom(value) => value.clone(),
+        })
+    }
+}
+
+impl From<SpecialCode> for ValueRef<'_> {
+    fn from(code: SpecialCode) -> Self {
+        match code {
+            SpecialCode::Null => ValueRef::Null,
+            SpecialCode::No => ValueRef::Bool(false),
+            SpecialCode::Yes => ValueRef::Bool(true),
+            SpecialCode::Escape => ValueRef::Escape,
+            SpecialCode::Empty => ValueRef::Empty,
+        }
+    }
+}
+
+/// Fixed size primitive, kept as its qb64 text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrimitiveRef<'a> {
+    code: PrimitiveCode,
+    qb64: &'a str,
+    /// Lead bits have to be zero, as configured in parser.
+    strict: bool,
+}
+
+impl<'a> PrimitiveRef<'a> {
+    pub fn code(&self) -> &PrimitiveCode {
+        &self.code
+    }
+
+    pub fn qb64(&self) -> &'a str {
+        self.qb64
+    }
+
+    /// Decodes raw value, without lead bytes. Lead bytes have to be zero
+    /// if primitive was parsed in strict mode.
+    pub fn raw(&self) -> Result<Vec<u8>, Error> {
+        let code_size = self.code.code_size();
+        decode_raw(code_size, &self.qb64[code_size..], self.strict)
+    }
+}
+
+/// Variable length primitive, kept as its text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableLengthRef<'a> {
+    code: VariableLengthCode,
+    text: &'a str,
+    /// Lead bytes have to be zero, as configured in parser.
+    strict: bool,
+}
+
+impl<'a> VariableLengthRef<'a> {
+    pub fn code(&self) -> &VariableLengthCode {
+        &self.code
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.text
+    }
+
+    /// Decodes value, without lead bytes. Lead bytes have to be zero if
+    /// primitive was parsed in strict mode.
+    pub fn value(&self) -> Result<Vec<u8>, Error> {
+        let value = &self.text[self.text.len() - self.code.quadlets() as usize * 4..];
+        decode_lead(value, 0, self.code.lead_bytes().size(), self.strict)
+    }
+
+    pub fn to_owned(&self) -> Result<VariableLengthPrimitive, Error> {
+        Ok(VariableLengthPrimitive::new(
+            self.code.clone(),
+            self.value()?,
+        ))
+    }
+}
+
+/// Universal group, kept as its text. Values are parsed one by one while
+/// iterating.
+#[derive(Debug, Clone)]
+pub struct UniversalGroupRef<'a> {
+    code: UniversalGroupCode,
+    text: &'a str,
+    override_allowed: bool,
+    /// Configuration for group content.
+    config: Cow<'a, ParserConfig>,
+}
+
+impl<'a> UniversalGroupRef<'a> {
+    pub fn code(&self) -> &UniversalGroupCode {
+        &self.code
+    }
+
+    /// Whole group text, including counter.
+    pub fn as_str(&self) -> &'a str {
+        self.text
+    }
+
+    /// Iterates over group values. Genus version code at the start of group
+    /// that allows override switches code table for the rest of the group.
+    pub fn values(&self) -> UniversalGroupValues<'a> {
+        UniversalGroupValues {
+            rest: self.content(),
+            config: self.config.clone(),
+            override_allowed: self.override_allowed,
+            items: 0,
+        }
+    }
+
+    /// Group text without counter.
+    fn content(&self) -> &'a str {
+        &self.text[self.code.code_size()..]
+    }
+
+    pub fn to_owned(&self) -> Result<Value, ParsingError> {
+        let values = universal_group_values(self.content(), &self.config, self.override_allowed)?;
+        Ok(Value::UniversalGroup(self.code.clone(), values))
+    }
+}
+
+impl PartialEq for UniversalGroupRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.code == other.code && self.text == other.text
+    }
+}
+
+/// Iterator over values of universal group.
+pub struct UniversalGroupValues<'a> {
+    rest: &'a str,
+    config: Cow<'a, ParserConfig>,
+    override_allowed: bool,
+    items: usize,
+}
+
+impl<'a> Iterator for UniversalGroupValues<'a> {
+    type Item = Result<ValueRef<'a>, ParsingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let override_allowed = std::mem::take(&mut self.override_allowed);
+        if self.rest.starts_with(GENUS_SELECTOR) && !override_allowed {
+            let error = StreamError::failure(self.rest, StreamErrorKind::GenusOverrideNotAllowed);
+            self.rest = "";
+            return Some(Err(error.into()));
+        }
+        self.items += 1;
+        if let Err(error) = self.config.check_group_items(self.rest, self.items) {
+            self.rest = "";
+            return Some(Err(error.into()));
+        }
+        match value_ref(self.rest, &self.config) {
+            Ok((rest, value)) => {
+                if let ValueRef::VersionGenus(genus) = &value {
+                    self.config =
+                        Cow::Owned(self.config.as_ref().clone().with_genus(genus.clone()));
+                }
+                self.rest = rest;
+                Some(Ok(value))
+            }
+            Err(e) => {
+                self.rest = "";
+                Some(Err(e.into()))
+            }
+        }
+    }
+}
+
+/// Specific group, kept as its text. Items are parsed on conversion.
+#[derive(Debug, Clone)]
+pub struct GroupRef<'a> {
+    code: GroupCode,
+    text: &'a str,
+    config: Cow<'a, ParserConfig>,
+}
+
+impl<'a> GroupRef<'a> {
+    pub fn code(&self) -> &GroupCode {
+        &self.code
+    }
+
+    /// Whole group text, including counter.
+    pub fn as_str(&self) -> &'a str {
+        self.text
+    }
+
+    pub fn to_group(&self) -> Result<Group, ParsingError> {
+        parse_whole(parse_group_with(self.text, &self.config))
+    }
+}
+
+impl PartialEq for GroupRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.code == other.code && self.text == other.text
+    }
+}
+
+/// Checks that value was parsed out of the whole text.
+fn parse_whole<T>(parsed: IResult<&str, T>) -> Result<T, ParsingError> {
+    match parsed? {
+        ("", value) => Ok(value),
+        (rest, _) => Err(ParsingError::Error(rest.to_string())),
+    }
+}
+
+pub fn parse_value_ref(stream: &str) -> IResult<&str, ValueRef<'_>> {
+    value_ref(stream, &Cow::Borrowed(&DEFAULT_CONFIG))
+}
+
+pub fn parse_value_ref_with<'a>(
+    stream: &'a str,
+    config: &'a ParserConfig,
+) -> IResult<&'a str, ValueRef<'a>> {
+    value_ref(stream, &Cow::Borrowed(config))
+}
+
+/// Parses value borrowed from stream. Configuration is kept in group values,
+/// so it is borrowed unless genus version code switched it during parsing.
+pub(crate) fn value_ref<'a>(
+    stream: &'a str,
+    config: &Cow<'a, ParserConfig>,
+) -> IResult<&'a str, ValueRef<'a>> {
+    if let Some(custom) = parse_custom(stream, config) {
+        return custom.map(|(rest, value)| (rest, ValueRef::Custom(value)));
+    }
+    if config.passthrough_unknown {
+        let unknown_size = unknown_counter_size(stream, config)
+            .or_else(|| unknown_variable_length_size(stream))
+            .or_else(|| unknown_fixed_size(stream));
+        if let Some((code_size, value_size)) = unknown_size {
+            let (rest, code) = take(code_size)(stream)?;
+            let (rest, value) = take(value_size)(rest)?;
+            return Ok((rest, ValueRef::Unknown { code, value }));
+        }
+    }
+    let (rest, selector) = anychar::<_, StreamError<&str>>(stream)?;
+    match selector {
+        '{' => {
+            let (rest, payload) = payload_with(stream, config)?;
+            Ok((rest, ValueRef::Payload(payload)))
+        }
+        '-' => {
+            let (_, selector) = peek(anychar)(rest)?;
+            match selector {
+                '_' => {
+                    // Protocol Version Genus
+                    let (rest, genus) = genus_code(rest)?;
+                    if config.genera.table_for(&genus).is_none() && !config.passthrough_unknown {
+                        return Err(nom::Err::Error(make_error(stream, ErrorKind::Verify)));
+                    }
+                    Ok((rest, ValueRef::VersionGenus(genus)))
+                }
+                _ if config.universal_codes() && is_universal_counter(rest) => {
+                    // Universal group code
+                    let (_, group_code) = universal_group_code(rest)?;
+                    let override_allowed =
+                        matches!(group_code, UniversalGroupCode::OverrideAllowed { .. });
+                    let quadlets = group_code.value_size();
+                    universal_group_ref(stream, group_code, quadlets, override_allowed, config)
+                }
+                _ if config
+                    .code_table()
+                    .and_then(CodeTable::attachment_counter)
+                    .is_some_and(|counter| stream.starts_with(counter)) =>
+                {
+                    // Attachments group of code table without universal codes
+                    let (_, code) = take(4u8)(stream)?;
+                    let Ok(quadlets) = b64_to_num(&code[2..]) else {
+                        return Err(nom::Err::Error(make_error(stream, ErrorKind::IsNot)));
+                    };
+                    let group_code = UniversalGroupCode::OverrideAllowed {
+                        code: CustomizableCode::Attachments,
+                        quadlets: quadlets as u32,
+                    };
+                    universal_group_ref(stream, group_code, quadlets as usize, true, config)
+                }
+                _ => {
+                    // Specific group code
+                    let (rest, (code, text)) = group_extent(stream, config)?;
+                    let group = GroupRef {
+                        code,
+                        text,
+                        config: config.clone(),
+                    };
+                    Ok((rest, ValueRef::SpecificGroup(group)))
+                }
+            }
+        }
+        '4' | '5' | '6' | '7' | '8' | '9' => {
+            let (rest, code) = variable_length_code(stream)?;
+            let (rest, value) = take(code.quadlets() as usize * 4)(rest)?;
+            // Value is sliced by bytes when decoded
+            if !is_base64(value) {
+                return Err(nom::Err::Error(make_error(stream, ErrorKind::IsNot)));
+            }
+            let text = &stream[..stream.len() - rest.len()];
+            Ok((
+                rest,
+                ValueRef::VariableLengthRaw(VariableLengthRef {
+                    code,
+                    text,
+                    strict: config.strict,
+                }),
+            ))
+        }
+        x if x.is_alphanumeric() => {
+            // It's primitive
+            let Ok(code) = PrimitiveCode::from_str(stream) else {
+                return Err(nom::Err::Error(make_error(stream, ErrorKind::IsNot)));
+            };
+            let (rest, qb64) = take(code.full_size())(stream)?;
+            match code {
+                PrimitiveCode::Tag(tag_code) => Ok((rest, ValueRef::Tag(tag_code))),
+                PrimitiveCode::Special(code) => Ok((rest, code.into())),
+                code => Ok((
+                    rest,
+                    ValueRef::Primitive(PrimitiveRef {
+                        code,
+                        qb64,
+                        strict: config.strict,
+                    }),
+                )),
+            }
+        }
+        _ => Err(nom::Err::Error(make_error(stream, ErrorKind::IsNot))),
+    }
+}
+
+fn universal_group_ref<'a>(
+    stream: &'a str,
+    code: UniversalGroupCode,
+    quadlets: usize,
+    override_allowed: bool,
+    config: &Cow<'a, ParserConfig>,
+) -> IResult<&'a str, ValueRef<'a>> {
+    let (rest, _) = take(code.code_size())(stream)?;
+    config.check_group_quadlets(stream, quadlets)?;
+    let (rest, inner) = take(quadlets * 4)(rest)?;
+    if inner.is_empty() {
+        return Err(nom::Err::Error(make_error(inner, ErrorKind::Many1)));
+    }
+    let group = UniversalGroupRef {
+        code,
+        text: &stream[..stream.len() - rest.len()],
+        override_allowed,
+        config: Cow::Owned(config.nested(stream)?),
+    };
+    Ok((rest, ValueRef::UniversalGroup(group)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::ParserConfig,
+        error::ParsingError,
+        group::{codes::GroupCode, Group},
+        parse_all, parse_all_ref, parse_all_ref_with, parse_all_with,
+        universal_codes::GenusCountCode,
+        value::Value,
+        value_ref::{parse_value_ref, ValueRef},
+    };
+
+    const KEL: &str = r#"{"v":"KERI10JSON000188_","t":"icp","d":"EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg","i":"EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg","s":"0","kt":"1","k":["DA4cgeFcpglZf6fQ7u1j8fMs7GbkOQBzVHhBJlaHQLC9"],"nt":"1","n":["EJMujtnS0x3RGp_kHC2bh3p6cAz_4nKp6E3Yrj2u-Lsh"],"bt":"2","b":["BJq7UABlttINuWJh1Xl2lkqZG4NTdUdqnbFJDa6ZyxCC","BDg1zxxf8u4Hx5IPraZzmStfSCZFZbDzMHjqVcFW5OfP"],"c":[],"a":[]}-KABAADZCv1YufmwIvFbzC9jNoVZx2ZgOF8hzrxcuP9vlhJ0tNAYIvNEh0yKIGtkk1bIhrLIAEScbBmxxPosX-rGSAsD-MABBDg1zxxf8u4Hx5IPraZzmStfSCZFZbDzMHjqVcFW5OfP0BCQwOrc3LZqdYs8OEKhQlP4LpB9AqCVpwyGHCB1nfjrBjSYiWtlcvSYI5Vugh3H3rh0gfDqGHUfRKEQrIXKTWAC-MABBJq7UABlttINuWJh1Xl2lkqZG4NTdUdqnbFJDa6ZyxCC0BCO8ycCB9reZHhv7wT4yEAy-q_IFbCA29ttaU3IcQ1tZAIGNKYNkZMY9EjGfRsq8shizeURuoxdYoRXGscQFVQM"#;
+
+    #[test]
+    fn test_kel_to_owned() {
+        let (rest, values) = parse_all_ref(KEL).unwrap();
+        assert!(rest.is_empty());
+        let (_, expected) = parse_all(KEL).unwrap();
+        let owned = values
+            .iter()
+            .map(ValueRef::to_owned)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(owned, expected);
+
+        // Payload and groups are slices of stream
+        let ValueRef::Payload(payload) = &values[0] else {
+            panic!("Unexpected value: {:?}", values[0])
+        };
+        assert!(KEL.as_bytes().starts_with(payload.as_bytes()));
+        let ValueRef::SpecificGroup(group) = &values[2] else {
+            panic!("Unexpected value: {:?}", values[2])
+        };
+        assert_eq!(group.code(), &GroupCode::NontransferableReceiptCouples(1));
+        assert!(std::ptr::eq(
+            group.as_str().as_ptr(),
+            KEL[KEL.find("-MAB").unwrap()..].as_ptr()
+        ));
+    }
+
+    #[test]
+    fn test_primitive_ref() {
+        let digest = "EJ11vJy_lLwv-lWGZnjhuWUh4EjMQyyMHRH1-uDAxiLg";
+        let stream = [digest, "MAAB"].concat();
+        let (rest, value) = parse_value_ref(&stream).unwrap();
+        assert_eq!(rest, "MAAB");
+        let ValueRef::Primitive(primitive) = &value else {
+            panic!("Unexpected value: {:?}", value)
+        };
+        assert_eq!(primitive.qb64(), digest);
+        assert_eq!(primitive.raw().unwrap().len(), 32);
+        assert_eq!(value.to_owned().unwrap().to_string(), digest);
+
+        // Value is decoded only on demand
+        let invalid = ["E", &"!".repeat(43)].concat();
+        let (_, value) = parse_value_ref(&invalid).unwrap();
+        assert!(matches!(&value, ValueRef::Primitive(primitive) if primitive.raw().is_err()));
+        assert!(value.to_owned().is_err());
+        let multibyte = ["E", &"é".repeat(43)].concat();
+        let (_, value) = parse_value_ref(&multibyte).unwrap();
+        assert!(value.to_owned().is_err());
+
+        // Variable length value has to be Base64, as it's sliced by bytes
+        let (rest, values) = parse_all_ref("4AABéAAA").unwrap();
+        assert_eq!((rest, values.len()), ("4AABéAAA", 0));
+        let (_, value) = parse_value_ref("4AABAAAA").unwrap();
+        assert_eq!(value.to_owned().unwrap().to_string(), "4AABAAAA");
+    }
+
+    #[test]
+    fn test_groups_extent() {
+        let prefix = "EKC8085pwSwzLwUGzh-HrEoFDwZnCJq27bVp5atdMT9o";
+        let sn = "0AAAAAAAAAAAAAAAAAAAAAAA";
+        let digest = "EJtQndkvwnMpVGE5oVVbLWSCm-jLviGw1AOOkzBvNwsS";
+        let signature =
+            "AABB5IVZOhEfcH4TBQgOCyMgyQrJujtBBjT8K_zTPk0-FLMtTZuBgXV7jnLw6fDe6FWtzshh2HGCL_H_j4i1b9kF";
+        let group = ["-XAB", prefix, sn, digest, "-KAC", signature, signature].concat();
+        let stream = [&group, "MAAB"].concat();
+
+        let (rest, value) = parse_value_ref(&stream).unwrap();
+        assert_eq!(rest, "MAAB");
+        let ValueRef::SpecificGroup(group_ref) = &value else {
+            panic!("Unexpected value: {:?}", value)
+        };
+        assert_eq!(group_ref.as_str(), group);
+        assert!(matches!(
+            group_ref.to_group().unwrap(),
+            Group::TransIndexedSigGroups(groups) if groups[0].3.len() == 2
+        ));
+
+        // Nested group has to be controller signatures group
+        let wrong_nested = ["-YAB", prefix, "-LAB", signature].concat();
+        assert!(parse_value_ref(&wrong_nested).is_err());
+        // Nested count has to match signatures
+        let wrong_count = ["-YAB", prefix, "-KAC", signature].concat();
+        assert!(parse_value_ref(&wrong_count).is_err());
+    }
+
+    #[test]
+    fn test_universal_group_values() {
+        let signature = ["AA", &"A".repeat(86)].concat();
+        let input = ["-AAZ-_AAABAA-AAB", &signature, "-KAB", &signature].concat();
+        let (rest, values) = parse_all_ref(&input).unwrap();
+        assert!(rest.is_empty());
+        let [ValueRef::UniversalGroup(group), ValueRef::SpecificGroup(_)] = values.as_slice()
+        else {
+            panic!("Unexpected values: {:?}", values)
+        };
+        let inner = group.values().collect::<Result<Vec<_>, _>>().unwrap();
+        assert!(matches!(
+            inner.as_slice(),
+            [
+                ValueRef::VersionGenus(GenusCountCode::Keri { minor: 0, major: 1 }),
+                ValueRef::SpecificGroup(group)
+            ] if group.code() == &GroupCode::IndexedControllerSignatures(1)
+        ));
+        let (_, expected) = parse_all(&input).unwrap();
+        assert_eq!(group.to_owned().unwrap(), expected[0]);
+
+        // Override is allowed only at the start of group
+        let input = ["-AAZ-KAB", &signature, "-_AAABAA"].concat();
+        let (_, value) = parse_value_ref(&input).unwrap();
+        let ValueRef::UniversalGroup(group) = value else {
+            panic!("Unexpected value: {:?}", value)
+        };
+        let mut values = group.values();
+        assert!(matches!(
+            values.next(),
+            Some(Ok(ValueRef::SpecificGroup(_)))
+        ));
+        assert!(matches!(
+            values.next(),
+            Some(Err(ParsingError::GenusOverrideNotAllowed(_)))
+        ));
+        assert!(values.next().is_none());
+    }
+
+    #[test]
+    fn test_passthrough_unknown_ref() {
+        let config = ParserConfig::default().with_passthrough_unknown(true);
+        let stream = "-KABAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA-WABabcdMAAB";
+        let (rest, values) = parse_all_ref_with(stream, &config).unwrap();
+        assert!(rest.is_empty());
+        assert!(matches!(
+            values[1],
+            ValueRef::Unknown {
+                code: "-WAB",
+                value: "abcd"
+            }
+        ));
+        let owned = values
+            .iter()
+            .map(ValueRef::to_owned)
+            .collect::<Result<Vec<Value>, _>>()
+            .unwrap();
+        assert_eq!(owned, parse_all_with(stream, &config).unwrap().1);
+    }
+}
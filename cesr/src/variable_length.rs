@@ -1,12 +1,26 @@
-use std::{fmt::Display, str::FromStr};
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
 
-use nom::bytes::complete::take;
+use nom::{
+    bytes::complete::take,
+    error::{make_error, ErrorKind},
+};
 
 use crate::{
-    conversion::{adjust_with_num, b64_to_num, from_bytes_to_text, from_text_to_bytes},
-    error::Error,
+    conversion::{
+        adjust_with_num, b64_len, b64_to_u64, decode_lead, from_bytes_to_text, from_text_to_bytes,
+        u64_to_b64, write_b64, write_b64_num,
+    },
+    encode::Encode,
+    error::{Error, IResult, StreamError, StreamErrorKind},
 };
 
+/// Maximal number of quadlets that can be encoded with small variable length
+/// code.
+pub const MAX_SMALL_QUADLETS: usize = 4095;
+
 #[allow(clippy::enum_variant_names)]
 pub enum VariableCodeSelector {
     ShortZeroLeadBytes,
@@ -78,7 +92,7 @@ impl FromStr for VariableCodeSelector {
             "7" => Ok(VariableCodeSelector::LongZeroLeadBytes),
             "8" => Ok(VariableCodeSelector::LongOneLeadBytes),
             "9" => Ok(VariableCodeSelector::LongTwoLeadBytes),
-            _ => todo!("Unknown variable code selector: {}", s),
+            _ => Err(Error::UnknownCodeError),
         }
     }
 }
@@ -90,18 +104,40 @@ pub enum LeadBytes {
     Two,
 }
 
+impl LeadBytes {
+    /// Lead bytes needed to align raw value of given size to 24 bit boundary.
+    pub fn for_raw_size(size: usize) -> Self {
+        match (3 - size % 3) % 3 {
+            0 => LeadBytes::Zero,
+            1 => LeadBytes::One,
+            _ => LeadBytes::Two,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            LeadBytes::Zero => 0,
+            LeadBytes::One => 1,
+            LeadBytes::Two => 2,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum SmallVariableLengthCode {
     HPKEBaseCipher,
     HPKEAuthCipher,
     // String Base64 Only
     Base64String,
+    // Raw bytes, also used for UTF-8 strings
+    Bytes,
 }
 
 impl Display for SmallVariableLengthCode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(match self {
             SmallVariableLengthCode::Base64String => "A",
+            SmallVariableLengthCode::Bytes => "B",
             SmallVariableLengthCode::HPKEBaseCipher => "F",
             SmallVariableLengthCode::HPKEAuthCipher => "G",
         })
@@ -114,6 +150,7 @@ impl FromStr for SmallVariableLengthCode {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "A" => Ok(SmallVariableLengthCode::Base64String),
+            "B" => Ok(SmallVariableLengthCode::Bytes),
             "F" => Ok(SmallVariableLengthCode::HPKEBaseCipher),
             "G" => Ok(SmallVariableLengthCode::HPKEAuthCipher),
             _ => Err(Error::UnknownCodeError),
@@ -125,11 +162,28 @@ impl FromStr for SmallVariableLengthCode {
 pub enum LargeVariableLengthCode {
     HPKEBaseCipher,
     HPKEAuthCipher,
+    // String Base64 Only
+    Base64String,
+    // Raw bytes, also used for UTF-8 strings
+    Bytes,
+}
+
+impl From<SmallVariableLengthCode> for LargeVariableLengthCode {
+    fn from(code: SmallVariableLengthCode) -> Self {
+        match code {
+            SmallVariableLengthCode::HPKEBaseCipher => LargeVariableLengthCode::HPKEBaseCipher,
+            SmallVariableLengthCode::HPKEAuthCipher => LargeVariableLengthCode::HPKEAuthCipher,
+            SmallVariableLengthCode::Base64String => LargeVariableLengthCode::Base64String,
+            SmallVariableLengthCode::Bytes => LargeVariableLengthCode::Bytes,
+        }
+    }
 }
 
 impl Display for LargeVariableLengthCode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(match self {
+            LargeVariableLengthCode::Base64String => "AAA",
+            LargeVariableLengthCode::Bytes => "AAB",
             LargeVariableLengthCode::HPKEBaseCipher => "AAF",
             LargeVariableLengthCode::HPKEAuthCipher => "AAG",
         })
@@ -141,6 +195,8 @@ impl FromStr for LargeVariableLengthCode {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
+            "AAA" => Ok(LargeVariableLengthCode::Base64String),
+            "AAB" => Ok(LargeVariableLengthCode::Bytes),
             "AAF" => Ok(LargeVariableLengthCode::HPKEBaseCipher),
             "AAG" => Ok(LargeVariableLengthCode::HPKEAuthCipher),
             _ => Err(Error::UnknownCodeError),
@@ -158,6 +214,8 @@ impl VariableLengthPrimitive {
     pub fn new(code: VariableLengthCode, value: Vec<u8>) -> Self {
         VariableLengthPrimitive { code, value }
     }
+    /// Creates primitive of Base64 text. Picks small or large code form
+    /// depending on text length.
     pub fn create_from_str(
         code: SmallVariableLengthCode,
         encoded_value: &str,
@@ -177,41 +235,37 @@ impl VariableLengthPrimitive {
             _ => panic!("Invalid leading bytes length"),
         };
 
-        let quadlets = if len_modulo == 0 {
-            encoded_value.len() / 4
-        } else {
-            (encoded_value.len() + 4 - len_modulo) / 4
-        };
+        let quadlets = encoded_value.len().div_ceil(4);
 
         VariableLengthPrimitive {
-            code: VariableLengthCode::Small {
-                lb,
-                code,
-                length: quadlets as u16,
-            },
+            code: VariableLengthCode::new(lb, code, quadlets),
             value,
         }
     }
+
+    /// Creates primitive of raw bytes. Picks small or large code form
+    /// depending on value size.
     pub fn create_from_bytes(
         code: SmallVariableLengthCode,
         value: Vec<u8>,
     ) -> VariableLengthPrimitive {
-        let lead_bytes = 3 - (value.len() % 3);
-        let lb = match lead_bytes {
-            0 => LeadBytes::Zero,
-            1 => LeadBytes::One,
-            2 => LeadBytes::Two,
-            _ => todo!(),
-        };
-        let triplets = (value.len() + lead_bytes) / 3;
+        let lb = LeadBytes::for_raw_size(value.len());
+        let triplets = (value.len() + lb.size()) / 3;
 
-        let code = VariableLengthCode::Small {
-            lb,
-            code,
-            length: triplets as u16,
-        };
+        VariableLengthPrimitive {
+            code: VariableLengthCode::new(lb, code, triplets),
+            value,
+        }
+    }
 
-        VariableLengthPrimitive { code, value }
+    /// Creates primitive of raw bytes, encoded with bytes code.
+    pub fn from_bytes(value: Vec<u8>) -> VariableLengthPrimitive {
+        Self::create_from_bytes(SmallVariableLengthCode::Bytes, value)
+    }
+
+    /// Creates primitive of UTF-8 string, encoded with bytes code.
+    pub fn from_string(text: &str) -> VariableLengthPrimitive {
+        Self::from_bytes(text.as_bytes().to_vec())
     }
 
     pub fn code(&self) -> &VariableLengthCode {
@@ -222,12 +276,28 @@ impl VariableLengthPrimitive {
         &self.value
     }
 
+    /// Returns value as UTF-8 string.
+    pub fn as_str(&self) -> Result<&str, Error> {
+        std::str::from_utf8(&self.value).map_err(|_| Error::Utf8DecodingError)
+    }
+
     pub fn to_cesr(&self) -> String {
         let encoded_value = from_bytes_to_text(&self.value);
         format!("{}{}", self.code.to_cesr(), encoded_value)
     }
 }
 
+impl Encode for VariableLengthPrimitive {
+    fn encoded_len(&self) -> usize {
+        self.code.encoded_len() + b64_len(self.value.len())
+    }
+
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        self.code.write_cesr(writer)?;
+        write_b64(&self.value, 0, writer)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum VariableLengthCode {
     Small {
@@ -242,6 +312,24 @@ pub enum VariableLengthCode {
     },
 }
 impl VariableLengthCode {
+    /// Creates small code if quadlets fit into its count, large code
+    /// otherwise.
+    pub fn new(lb: LeadBytes, code: SmallVariableLengthCode, quadlets: usize) -> Self {
+        if quadlets <= MAX_SMALL_QUADLETS {
+            VariableLengthCode::Small {
+                lb,
+                code,
+                length: quadlets as u16,
+            }
+        } else {
+            VariableLengthCode::Large {
+                lb,
+                code: code.into(),
+                length: quadlets as u32,
+            }
+        }
+    }
+
     pub fn quadlets(&self) -> u32 {
         match self {
             VariableLengthCode::Small { length, .. } => *length as u32,
@@ -273,26 +361,64 @@ impl VariableLengthCode {
                     LeadBytes::One => VariableCodeSelector::LongOneLeadBytes,
                     LeadBytes::Two => VariableCodeSelector::LongTwoLeadBytes,
                 };
-                let quadlets = adjust_with_num(*length as u16, 4);
+                let quadlets = u64_to_b64(*length as u64, 4);
                 format!("{}{}{}", selector, code, quadlets)
             }
         }
     }
 }
 
-pub fn variable_length_code(s: &str) -> nom::IResult<&str, VariableLengthCode> {
+impl Encode for VariableLengthCode {
+    fn encoded_len(&self) -> usize {
+        match self {
+            VariableLengthCode::Small { .. } => 4,
+            VariableLengthCode::Large { .. } => 8,
+        }
+    }
+
+    fn write_cesr<W: fmt::Write + ?Sized>(&self, writer: &mut W) -> fmt::Result {
+        match self {
+            VariableLengthCode::Small { lb, code, length } => {
+                let selector = match lb {
+                    LeadBytes::Zero => VariableCodeSelector::ShortZeroLeadBytes,
+                    LeadBytes::One => VariableCodeSelector::ShortOneLeadBytes,
+                    LeadBytes::Two => VariableCodeSelector::ShortTwoLeadBytes,
+                };
+                write!(writer, "{}{}", selector, code)?;
+                write_b64_num(*length as u64, 2, writer)
+            }
+            VariableLengthCode::Large { lb, code, length } => {
+                let selector = match lb {
+                    LeadBytes::Zero => VariableCodeSelector::LongZeroLeadBytes,
+                    LeadBytes::One => VariableCodeSelector::LongOneLeadBytes,
+                    LeadBytes::Two => VariableCodeSelector::LongTwoLeadBytes,
+                };
+                write!(writer, "{}{}", selector, code)?;
+                write_b64_num(*length as u64, 4, writer)
+            }
+        }
+    }
+}
+
+pub fn variable_length_code(s: &str) -> IResult<&str, VariableLengthCode> {
     let (more, selector) = take(1u8)(s)?;
-    let selector: VariableCodeSelector = selector.parse().unwrap();
+    let Ok(selector) = selector.parse::<VariableCodeSelector>() else {
+        return Err(nom::Err::Error(make_error(s, ErrorKind::IsNot)));
+    };
 
     let (more, code_type) = take(selector.type_len())(more)?;
     let (more, data_len) = take(selector.counter_len())(more)?;
-    let len = b64_to_num(data_len).unwrap();
+    let Ok(len) = b64_to_u64(data_len) else {
+        return Err(nom::Err::Error(make_error(s, ErrorKind::IsNot)));
+    };
 
     match selector {
         VariableCodeSelector::ShortZeroLeadBytes
         | VariableCodeSelector::ShortOneLeadBytes
         | VariableCodeSelector::ShortTwoLeadBytes => {
-            let code: SmallVariableLengthCode = code_type.parse().unwrap();
+            let Ok(code) = code_type.parse::<SmallVariableLengthCode>() else {
+                return Err(nom::Err::Error(make_error(s, ErrorKind::IsNot)));
+            };
             Ok((
                 more,
                 VariableLengthCode::Small {
@@ -305,7 +431,9 @@ pub fn variable_length_code(s: &str) -> nom::IResult<&str, VariableLengthCode> {
         VariableCodeSelector::LongZeroLeadBytes
         | VariableCodeSelector::LongOneLeadBytes
         | VariableCodeSelector::LongTwoLeadBytes => {
-            let code: LargeVariableLengthCode = code_type.parse().unwrap();
+            let Ok(code) = code_type.parse::<LargeVariableLengthCode>() else {
+                return Err(nom::Err::Error(make_error(s, ErrorKind::IsNot)));
+            };
             Ok((
                 more,
                 VariableLengthCode::Large {
@@ -318,16 +446,45 @@ pub fn variable_length_code(s: &str) -> nom::IResult<&str, VariableLengthCode> {
     }
 }
 
-pub fn variable_length_value(input: &str) -> nom::IResult<&str, VariableLengthPrimitive> {
+/// Returns code and value sizes of variable length primitive with code
+/// unknown to this crate. Sizes follow from selector alone, because soft part
+/// of code counts value quadlets.
+pub(crate) fn unknown_variable_length_size(s: &str) -> Option<(usize, usize)> {
+    if variable_length_code(s).is_ok() {
+        return None;
+    }
+    variable_length_size(s)
+}
+
+/// Returns code and value sizes of variable length primitive, without
+/// decoding it.
+pub(crate) fn variable_length_size(s: &str) -> Option<(usize, usize)> {
+    let selector: VariableCodeSelector = s.get(..1)?.parse().ok()?;
+    let count_start = 1 + selector.type_len() as usize;
+    let code_len = count_start + selector.counter_len() as usize;
+    let quadlets = b64_to_u64(s.get(count_start..code_len)?).ok()?;
+    Some((code_len, quadlets as usize * 4))
+}
+
+pub fn variable_length_value(input: &str) -> IResult<&str, VariableLengthPrimitive> {
+    variable_length_value_with(input, true)
+}
+
+/// Parses variable length primitive, accepting text with non-zero lead bytes
+/// unless `strict`.
+pub(crate) fn variable_length_value_with(
+    input: &str,
+    strict: bool,
+) -> IResult<&str, VariableLengthPrimitive> {
     let (rest, code) = variable_length_code(input)?;
     let (rest, value) = take(code.quadlets() * 4)(rest)?;
-    let bytes = from_text_to_bytes(value).unwrap();
-    let lb = match code.lead_bytes() {
-        LeadBytes::Zero => 0,
-        LeadBytes::One => 1,
-        LeadBytes::Two => 2,
+    let value = match decode_lead(value, 0, code.lead_bytes().size(), strict) {
+        Ok(value) => value,
+        Err(Error::NonZeroPadBitsError(_)) => {
+            return Err(StreamError::failure(input, StreamErrorKind::NonZeroPadBits))
+        }
+        Err(_) => return Err(nom::Err::Error(make_error(input, ErrorKind::IsNot))),
     };
-    let value = bytes[lb..].to_vec();
     Ok((rest, VariableLengthPrimitive::new(code, value)))
 }
 
@@ -434,6 +591,51 @@ pub fn test_to_value() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+pub fn test_bytes_and_string() {
+    use crate::value::{parse_value, Value};
+
+    // Aligned value needs no lead bytes
+    let primitive = VariableLengthPrimitive::from_bytes(vec![1, 2, 3]);
+    assert_eq!(primitive.code().lead_bytes(), LeadBytes::Zero);
+    assert_eq!(primitive.to_cesr(), "4BABAQID");
+
+    let primitive = VariableLengthPrimitive::from_string("hello");
+    let cesr = primitive.to_cesr();
+    assert_eq!(cesr, "5BACAGhlbGxv");
+    let (rest, parsed) = variable_length_value(&cesr).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(parsed.as_str().unwrap(), "hello");
+
+    // Value too long for small code
+    let long_text = "a".repeat(3 * (MAX_SMALL_QUADLETS + 1));
+    let primitive = VariableLengthPrimitive::from_string(&long_text);
+    assert_eq!(
+        primitive.code(),
+        &VariableLengthCode::Large {
+            lb: LeadBytes::Zero,
+            code: LargeVariableLengthCode::Bytes,
+            length: 4096
+        }
+    );
+    let cesr = primitive.to_cesr();
+    assert!(cesr.starts_with("7AABABAA"));
+    let (rest, parsed) = parse_value(&cesr).unwrap();
+    assert!(rest.is_empty());
+    match parsed {
+        Value::VariableLengthRaw(parsed) => assert_eq!(parsed.as_str().unwrap(), long_text),
+        _ => panic!("Unexpected value type"),
+    };
+
+    let long_b64 = "A".repeat(4 * (MAX_SMALL_QUADLETS + 1));
+    let primitive =
+        VariableLengthPrimitive::create_from_str(SmallVariableLengthCode::Base64String, &long_b64);
+    assert!(primitive.to_cesr().starts_with("7AAAABAA"));
+
+    // Unknown code is an error, not a panic
+    assert!(variable_length_code("4ZAB").is_err());
+}
+
 #[test]
 fn test_base64_string() {
     use crate::value::{parse_value, Value};
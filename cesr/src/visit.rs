@@ -0,0 +1,380 @@
+//! Traversal of parsed values. `Visitor` walks value tree by reference and
+//! `VisitorMut` walks it mutably, both recursively by default: into
+//! universal groups, items of specific and custom groups, and groups nested
+//! in pathed material. Overridden method may call the matching `walk_*`
+//! function to keep walking below the value it handles.
+
+use crate::{
+    encode::Encode,
+    group::Group,
+    payload::Payload,
+    primitives::{
+        codes::PrimitiveCode, FixedSizeCode, Identifier, IndexedSignature, Matter, Primitive,
+    },
+    registry::CustomGroup,
+    universal_codes::UniversalGroupCode,
+    value::Value,
+};
+
+/// Visits values by reference. Primitives are visited also within items of
+/// specific groups, as code and raw value, so e.g. digests can be collected
+/// from seals and receipts. Serial numbers, timestamps and labels of group
+/// items aren't visited.
+pub trait Visitor {
+    fn visit_value(&mut self, value: &Value) {
+        walk_value(self, value)
+    }
+
+    fn visit_payload(&mut self, _payload: &Payload) {}
+
+    fn visit_primitive(&mut self, _code: &PrimitiveCode, _raw: &[u8]) {}
+
+    fn visit_universal_group(&mut self, _code: &UniversalGroupCode, values: &[Value]) {
+        walk_values(self, values)
+    }
+
+    fn visit_group(&mut self, group: &Group) {
+        walk_group(self, group)
+    }
+
+    fn visit_custom_group(&mut self, group: &CustomGroup) {
+        walk_custom_group(self, group)
+    }
+}
+
+/// Visits values of stream, or of group, in order.
+pub fn walk_values<V: Visitor + ?Sized>(visitor: &mut V, values: &[Value]) {
+    values.iter().for_each(|value| visitor.visit_value(value))
+}
+
+/// Calls visitor method of value kind. Values without content of their own
+/// to visit, like tags or `Null`, are visited only by `visit_value`.
+pub fn walk_value<V: Visitor + ?Sized>(visitor: &mut V, value: &Value) {
+    match value {
+        Value::Payload(payload) => visitor.visit_payload(payload),
+        Value::Primitive(code, raw) => visitor.visit_primitive(code, raw),
+        Value::UniversalGroup(code, values) => visitor.visit_universal_group(code, values),
+        Value::SpecificGroup(group) => visitor.visit_group(group),
+        Value::CustomGroup(group) => visitor.visit_custom_group(group),
+        _ => (),
+    }
+}
+
+/// Visits primitives of group items, nested groups of pathed material and
+/// values of TSP payload.
+pub fn walk_group<V: Visitor + ?Sized>(visitor: &mut V, group: &Group) {
+    match group {
+        Group::IndexedControllerSignatures(signatures)
+        | Group::IndexedWitnessSignatures(signatures) => {
+            signatures.iter().for_each(|sig| signature(visitor, sig))
+        }
+        Group::NontransReceiptCouples(couples) => {
+            for (key, sig) in couples {
+                primitive(visitor, key);
+                primitive(visitor, sig);
+            }
+        }
+        Group::TransReceiptQuadruples(quadruples) => {
+            for (id, _, digest, sig) in quadruples {
+                identifier(visitor, id);
+                primitive(visitor, digest);
+                signature(visitor, sig);
+            }
+        }
+        Group::SourceSealCouples(seals) => seals
+            .iter()
+            .for_each(|seal| primitive(visitor, &seal.digest)),
+        Group::FirstSeenReplyCouples(_) => (),
+        Group::DigestSeals(seals) => seals
+            .iter()
+            .for_each(|seal| primitive(visitor, &seal.digest)),
+        Group::MerkleRootSeals(seals) => seals
+            .iter()
+            .for_each(|seal| primitive(visitor, &seal.root_digest)),
+        Group::AnchoringSeals(seals) => {
+            for seal in seals {
+                identifier(visitor, &seal.identifier);
+                primitive(visitor, &seal.digest);
+            }
+        }
+        Group::LastEstablishmentSeals(seals) => seals
+            .iter()
+            .for_each(|seal| identifier(visitor, &seal.identifier)),
+        Group::RegistrarSeals(seals) => {
+            for seal in seals {
+                identifier(visitor, &seal.identifier);
+                primitive(visitor, &seal.digest);
+            }
+        }
+        Group::TransIndexedSigGroups(groups) => {
+            for (id, _, digest, signatures) in groups {
+                identifier(visitor, id);
+                primitive(visitor, digest);
+                signatures.iter().for_each(|sig| signature(visitor, sig));
+            }
+        }
+        Group::TransLastIdxSigGroups(groups) => {
+            for (id, signatures) in groups {
+                identifier(visitor, id);
+                signatures.iter().for_each(|sig| signature(visitor, sig));
+            }
+        }
+        #[cfg(feature = "cesr-proof")]
+        Group::PathedMaterialQuadruplet(_, groups) => {
+            groups.iter().for_each(|group| visitor.visit_group(group))
+        }
+        Group::TSPPayload(values) => walk_values(visitor, values),
+        Group::BlindedStates(states) => {
+            for state in states {
+                primitive(visitor, &state.digest);
+                primitive(visitor, &state.uuid);
+                if let Some(acdc) = &state.acdc {
+                    primitive(visitor, acdc);
+                }
+            }
+        }
+        Group::BoundStates(states) => {
+            for state in states {
+                primitive(visitor, &state.digest);
+                primitive(visitor, &state.uuid);
+                if let Some(acdc) = &state.acdc {
+                    primitive(visitor, acdc);
+                }
+                primitive(visitor, &state.bound_digest);
+            }
+        }
+    }
+}
+
+/// Visits values of custom group items.
+pub fn walk_custom_group<V: Visitor + ?Sized>(visitor: &mut V, group: &CustomGroup) {
+    group
+        .items()
+        .iter()
+        .for_each(|item| walk_values(visitor, item))
+}
+
+fn primitive<V: Visitor + ?Sized, C: FixedSizeCode>(visitor: &mut V, primitive: &Primitive<C>) {
+    visitor.visit_primitive(&primitive.code().clone().into(), primitive.raw())
+}
+
+fn signature<V: Visitor + ?Sized>(visitor: &mut V, signature: &IndexedSignature) {
+    let code = PrimitiveCode::IndexedSignature(*signature.code());
+    visitor.visit_primitive(&code, signature.raw())
+}
+
+fn identifier<V: Visitor + ?Sized>(visitor: &mut V, (code, raw): &Identifier) {
+    visitor.visit_primitive(&code.clone().into(), raw)
+}
+
+/// Visits values mutably. Values can be replaced in `visit_value_mut`, and
+/// removed or added in `visit_values_mut` and `visit_groups_mut`. Quadlet
+/// counts of universal groups are recomputed after their content is
+/// walked, so groups are encoded with counts of their new content.
+pub trait VisitorMut {
+    /// Visits values of stream, or of group.
+    fn visit_values_mut(&mut self, values: &mut Vec<Value>) {
+        walk_values_mut(self, values)
+    }
+
+    fn visit_value_mut(&mut self, value: &mut Value) {
+        walk_value_mut(self, value)
+    }
+
+    fn visit_universal_group_mut(
+        &mut self,
+        _code: &mut UniversalGroupCode,
+        values: &mut Vec<Value>,
+    ) {
+        self.visit_values_mut(values)
+    }
+
+    fn visit_group_mut(&mut self, group: &mut Group) {
+        walk_group_mut(self, group)
+    }
+
+    /// Visits groups nested in pathed material.
+    fn visit_groups_mut(&mut self, groups: &mut Vec<Group>) {
+        groups
+            .iter_mut()
+            .for_each(|group| self.visit_group_mut(group))
+    }
+
+    fn visit_custom_group_mut(&mut self, group: &mut CustomGroup) {
+        group
+            .items_mut()
+            .iter_mut()
+            .for_each(|item| self.visit_values_mut(item))
+    }
+}
+
+pub fn walk_values_mut<V: VisitorMut + ?Sized>(visitor: &mut V, values: &mut [Value]) {
+    values
+        .iter_mut()
+        .for_each(|value| visitor.visit_value_mut(value))
+}
+
+/// Walks groups of value, and recomputes quadlet count of universal group
+/// after its content was visited.
+pub fn walk_value_mut<V: VisitorMut + ?Sized>(visitor: &mut V, value: &mut Value) {
+    match value {
+        Value::UniversalGroup(code, values) => {
+            visitor.visit_universal_group_mut(code, values);
+            recount(code, values);
+        }
+        Value::SpecificGroup(group) => visitor.visit_group_mut(group),
+        Value::CustomGroup(group) => visitor.visit_custom_group_mut(group),
+        _ => (),
+    }
+}
+
+/// Walks groups nested in pathed material and values of TSP payload. Items
+/// of other groups have no nested values.
+pub fn walk_group_mut<V: VisitorMut + ?Sized>(visitor: &mut V, group: &mut Group) {
+    match group {
+        #[cfg(feature = "cesr-proof")]
+        Group::PathedMaterialQuadruplet(_, groups) => visitor.visit_groups_mut(groups),
+        Group::TSPPayload(values) => visitor.visit_values_mut(values),
+        _ => (),
+    }
+}
+
+/// Sets quadlet count of universal group code to size of its content.
+/// Count that doesn't fit into code is left to fail when group is encoded.
+fn recount(code: &mut UniversalGroupCode, values: &[Value]) {
+    let content_len: usize = values.iter().map(Encode::encoded_len).sum();
+    let Ok(count) = u32::try_from(content_len / 4) else {
+        return;
+    };
+    match code {
+        UniversalGroupCode::OverrideAllowed { quadlets, .. }
+        | UniversalGroupCode::OverrideNotAllowed { quadlets, .. } => *quadlets = count,
+        UniversalGroupCode::Genus(_) => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        encode::Encode,
+        group::Group,
+        parse_all,
+        primitives::codes::{self_addressing::SelfAddressing, PrimitiveCode},
+        universal_codes::{CustomizableCode, UniversalGroupCode},
+        value::Value,
+        visit::{walk_value, walk_values_mut, Visitor, VisitorMut},
+    };
+
+    const SIGNATURE: &str =
+        "AABB5IVZOhEfcH4TBQgOCyMgyQrJujtBBjT8K_zTPk0-FLMtTZuBgXV7jnLw6fDe6FWtzshh2HGCL_H_j4i1b9kF";
+    const DIGEST: &str = "EJtQndkvwnMpVGE5oVVbLWSCm-jLviGw1AOOkzBvNwsS";
+    const PREFIX: &str = "EKC8085pwSwzLwUGzh-HrEoFDwZnCJq27bVp5atdMT9o";
+
+    /// Attachments group with controller and witness signatures, and
+    /// anchoring seal.
+    fn attachments() -> String {
+        let content = [
+            "-KAB",
+            SIGNATURE,
+            "-LAB",
+            SIGNATURE,
+            "-SAB",
+            PREFIX,
+            "0AAAAAAAAAAAAAAAAAAAAAAA",
+            DIGEST,
+        ]
+        .concat();
+        ["-CBL", &content].concat()
+    }
+
+    #[derive(Default)]
+    struct Digests(Vec<Vec<u8>>);
+
+    impl Visitor for Digests {
+        fn visit_primitive(&mut self, code: &PrimitiveCode, raw: &[u8]) {
+            if let PrimitiveCode::SelfAddressing(SelfAddressing::Blake3_256) = code {
+                self.0.push(raw.to_vec());
+            }
+        }
+    }
+
+    #[test]
+    fn test_visitor() {
+        let stream = [DIGEST, &attachments()].concat();
+        let (_, values) = parse_all(&stream).unwrap();
+        let mut digests = Digests::default();
+        values.iter().for_each(|value| digests.visit_value(value));
+        // Digest value, and identifier and digest of the seal
+        assert_eq!(digests.0.len(), 3);
+
+        // Overridden method walks further on its own
+        struct Groups(usize);
+        impl Visitor for Groups {
+            fn visit_value(&mut self, value: &Value) {
+                if matches!(value, Value::UniversalGroup(..) | Value::SpecificGroup(_)) {
+                    self.0 += 1;
+                }
+                walk_value(self, value)
+            }
+        }
+        let mut groups = Groups(0);
+        values.iter().for_each(|value| groups.visit_value(value));
+        assert_eq!(groups.0, 4);
+    }
+
+    #[test]
+    fn test_visitor_mut() {
+        let (_, mut values) = parse_all(&attachments()).unwrap();
+
+        // Witness signatures are removed, and controller signatures replaced
+        struct Strip;
+        impl VisitorMut for Strip {
+            fn visit_values_mut(&mut self, values: &mut Vec<Value>) {
+                values.retain(|value| {
+                    !matches!(
+                        value,
+                        Value::SpecificGroup(Group::IndexedWitnessSignatures(_))
+                    )
+                });
+                walk_values_mut(self, values)
+            }
+
+            fn visit_group_mut(&mut self, group: &mut Group) {
+                if let Group::IndexedControllerSignatures(signatures) = group {
+                    signatures.truncate(0);
+                }
+            }
+        }
+        Strip.visit_values_mut(&mut values);
+
+        let content = ["-KAA-SAB", PREFIX, "0AAAAAAAAAAAAAAAAAAAAAAA", DIGEST].concat();
+        let expected = ["-CAe", &content].concat();
+        assert_eq!(values[0].to_cesr_string(), expected);
+        assert!(matches!(
+            values[0],
+            Value::UniversalGroup(UniversalGroupCode::OverrideAllowed { quadlets: 30, .. }, _)
+        ));
+
+        // Code of group can be rewritten as well
+        struct Pipeline;
+        impl VisitorMut for Pipeline {
+            fn visit_universal_group_mut(
+                &mut self,
+                code: &mut UniversalGroupCode,
+                values: &mut Vec<Value>,
+            ) {
+                values.push(Value::Null);
+                *code = UniversalGroupCode::OverrideAllowed {
+                    code: CustomizableCode::GenericPipeline,
+                    quadlets: 0,
+                };
+            }
+        }
+        Pipeline.visit_values_mut(&mut values);
+        assert_eq!(
+            values[0].to_cesr_string(),
+            ["-AAf", &content, "1AAK"].concat()
+        );
+        assert_eq!(values[0].encoded_len(), 4 + 31 * 4);
+    }
+}
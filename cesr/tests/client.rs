@@ -5,7 +5,10 @@ pub mod test {
         group::Group,
         parse_all, parse_and_send,
         payload::Payload,
-        primitives::codes::{basic::Basic, self_signing::SelfSigning},
+        primitives::{
+            codes::{basic::Basic, self_signing::SelfSigning},
+            PublicKey,
+        },
         value::Value,
     };
 
@@ -26,7 +29,9 @@ pub mod test {
         let Value::SpecificGroup(Group::NontransReceiptCouples(couples)) = &values[1] else {
             unreachable!()
         };
-        let ((key_code, pub_key), (sig_code, signature)) = couples[0].clone();
+        let (public_key, signature) = couples[0].clone();
+        let (key_code, pub_key) = public_key.into_parts();
+        let (sig_code, signature) = signature.into_parts();
 
         assert_eq!(key_code, Basic::Ed25519Nontrans);
         assert_eq!(
@@ -54,8 +59,11 @@ pub mod test {
         let message = br#"{"name":"John","surname":"Doe"}"#;
         let ed_signature: Signature = signing_key.sign(message);
 
-        let public_key = (Basic::Ed25519Nontrans, verifying_key.to_bytes().to_vec());
-        let signature = (SelfSigning::Ed25519Sha512, ed_signature.to_bytes().to_vec());
+        let public_key = PublicKey::new(Basic::Ed25519Nontrans, verifying_key.to_bytes().to_vec())?;
+        let signature = cesrox::primitives::Signature::new(
+            SelfSigning::Ed25519Sha512,
+            ed_signature.to_bytes().to_vec(),
+        )?;
 
         let attachment =
             Group::NontransReceiptCouples(vec![(public_key.clone(), signature.clone())]);